@@ -0,0 +1,52 @@
+use std::{collections::HashMap, time::Instant};
+
+use rusty_bells::{MethodFilter, PermutationGraph, SearchOptions, StrongNodeVector};
+
+// Five bell half methods are out of reach to pair every way, even filtered
+fn main() {
+  let graph = PermutationGraph::new(4);
+
+  run_pairing("4 bells", graph.get_valid_half_methods());
+  run_pairing(
+    "4 bells, right place",
+    graph.get_half_methods(&SearchOptions::new().filter(MethodFilter::RightPlace)),
+  );
+}
+
+// Pairs the same half methods with and without grouping them by end row
+fn run_pairing(label: &str, half_methods: Vec<StrongNodeVector>) {
+  let half_method_count = half_methods.len();
+  let (all_pairs, grouped_pairs) = count_pairs(&half_methods);
+  println!(
+    "{label}: grouping by end row leaves {grouped_pairs} of {all_pairs} pairs of half methods to compare"
+  );
+
+  for group_by_end_row in [false, true] {
+    let options = SearchOptions::new().group_by_end_row(group_by_end_row);
+    let start = Instant::now();
+    let method_count = PermutationGraph::pair_half_methods(half_methods.clone(), &options).count();
+
+    println!(
+      "{label}, {}: paired {half_method_count} half methods into {method_count} methods in {:?}",
+      match group_by_end_row {
+        true => "grouped",
+        false => "ungrouped",
+      },
+      start.elapsed()
+    );
+  }
+}
+
+fn count_pairs(half_methods: &[StrongNodeVector]) -> (usize, usize) {
+  let mut group_sizes: HashMap<&Vec<u8>, usize> = HashMap::new();
+  for half_method in half_methods {
+    let end_row = half_method.last().expect("Half methods are never empty");
+    *group_sizes.entry(end_row.get_permutation()).or_default() += 1;
+  }
+
+  let pairs = |size: usize| size * size.saturating_sub(1) / 2;
+  (
+    pairs(half_methods.len()),
+    group_sizes.values().map(|&size| pairs(size)).sum(),
+  )
+}
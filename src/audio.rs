@@ -0,0 +1,330 @@
+//! Ringing rows as the sound of tower bells, written as WAV audio.
+//!
+//! Each bell is modelled by its five main partials, the hum, prime, tierce,
+//! quint and nominal, each dying away at its own rate. The tenor sounds the
+//! key note and the other bells climb the scale above it. Bells strike as
+//! the [`Timing`] sets.
+
+use std::{
+  error::Error,
+  f64::consts::TAU,
+  fmt,
+  io::{Result, Write},
+};
+
+use crate::timing::Timing;
+
+const MAJOR_STEPS: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+const MINOR_STEPS: [i32; 7] = [0, 2, 3, 5, 7, 8, 10];
+const NOTE_NAMES: [&str; 7] = ["C", "D", "E", "F", "G", "A", "B"];
+const NOTE_SEMITONES: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+const A4_FREQUENCY: f64 = 440.0;
+const A4_SEMITONES: i32 = 9;
+
+/// Frequency as a fraction of the nominal, loudness and seconds to die
+/// away to a third, for the hum, prime, tierce, quint and nominal.
+const PARTIALS: [(f64, f64, f64); 5] = [
+  (0.25, 0.5, 1.8),
+  (0.5, 0.4, 1.2),
+  (0.6, 0.35, 0.8),
+  (0.75, 0.2, 0.6),
+  (1.0, 0.8, 0.7),
+];
+const RING_SECONDS: f64 = 1.5;
+const PEAK_AMPLITUDE: f64 = 0.9;
+
+#[derive(Debug, PartialEq)]
+pub enum AudioError {
+  UnknownNote(String),
+}
+
+impl fmt::Display for AudioError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      AudioError::UnknownNote(note) => write!(f, "unknown note '{note}'"),
+    }
+  }
+}
+
+impl Error for AudioError {}
+
+/// The scale the bells are tuned to, up from the tenor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scale {
+  Major,
+  Minor,
+}
+
+impl Scale {
+  /// Semitones from the tenor up to a bell, the treble being the highest.
+  pub fn get_semitones(&self, bell: u8, bells_amount: usize) -> i32 {
+    let steps = match self {
+      Scale::Major => MAJOR_STEPS,
+      Scale::Minor => MINOR_STEPS,
+    };
+    let degree = bells_amount.saturating_sub(bell as usize);
+
+    (degree / steps.len()) as i32 * 12 + steps[degree % steps.len()]
+  }
+}
+
+/// Settings for the sound and timing of the bells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioOptions {
+  key: f64,
+  scale: Scale,
+  timing: Timing,
+  sample_rate: u32,
+}
+
+impl AudioOptions {
+  /// A tenor in C, a major scale, the default [`Timing`] and 22050
+  /// samples a second.
+  pub fn new() -> AudioOptions {
+    AudioOptions {
+      key: parse_note("C3").expect("C3 is a note"),
+      scale: Scale::Major,
+      timing: Timing::new(),
+      sample_rate: 22050,
+    }
+  }
+
+  /// The nominal frequency of the tenor, in hertz.
+  pub fn key(mut self, frequency: f64) -> Self {
+    if frequency <= 0.0 {
+      panic!("The key must have a positive frequency");
+    }
+
+    self.key = frequency;
+    self
+  }
+
+  pub fn scale(mut self, scale: Scale) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  pub fn timing(mut self, timing: Timing) -> Self {
+    self.timing = timing;
+    self
+  }
+
+  pub fn sample_rate(mut self, samples_per_second: u32) -> Self {
+    if samples_per_second == 0 {
+      panic!("The sample rate must be positive");
+    }
+
+    self.sample_rate = samples_per_second;
+    self
+  }
+
+  pub fn get_key(&self) -> f64 {
+    self.key
+  }
+
+  pub fn get_timing(&self) -> &Timing {
+    &self.timing
+  }
+
+  pub fn get_sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  /// The nominal frequency of a bell, the treble being the highest.
+  pub fn get_frequency(&self, bell: u8, bells_amount: usize) -> f64 {
+    let semitones = self.scale.get_semitones(bell, bells_amount);
+    self.key * 2f64.powf(semitones as f64 / 12.0)
+  }
+}
+
+impl Default for AudioOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// The frequency of a note such as `C`, `F#4` or `Bb2`, in the 4th octave
+/// unless one is given.
+pub fn parse_note(note: &str) -> std::result::Result<f64, AudioError> {
+  let unknown = || AudioError::UnknownNote(note.to_string());
+  let mut symbols = note.trim().chars().peekable();
+
+  let letter = symbols.next().ok_or_else(unknown)?.to_ascii_uppercase();
+  let mut semitones = NOTE_NAMES
+    .iter()
+    .position(|name| name.starts_with(letter))
+    .map(|index| NOTE_SEMITONES[index])
+    .ok_or_else(unknown)?;
+
+  match symbols.peek() {
+    Some('#') => semitones += 1,
+    Some('b') => semitones -= 1,
+    _ => {}
+  }
+  if matches!(symbols.peek(), Some('#' | 'b')) {
+    symbols.next();
+  }
+
+  let octave: String = symbols.collect();
+  let octave: i32 = if octave.is_empty() {
+    4
+  } else {
+    octave.parse().map_err(|_| unknown())?
+  };
+
+  let from_a4 = (octave - 4) * 12 + semitones - A4_SEMITONES;
+  let frequency = A4_FREQUENCY * 2f64.powf(from_a4 as f64 / 12.0);
+  // Octaves far enough out can't be held as a frequency at all
+  if frequency.is_normal() {
+    Ok(frequency)
+  } else {
+    Err(unknown())
+  }
+}
+
+/// The sound of the rows being rung, one sample per entry between -1 and 1.
+pub fn render_rows(rows: &[Vec<u8>], options: &AudioOptions) -> Vec<f32> {
+  let bells_amount = rows.first().map_or(0, Vec::len);
+  let sample_rate = options.sample_rate as f64;
+  let ring_samples = (RING_SECONDS * sample_rate) as usize;
+  let end_time = options.timing.get_strike_time(rows.len(), 0, bells_amount);
+  let mut samples = vec![0.0; (end_time * sample_rate) as usize + ring_samples];
+
+  for (row_index, row) in rows.iter().enumerate() {
+    for (place, &bell) in row.iter().enumerate() {
+      let start = (options
+        .timing
+        .get_strike_time(row_index, place, bells_amount)
+        * sample_rate) as usize;
+      let nominal = options.get_frequency(bell, bells_amount);
+
+      for (offset, sample) in samples[start..start + ring_samples].iter_mut().enumerate() {
+        let time = offset as f64 / sample_rate;
+        *sample += PARTIALS
+          .iter()
+          .map(|&(ratio, loudness, decay)| {
+            loudness * (-time / decay).exp() * (TAU * ratio * nominal * time).sin()
+          })
+          .sum::<f64>();
+      }
+    }
+  }
+
+  let peak = samples
+    .iter()
+    .fold(0.0, |peak: f64, sample| peak.max(sample.abs()));
+  let scale = if peak > 0.0 {
+    PEAK_AMPLITUDE / peak
+  } else {
+    0.0
+  };
+  samples
+    .iter()
+    .map(|sample| (sample * scale) as f32)
+    .collect()
+}
+
+/// Writes samples as a mono 16 bit PCM WAV file.
+pub fn write_wav(samples: &[f32], sample_rate: u32, out: &mut impl Write) -> Result<()> {
+  let data_size = samples.len() as u32 * 2;
+
+  out.write_all(b"RIFF")?;
+  out.write_all(&(36 + data_size).to_le_bytes())?;
+  out.write_all(b"WAVEfmt ")?;
+  out.write_all(&16u32.to_le_bytes())?;
+  out.write_all(&1u16.to_le_bytes())?;
+  out.write_all(&1u16.to_le_bytes())?;
+  out.write_all(&sample_rate.to_le_bytes())?;
+  out.write_all(&(sample_rate * 2).to_le_bytes())?;
+  out.write_all(&2u16.to_le_bytes())?;
+  out.write_all(&16u16.to_le_bytes())?;
+  out.write_all(b"data")?;
+  out.write_all(&data_size.to_le_bytes())?;
+
+  for sample in samples {
+    let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+    out.write_all(&sample.to_le_bytes())?;
+  }
+
+  Ok(())
+}
+
+/// Rings the rows and writes them as a WAV file.
+pub fn write_audio(rows: &[Vec<u8>], options: &AudioOptions, out: &mut impl Write) -> Result<()> {
+  write_wav(&render_rows(rows, options), options.sample_rate, out)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::test_helpers::assert_close;
+
+  #[test]
+  fn can_parse_notes() {
+    assert_close(parse_note("A").unwrap(), 440.0);
+    assert_close(parse_note("A5").unwrap(), 880.0);
+    assert_close(parse_note("c4").unwrap(), 261.6255653);
+    assert_close(parse_note("F#3").unwrap(), parse_note("Gb3").unwrap());
+    assert_eq!(
+      parse_note("H"),
+      Err(AudioError::UnknownNote("H".to_string()))
+    );
+    assert_eq!(
+      parse_note("Cx"),
+      Err(AudioError::UnknownNote("Cx".to_string()))
+    );
+    assert!(parse_note("").is_err());
+    assert!(parse_note("C2000").is_err());
+    assert!(parse_note("C-2000").is_err());
+  }
+
+  #[test]
+  fn tunes_bells_up_the_scale_from_the_tenor() {
+    let options = AudioOptions::new().key(100.0);
+    let minor = options.clone().scale(Scale::Minor);
+
+    assert_close(options.get_frequency(8, 8), 100.0);
+    assert_close(options.get_frequency(1, 8), 200.0);
+    assert_close(options.get_frequency(6, 8), 100.0 * 2f64.powf(4.0 / 12.0));
+    assert_close(minor.get_frequency(6, 8), 100.0 * 2f64.powf(3.0 / 12.0));
+  }
+
+  #[test]
+  fn renders_every_strike() {
+    let options = AudioOptions::new()
+      .timing(Timing::new().bell_gap(0.2))
+      .sample_rate(1000);
+    let samples = render_rows(&[vec![1, 2], vec![2, 1]], &options);
+
+    assert_eq!(samples.len(), 1000 + 1500);
+    assert!(samples.iter().all(|sample| sample.abs() <= 0.9 + 1e-6));
+    assert!(samples[600..700].iter().any(|sample| sample.abs() > 0.1));
+    assert!(
+      render_rows(&[], &options)
+        .iter()
+        .all(|&sample| sample == 0.0)
+    );
+  }
+
+  #[test]
+  fn writes_a_wav_header() {
+    let mut out = Vec::new();
+    write_wav(&[0.0, 1.0, -1.0], 8000, &mut out).unwrap();
+
+    assert_eq!(out.len(), 44 + 6);
+    assert_eq!(&out[0..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(out[4..8].try_into().unwrap()), 42);
+    assert_eq!(&out[8..16], b"WAVEfmt ");
+    assert_eq!(u32::from_le_bytes(out[24..28].try_into().unwrap()), 8000);
+    assert_eq!(&out[36..40], b"data");
+    assert_eq!(i16::from_le_bytes([out[46], out[47]]), i16::MAX);
+    assert_eq!(i16::from_le_bytes([out[48], out[49]]), -i16::MAX);
+  }
+
+  #[test]
+  #[should_panic(expected = "The sample rate must be positive")]
+  fn panics_without_a_sample_rate() {
+    AudioOptions::new().sample_rate(0);
+  }
+}
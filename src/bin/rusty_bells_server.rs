@@ -0,0 +1,24 @@
+use std::{error::Error, net::TcpListener};
+
+use clap::Parser;
+use rusty_bells::service;
+
+#[derive(Parser)]
+#[command(version)]
+#[command(about = "Answers method queries over HTTP with JSON")]
+#[command(long_about = None)]
+struct Args {
+  /// Address to listen on
+  #[arg(long, default_value = "127.0.0.1:8080")]
+  address: String,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let args = Args::parse();
+
+  let listener = TcpListener::bind(&args.address)?;
+  eprintln!("Listening on http://{}", listener.local_addr()?);
+  service::serve(listener)?;
+
+  Ok(())
+}
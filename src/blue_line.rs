@@ -0,0 +1,234 @@
+use std::{
+  fmt::Write as _,
+  io::{Result, Write},
+};
+
+use crate::place_notation::{bell_to_char, get_place_notation};
+
+const ROW_HEIGHT: usize = 18;
+const COLUMN_WIDTH: usize = 14;
+const MARGIN: usize = 12;
+const NOTATION_WIDTH: usize = 48;
+const TREBLE_COLOUR: &str = "#d62728";
+const WORKING_BELL_COLOUR: &str = "#1f4fd6";
+
+/// Settings for drawing a blue line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlueLineOptions {
+  working_bell: u8,
+  lead_length: Option<usize>,
+}
+
+impl BlueLineOptions {
+  /// Follows the 2, without lead end markers.
+  pub fn new() -> BlueLineOptions {
+    BlueLineOptions {
+      working_bell: 2,
+      lead_length: None,
+    }
+  }
+
+  /// The bell drawn in blue alongside the treble's red line.
+  pub fn working_bell(mut self, bell: u8) -> Self {
+    self.working_bell = bell;
+    self
+  }
+
+  /// Number of changes in a lead, marking a lead end after every lead.
+  pub fn lead_length(mut self, changes: usize) -> Self {
+    if changes == 0 {
+      panic!("A lead must have at least one change");
+    }
+
+    self.lead_length = Some(changes);
+    self
+  }
+}
+
+impl Default for BlueLineOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Draws rows as an SVG blue line: the grid of rows with the treble's path
+/// in red, the working bell's path in blue, a line under each lead end and
+/// the place notation of each change in the right hand margin.
+pub fn write_blue_line(
+  rows: &[Vec<u8>],
+  options: &BlueLineOptions,
+  out: &mut impl Write,
+) -> Result<()> {
+  let bells_amount = rows.first().map_or(0, Vec::len);
+  let grid_width = bells_amount * COLUMN_WIDTH;
+  let width = grid_width + NOTATION_WIDTH + MARGIN * 2;
+  let height = rows.len() * ROW_HEIGHT + MARGIN * 2;
+
+  writeln!(
+    out,
+    "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+  )?;
+  writeln!(
+    out,
+    "  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>"
+  )?;
+  writeln!(
+    out,
+    "  <g font-family=\"monospace\" font-size=\"12\" text-anchor=\"middle\" fill=\"#555\">"
+  )?;
+
+  for (row_index, row) in rows.iter().enumerate() {
+    for (place, &bell) in row.iter().enumerate() {
+      writeln!(
+        out,
+        "    <text x=\"{}\" y=\"{}\">{}</text>",
+        get_x(place),
+        get_y(row_index) + 4,
+        bell_to_char(bell)
+      )?;
+    }
+  }
+
+  writeln!(out, "  </g>")?;
+
+  if let Some(lead_length) = options.lead_length {
+    for lead_end in (lead_length..rows.len().saturating_sub(1)).step_by(lead_length) {
+      let y = get_y(lead_end) + ROW_HEIGHT / 2;
+      writeln!(
+        out,
+        "  <line x1=\"{MARGIN}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#888\"/>",
+        MARGIN + grid_width
+      )?;
+    }
+  }
+
+  writeln!(
+    out,
+    "  <g font-family=\"monospace\" font-size=\"10\" text-anchor=\"start\" fill=\"#333\">"
+  )?;
+
+  for (row_index, pair) in rows.windows(2).enumerate() {
+    writeln!(
+      out,
+      "    <text x=\"{}\" y=\"{}\">{}</text>",
+      MARGIN * 2 + grid_width,
+      get_y(row_index) + ROW_HEIGHT / 2 + 3,
+      get_place_notation(&pair[0], &pair[1])
+    )?;
+  }
+
+  writeln!(out, "  </g>")?;
+
+  if options.working_bell != 1 {
+    write_bell_line(rows, options.working_bell, WORKING_BELL_COLOUR, out)?;
+  }
+  write_bell_line(rows, 1, TREBLE_COLOUR, out)?;
+
+  writeln!(out, "</svg>")
+}
+
+fn write_bell_line(rows: &[Vec<u8>], bell: u8, colour: &str, out: &mut impl Write) -> Result<()> {
+  let mut points = String::new();
+
+  for (row_index, row) in rows.iter().enumerate() {
+    if let Some(place) = row.iter().position(|&row_bell| row_bell == bell) {
+      write!(points, "{},{} ", get_x(place), get_y(row_index)).expect("Writing to a string");
+    }
+  }
+
+  if points.is_empty() {
+    return Ok(());
+  }
+
+  writeln!(
+    out,
+    "  <polyline points=\"{}\" fill=\"none\" stroke=\"{colour}\" stroke-width=\"2\" stroke-linejoin=\"round\"/>",
+    points.trim_end()
+  )
+}
+
+fn get_x(place: usize) -> usize {
+  MARGIN + place * COLUMN_WIDTH + COLUMN_WIDTH / 2
+}
+
+fn get_y(row_index: usize) -> usize {
+  MARGIN + row_index * ROW_HEIGHT + ROW_HEIGHT / 2
+}
+
+#[cfg(test)]
+mod test {
+  use crate::place_notation::expand_lead;
+
+  use super::*;
+
+  fn get_svg(rows: &[Vec<u8>], options: &BlueLineOptions) -> String {
+    let mut out = Vec::new();
+    write_blue_line(rows, options, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn can_set_options() {
+    let options = BlueLineOptions::new().working_bell(3).lead_length(8);
+
+    assert_eq!(options.working_bell, 3);
+    assert_eq!(options.lead_length, Some(8));
+    assert_eq!(BlueLineOptions::default().working_bell, 2);
+  }
+
+  #[test]
+  #[should_panic(expected = "A lead must have at least one change")]
+  fn panics_if_lead_has_no_changes() {
+    BlueLineOptions::new().lead_length(0);
+  }
+
+  #[test]
+  fn draws_rows_lines_and_place_notation() {
+    let rows = expand_lead("x14x14,12", 4).unwrap();
+    let svg = get_svg(&rows, &BlueLineOptions::new());
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<text").count(), 9 * 4 + 8);
+    assert_eq!(svg.matches("<polyline").count(), 2);
+    assert!(svg.contains(&format!("stroke=\"{TREBLE_COLOUR}\"")));
+    assert!(svg.contains(&format!("stroke=\"{WORKING_BELL_COLOUR}\"")));
+    assert!(svg.contains(">x</text>"));
+    assert!(svg.contains(">14</text>"));
+    assert!(svg.contains(">12</text>"));
+    assert!(!svg.contains("<line"));
+  }
+
+  #[test]
+  fn draws_the_treble_path() {
+    let rows = vec![vec![1, 2], vec![2, 1], vec![1, 2]];
+    let svg = get_svg(&rows, &BlueLineOptions::new().working_bell(1));
+
+    assert_eq!(svg.matches("<polyline").count(), 1);
+    assert!(svg.contains("points=\"19,21 33,39 19,57\""));
+  }
+
+  #[test]
+  fn marks_lead_ends() {
+    let mut rows = expand_lead("x14x14,12", 4).unwrap();
+    let second_lead = expand_lead("x14x14,12", 4).unwrap();
+    let lead_head = rows.last().unwrap().clone();
+    rows.extend(second_lead[1..].iter().map(|row| {
+      row
+        .iter()
+        .map(|&bell| lead_head[bell as usize - 1])
+        .collect::<Vec<u8>>()
+    }));
+
+    let svg = get_svg(&rows, &BlueLineOptions::new().lead_length(8));
+
+    assert_eq!(svg.matches("<line").count(), 1);
+  }
+
+  #[test]
+  fn handles_no_rows() {
+    let svg = get_svg(&[], &BlueLineOptions::new().lead_length(2));
+
+    assert_eq!(svg.matches("<polyline").count(), 0);
+  }
+}
@@ -0,0 +1,474 @@
+//! An interactive terminal browser of the methods found by a search.
+//!
+//! Methods are added to the list as they are found. The selected method's
+//! rows are drawn beside the list with one bell highlighted, under its
+//! place notation, class and music score. The list can be narrowed with a
+//! [`MethodFilter`] expression and methods marked to be saved.
+//!
+//! | Key               | Action                                       |
+//! |-------------------|----------------------------------------------|
+//! | `↑` `↓` / `k` `j` | Select the previous or next method           |
+//! | `PgUp` `PgDn`     | Scroll the selected method's rows            |
+//! | `+` `-`           | Highlight a higher or lower numbered bell    |
+//! | `/`               | Type a filter, applied with `Enter`          |
+//! | `Space`           | Mark or unmark the selected method           |
+//! | `w`               | Save the marked methods                      |
+//! | `q`               | Quit                                         |
+
+mod terminal;
+
+use std::collections::BTreeSet;
+
+use crate::{
+  MethodFilter,
+  classification::{Classification, classify},
+  place_notation::{bell_to_char, get_place_notation},
+};
+
+pub use terminal::run;
+
+const LIST_WIDTH: usize = 32;
+const HEADER_HEIGHT: usize = 4;
+const PAGE_ROWS: usize = 20;
+const HIGHLIGHT: &str = "\x1b[1;34m";
+const SELECTED: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+/// A key pressed in the browser.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+  Up,
+  Down,
+  PageUp,
+  PageDown,
+  Enter,
+  Escape,
+  Backspace,
+  /// Ctrl-C, quitting whatever is being typed.
+  Interrupt,
+  Char(char),
+}
+
+/// What the browser wants done after a key press.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+  Continue,
+  Save,
+  Quit,
+}
+
+/// A method in the list, with what the browser shows about it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BrowsedMethod {
+  label: String,
+  rows: Vec<Vec<u8>>,
+  score: i32,
+  classification: Classification,
+  place_notation: String,
+}
+
+impl BrowsedMethod {
+  /// A method labelled by its name or index in the search.
+  pub fn new(label: String, rows: Vec<Vec<u8>>, score: i32) -> BrowsedMethod {
+    let place_notation = rows
+      .windows(2)
+      .map(|pair| get_place_notation(&pair[0], &pair[1]))
+      .collect::<Vec<String>>()
+      .join(".");
+
+    BrowsedMethod {
+      label,
+      classification: classify(&rows),
+      rows,
+      score,
+      place_notation,
+    }
+  }
+
+  pub fn get_label(&self) -> &str {
+    &self.label
+  }
+
+  pub fn get_rows(&self) -> &[Vec<u8>] {
+    &self.rows
+  }
+
+  pub fn get_score(&self) -> i32 {
+    self.score
+  }
+
+  pub fn get_classification(&self) -> Classification {
+    self.classification
+  }
+
+  pub fn get_place_notation(&self) -> &str {
+    &self.place_notation
+  }
+}
+
+/// The state of the browser, changed by key presses and drawn as text.
+#[derive(Clone, Debug)]
+pub struct Browser {
+  methods: Vec<BrowsedMethod>,
+  visible: Vec<usize>,
+  selected: usize,
+  row_offset: usize,
+  filter: MethodFilter,
+  filter_input: Option<String>,
+  working_bell: u8,
+  marked: BTreeSet<usize>,
+  message: String,
+}
+
+impl Browser {
+  pub fn new(working_bell: u8) -> Browser {
+    Browser {
+      methods: Vec::new(),
+      visible: Vec::new(),
+      selected: 0,
+      row_offset: 0,
+      filter: MethodFilter::default(),
+      filter_input: None,
+      working_bell,
+      marked: BTreeSet::new(),
+      message: String::new(),
+    }
+  }
+
+  /// Adds a method to the end of the list, shown if it passes the filter.
+  pub fn add_method(&mut self, method: BrowsedMethod) {
+    if self.filter.matches_rows(method.get_rows()) {
+      self.visible.push(self.methods.len());
+    }
+    self.methods.push(method);
+  }
+
+  pub fn get_methods(&self) -> &[BrowsedMethod] {
+    &self.methods
+  }
+
+  /// The methods passing the filter, in the order they were found.
+  pub fn get_visible(&self) -> impl Iterator<Item = &BrowsedMethod> {
+    self.visible.iter().map(|&index| &self.methods[index])
+  }
+
+  pub fn get_selected(&self) -> Option<&BrowsedMethod> {
+    self
+      .visible
+      .get(self.selected)
+      .map(|&index| &self.methods[index])
+  }
+
+  pub fn get_marked(&self) -> impl Iterator<Item = &BrowsedMethod> {
+    self.marked.iter().map(|&index| &self.methods[index])
+  }
+
+  pub fn get_working_bell(&self) -> u8 {
+    self.working_bell
+  }
+
+  /// Shows a line of text in the status bar until the next key press.
+  pub fn set_message(&mut self, message: String) {
+    self.message = message;
+  }
+
+  pub fn handle_key(&mut self, key: Key) -> Action {
+    self.message.clear();
+    if key == Key::Interrupt {
+      return Action::Quit;
+    }
+
+    if let Some(input) = &mut self.filter_input {
+      match key {
+        Key::Char(symbol) => input.push(symbol),
+        Key::Backspace => {
+          input.pop();
+        }
+        Key::Escape => self.filter_input = None,
+        Key::Enter => {
+          let input = self.filter_input.take().unwrap_or_default();
+          match input.parse() {
+            Ok(filter) => self.set_filter(filter),
+            Err(error) => self.message = format!("{error}"),
+          }
+        }
+        _ => {}
+      }
+      return Action::Continue;
+    }
+
+    match key {
+      Key::Up | Key::Char('k') => self.select(self.selected.saturating_sub(1)),
+      Key::Down | Key::Char('j') => self.select(self.selected + 1),
+      Key::PageUp => self.row_offset = self.row_offset.saturating_sub(PAGE_ROWS),
+      Key::PageDown => {
+        let rows_amount = self.get_selected().map_or(0, |method| method.rows.len());
+        self.row_offset = (self.row_offset + PAGE_ROWS).min(rows_amount.saturating_sub(1));
+      }
+      Key::Char('+') => {
+        let bells_amount = self.get_selected().map_or(0, |method| method.rows[0].len());
+        self.working_bell = (self.working_bell + 1).min(bells_amount.max(1) as u8);
+      }
+      Key::Char('-') => self.working_bell = self.working_bell.saturating_sub(1).max(1),
+      Key::Char('/') => self.filter_input = Some(String::new()),
+      Key::Char(' ') => {
+        if let Some(&index) = self.visible.get(self.selected)
+          && !self.marked.remove(&index)
+        {
+          self.marked.insert(index);
+        }
+      }
+      Key::Char('w') => return Action::Save,
+      Key::Char('q') | Key::Escape => return Action::Quit,
+      _ => {}
+    }
+
+    Action::Continue
+  }
+
+  fn select(&mut self, position: usize) {
+    self.selected = position.min(self.visible.len().saturating_sub(1));
+    self.row_offset = 0;
+  }
+
+  fn set_filter(&mut self, filter: MethodFilter) {
+    self.visible = (0..self.methods.len())
+      .filter(|&index| filter.matches_rows(self.methods[index].get_rows()))
+      .collect();
+    self.filter = filter;
+    self.select(0);
+  }
+
+  /// Draws the browser as `height` lines of text, with ANSI escapes for
+  /// the selected method and highlighted bell.
+  pub fn render(&self, width: usize, height: usize) -> Vec<String> {
+    let body_height = height.saturating_sub(1);
+    let list_start = self.selected.saturating_sub(body_height.saturating_sub(1));
+    let list: Vec<String> = (list_start..list_start + body_height)
+      .map(|position| self.render_list_entry(position))
+      .collect();
+    let details = self.render_details(width.saturating_sub(LIST_WIDTH + 1), body_height);
+
+    let mut lines: Vec<String> = list
+      .into_iter()
+      .zip(details)
+      .map(|(entry, detail)| format!("{entry} {detail}"))
+      .collect();
+    lines.push(self.render_status());
+    lines
+  }
+
+  fn render_list_entry(&self, position: usize) -> String {
+    let Some(&index) = self.visible.get(position) else {
+      return " ".repeat(LIST_WIDTH);
+    };
+    let method = &self.methods[index];
+    let mark = if self.marked.contains(&index) {
+      '*'
+    } else {
+      ' '
+    };
+    let entry = format!("{mark}{} ({})", method.label, method.score);
+    let entry: String = format!("{entry:<LIST_WIDTH$}")
+      .chars()
+      .take(LIST_WIDTH)
+      .collect();
+
+    if position == self.selected {
+      format!("{SELECTED}{entry}{RESET}")
+    } else {
+      entry
+    }
+  }
+
+  fn render_details(&self, width: usize, height: usize) -> Vec<String> {
+    let mut lines = vec![String::new(); height];
+    let Some(method) = self.get_selected() else {
+      if let Some(line) = lines.first_mut() {
+        *line = String::from("Searching...");
+      }
+      return lines;
+    };
+
+    let header = [
+      method.label.clone(),
+      format!("{} | music {}", method.classification, method.score),
+      method.place_notation.chars().take(width).collect(),
+      String::new(),
+    ];
+    let rows = method.rows.iter().skip(self.row_offset).map(|row| {
+      row
+        .iter()
+        .map(|&bell| {
+          if bell == self.working_bell {
+            format!("{HIGHLIGHT}{}{RESET}", bell_to_char(bell))
+          } else {
+            bell_to_char(bell).to_string()
+          }
+        })
+        .collect::<String>()
+    });
+
+    for (line, text) in lines
+      .iter_mut()
+      .zip(header.into_iter().take(HEADER_HEIGHT).chain(rows))
+    {
+      *line = text;
+    }
+    lines
+  }
+
+  fn render_status(&self) -> String {
+    if let Some(input) = &self.filter_input {
+      return format!("filter: {input}");
+    }
+
+    let status = format!(
+      "{} of {} methods, {} marked | j/k select, +/- bell, / filter, space mark, w save, q quit",
+      self.visible.len(),
+      self.methods.len(),
+      self.marked.len()
+    );
+    if self.message.is_empty() {
+      status
+    } else {
+      format!("{} | {status}", self.message)
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::method::Method;
+
+  fn method(label: &str, notation: &str) -> BrowsedMethod {
+    let mut rows = Method::from_place_notation(notation, 4)
+      .unwrap()
+      .get_plain_course();
+    rows.push(vec![1, 2, 3, 4]);
+    BrowsedMethod::new(label.to_string(), rows, 3)
+  }
+
+  fn browser() -> Browser {
+    let mut browser = Browser::new(2);
+    browser.add_method(method("0", "x14x14,12"));
+    browser.add_method(method("1", "x12x14,14"));
+    browser.add_method(method("2", "x14x14,14"));
+    browser
+  }
+
+  fn type_keys(browser: &mut Browser, keys: &str) {
+    for symbol in keys.chars() {
+      browser.handle_key(Key::Char(symbol));
+    }
+  }
+
+  #[test]
+  fn describes_each_method() {
+    let plain_bob = method("Plain Bob", "x14x14,12");
+
+    assert_eq!(plain_bob.get_classification(), Classification::Plain);
+    assert!(plain_bob.get_place_notation().starts_with("x.14.x.14"));
+    assert_eq!(plain_bob.get_rows().len(), 25);
+  }
+
+  #[test]
+  fn moves_the_selection() {
+    let mut browser = browser();
+    assert_eq!(browser.get_selected().unwrap().get_label(), "0");
+
+    browser.handle_key(Key::Down);
+    browser.handle_key(Key::Char('j'));
+    browser.handle_key(Key::Down);
+    assert_eq!(browser.get_selected().unwrap().get_label(), "2");
+
+    browser.handle_key(Key::Up);
+    assert_eq!(browser.get_selected().unwrap().get_label(), "1");
+  }
+
+  #[test]
+  fn filters_the_list() {
+    let mut browser = browser();
+
+    browser.handle_key(Key::Char('/'));
+    type_keys(&mut browser, "treble-hunts");
+    assert_eq!(browser.handle_key(Key::Enter), Action::Continue);
+
+    let labels: Vec<&str> = browser
+      .get_visible()
+      .map(BrowsedMethod::get_label)
+      .collect();
+    assert_eq!(labels, vec!["0", "2"]);
+
+    browser.add_method(method("3", "x12x14,14"));
+    assert_eq!(browser.get_visible().count(), 2);
+    assert_eq!(browser.get_methods().len(), 4);
+  }
+
+  #[test]
+  fn reports_invalid_filters() {
+    let mut browser = browser();
+
+    browser.handle_key(Key::Char('/'));
+    type_keys(&mut browser, "wrongg");
+    browser.handle_key(Key::Backspace);
+    browser.handle_key(Key::Enter);
+
+    assert!(browser.render(80, 5)[4].starts_with("unknown filter 'wrong'"));
+    assert_eq!(browser.get_visible().count(), 3);
+  }
+
+  #[test]
+  fn marks_methods_to_save() {
+    let mut browser = browser();
+
+    browser.handle_key(Key::Char(' '));
+    browser.handle_key(Key::Down);
+    browser.handle_key(Key::Char(' '));
+    browser.handle_key(Key::Char(' '));
+    browser.handle_key(Key::Down);
+    browser.handle_key(Key::Char(' '));
+
+    let labels: Vec<&str> = browser.get_marked().map(BrowsedMethod::get_label).collect();
+    assert_eq!(labels, vec!["0", "2"]);
+    assert_eq!(browser.handle_key(Key::Char('w')), Action::Save);
+    assert_eq!(browser.handle_key(Key::Char('q')), Action::Quit);
+  }
+
+  #[test]
+  fn highlights_the_working_bell() {
+    let mut browser = browser();
+    browser.handle_key(Key::Char('+'));
+    browser.handle_key(Key::Char('+'));
+    browser.handle_key(Key::Char('+'));
+    assert_eq!(browser.get_working_bell(), 4);
+
+    let lines = browser.render(80, 10);
+    assert_eq!(lines.len(), 10);
+    assert!(lines[0].contains(SELECTED));
+    assert!(lines[1].contains("Plain | music 3"));
+    assert!(lines[4].ends_with(&format!("123{HIGHLIGHT}4{RESET}")));
+  }
+
+  #[test]
+  fn scrolls_the_rows() {
+    let mut browser = browser();
+    browser.handle_key(Key::PageDown);
+
+    let lines = browser.render(80, 10);
+    // Row 20 of Plain Bob Minimus
+    assert!(lines[4].ends_with(&format!("3{HIGHLIGHT}2{RESET}41")));
+    browser.handle_key(Key::PageUp);
+    assert!(browser.render(80, 10)[4].ends_with(&format!("1{HIGHLIGHT}2{RESET}34")));
+  }
+
+  #[test]
+  fn waits_for_methods() {
+    let browser = Browser::new(2);
+
+    assert!(browser.render(80, 3)[0].ends_with("Searching..."));
+    assert!(browser.render(80, 3)[2].starts_with("0 of 0 methods"));
+  }
+}
@@ -0,0 +1,238 @@
+use std::{
+  fs::File,
+  io::{self, BufWriter, Read, Write},
+  path::Path,
+  process::{Command, Stdio},
+  sync::mpsc::{self, Receiver},
+  thread,
+};
+
+use crate::browser::{Action, BrowsedMethod, Browser, Key};
+
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+const LEAVE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+const HOME: &str = "\x1b[H";
+const CLEAR_LINE: &str = "\x1b[K";
+const DEFAULT_SIZE: (usize, usize) = (24, 80);
+
+enum Event {
+  Method(BrowsedMethod),
+  Keys(Vec<Key>),
+}
+
+/// Runs the browser in the terminal until it is quit, adding each method
+/// as it arrives. Marked methods are saved to `save_path`.
+///
+/// The terminal is put into raw mode with `stty`, so this needs a Unix
+/// terminal on stdin.
+pub fn run(browser: Browser, methods: Receiver<BrowsedMethod>, save_path: &Path) -> io::Result<()> {
+  let saved_mode = stty(&["-g"])?;
+  stty(&["raw", "-echo"])?;
+
+  let mut out = io::stdout().lock();
+  write!(out, "{ENTER_SCREEN}")?;
+  let result = run_in_raw_mode(browser, methods, save_path, &mut out);
+  write!(out, "{LEAVE_SCREEN}")?;
+  out.flush()?;
+
+  stty(&[saved_mode.trim()])?;
+  result
+}
+
+fn run_in_raw_mode(
+  mut browser: Browser,
+  methods: Receiver<BrowsedMethod>,
+  save_path: &Path,
+  out: &mut impl Write,
+) -> io::Result<()> {
+  let (sender, events) = mpsc::channel();
+
+  let method_sender = sender.clone();
+  thread::spawn(move || {
+    for method in methods {
+      if method_sender.send(Event::Method(method)).is_err() {
+        break;
+      }
+    }
+  });
+  thread::spawn(move || {
+    let mut stdin = io::stdin();
+    let mut buffer = [0; 32];
+    while let Ok(read @ 1..) = stdin.read(&mut buffer) {
+      if sender
+        .send(Event::Keys(parse_keys(&buffer[..read])))
+        .is_err()
+      {
+        break;
+      }
+    }
+  });
+
+  draw(&browser, out)?;
+  while let Ok(event) = events.recv() {
+    // Handles everything waiting before drawing, so a burst of methods
+    // is drawn once
+    for event in std::iter::once(event).chain(events.try_iter()) {
+      match event {
+        Event::Method(method) => browser.add_method(method),
+        Event::Keys(keys) => {
+          for key in keys {
+            match browser.handle_key(key) {
+              Action::Continue => {}
+              Action::Quit => return Ok(()),
+              Action::Save => {
+                let message = match save(&browser, save_path) {
+                  Ok(count) => format!("saved {count} methods to {}", save_path.display()),
+                  Err(error) => format!("could not save: {error}"),
+                };
+                browser.set_message(message);
+              }
+            }
+          }
+        }
+      }
+    }
+
+    draw(&browser, out)?;
+  }
+
+  Ok(())
+}
+
+fn draw(browser: &Browser, out: &mut impl Write) -> io::Result<()> {
+  let (height, width) = terminal_size();
+
+  write!(out, "{HOME}")?;
+  let lines = browser.render(width, height);
+  let last = lines.len().saturating_sub(1);
+  for (index, line) in lines.iter().enumerate() {
+    write!(out, "{line}{CLEAR_LINE}")?;
+    if index != last {
+      write!(out, "\r\n")?;
+    }
+  }
+  out.flush()
+}
+
+/// Writes the marked methods as `print_method` does, returning how many.
+fn save(browser: &Browser, path: &Path) -> io::Result<usize> {
+  let mut out = BufWriter::new(File::create(path)?);
+  let mut count = 0;
+
+  for method in browser.get_marked() {
+    writeln!(out, "{}", method.get_label())?;
+    for row in method.get_rows() {
+      writeln!(out, "{row:?},")?;
+    }
+    count += 1;
+  }
+
+  out.flush()?;
+  Ok(count)
+}
+
+fn stty(arguments: &[&str]) -> io::Result<String> {
+  let output = Command::new("stty")
+    .args(arguments)
+    .stdin(Stdio::inherit())
+    .output()?;
+
+  if output.status.success() {
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+  } else {
+    Err(io::Error::other(
+      "stty failed, the browser needs a terminal",
+    ))
+  }
+}
+
+/// Rows and columns of the terminal.
+fn terminal_size() -> (usize, usize) {
+  stty(&["size"])
+    .ok()
+    .and_then(|size| {
+      let (rows, columns) = size.trim().split_once(' ')?;
+      Some((rows.parse().ok()?, columns.parse().ok()?))
+    })
+    .unwrap_or(DEFAULT_SIZE)
+}
+
+/// The keys in bytes read from a raw mode terminal, where arrow keys and
+/// page keys arrive as escape sequences.
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+  let mut keys = Vec::new();
+  let text = String::from_utf8_lossy(bytes);
+  let mut symbols = text.chars().peekable();
+
+  while let Some(symbol) = symbols.next() {
+    let key = match symbol {
+      '\x1b' if symbols.peek() == Some(&'[') => {
+        symbols.next();
+        match symbols.next() {
+          Some('A') => Key::Up,
+          Some('B') => Key::Down,
+          Some(page @ ('5' | '6')) if symbols.next_if_eq(&'~').is_some() => {
+            if page == '5' {
+              Key::PageUp
+            } else {
+              Key::PageDown
+            }
+          }
+          _ => continue,
+        }
+      }
+      '\x1b' => Key::Escape,
+      '\r' | '\n' => Key::Enter,
+      '\x7f' | '\x08' => Key::Backspace,
+      '\x03' => Key::Interrupt,
+      symbol if symbol.is_control() => continue,
+      symbol => Key::Char(symbol),
+    };
+    keys.push(key);
+  }
+
+  keys
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn parses_keys() {
+    assert_eq!(
+      parse_keys(b"j\x1b[A\x1b[B\x1b[5~\x1b[6~"),
+      vec![
+        Key::Char('j'),
+        Key::Up,
+        Key::Down,
+        Key::PageUp,
+        Key::PageDown
+      ]
+    );
+    assert_eq!(
+      parse_keys(b"/x\x7f\r\x1b\x03"),
+      vec![
+        Key::Char('/'),
+        Key::Char('x'),
+        Key::Backspace,
+        Key::Enter,
+        Key::Escape,
+        Key::Interrupt
+      ]
+    );
+    assert_eq!(parse_keys(b"\x1b[C\x01q"), vec![Key::Char('q')]);
+  }
+
+  #[test]
+  fn draws_every_line() {
+    let browser = Browser::new(2);
+    let mut out = Vec::new();
+    draw(&browser, &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+
+    assert!(text.starts_with(HOME));
+    assert!(text.contains("Searching..."));
+    assert!(text.contains("0 of 0 methods"));
+  }
+}
@@ -0,0 +1,120 @@
+//! Sorting methods into broad classes by what the treble does.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::TreblePath;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Classification {
+  /// The treble plain hunts, leading and lying for two rows.
+  Plain,
+  /// The treble dodges in each pair of places on its way up and down.
+  TrebleDodging,
+  /// The treble follows neither path.
+  Unclassified,
+}
+
+impl fmt::Display for Classification {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Classification::Plain => write!(f, "Plain"),
+      Classification::TrebleDodging => write!(f, "Treble Dodging"),
+      Classification::Unclassified => write!(f, "Unclassified"),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ClassificationError(String);
+
+impl fmt::Display for ClassificationError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "unknown class '{}', expected plain, treble-dodging or unclassified",
+      self.0
+    )
+  }
+}
+
+impl Error for ClassificationError {}
+
+impl FromStr for Classification {
+  type Err = ClassificationError;
+
+  /// Reads `plain`, `treble-dodging` or `unclassified`.
+  fn from_str(class: &str) -> Result<Classification, ClassificationError> {
+    match class.trim().to_lowercase().as_str() {
+      "plain" => Ok(Classification::Plain),
+      "treble-dodging" | "treble dodging" => Ok(Classification::TrebleDodging),
+      "unclassified" => Ok(Classification::Unclassified),
+      _ => Err(ClassificationError(class.to_string())),
+    }
+  }
+}
+
+/// The class of the method ringing `rows`, which start from rounds.
+pub fn classify<R: AsRef<[u8]>>(rows: &[R]) -> Classification {
+  let bells_amount = rows.first().map_or(0, |row| row.as_ref().len());
+  let follows = |path: TreblePath| {
+    rows
+      .iter()
+      .enumerate()
+      .all(|(index, row)| path.allows_row(index, row.as_ref()))
+  };
+
+  if bells_amount == 0 {
+    Classification::Unclassified
+  } else if follows(TreblePath::plain_hunt(bells_amount)) {
+    Classification::Plain
+  } else if bells_amount.is_multiple_of(2) && follows(TreblePath::treble_bob(bells_amount)) {
+    Classification::TrebleDodging
+  } else {
+    Classification::Unclassified
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::method::Method;
+
+  fn classify_method(notation: &str, bells_amount: usize) -> Classification {
+    classify(
+      &Method::from_place_notation(notation, bells_amount)
+        .unwrap()
+        .get_plain_course(),
+    )
+  }
+
+  #[test]
+  fn classifies_by_the_treble() {
+    assert_eq!(classify_method("x16x16x16,12", 6), Classification::Plain);
+    assert_eq!(
+      classify_method("34x34.16x12x16x12x16,12", 6),
+      Classification::TrebleDodging
+    );
+    assert_eq!(
+      classify_method("x12x14,14", 4),
+      Classification::Unclassified
+    );
+    assert_eq!(classify::<Vec<u8>>(&[]), Classification::Unclassified);
+  }
+
+  #[test]
+  fn can_display_classes() {
+    assert_eq!(Classification::TrebleDodging.to_string(), "Treble Dodging");
+  }
+
+  #[test]
+  fn can_parse_classes() {
+    assert_eq!("plain".parse(), Ok(Classification::Plain));
+    assert_eq!("Treble Dodging".parse(), Ok(Classification::TrebleDodging));
+    assert_eq!("unclassified".parse(), Ok(Classification::Unclassified));
+    assert_eq!(
+      "surprise".parse::<Classification>(),
+      Err(ClassificationError("surprise".to_string()))
+    );
+  }
+}
@@ -0,0 +1,313 @@
+mod touch_search;
+
+use std::{error::Error, fmt};
+
+use crate::{
+  is_valid_change,
+  method::{Method, get_rounds},
+  permutation_graph::utility::are_unique_rows,
+  place_notation::{PlaceNotationError, Places, apply_change, parse_place_notation},
+};
+
+pub use touch_search::{MAX_TOUCH_SEARCH_BELLS, TouchSearch, TouchSearchOptions};
+
+const PLAIN_LEAD_SYMBOLS: [char; 2] = ['p', '.'];
+
+/// A call, replacing the last changes of a lead with its own, e.g. a bob of `14` in Plain Bob Minor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+  symbol: char,
+  bells_amount: usize,
+  changes: Vec<Places>,
+}
+
+impl Call {
+  /// A call written as `symbol` in calling strings, made of the changes in `notation`.
+  pub fn new(
+    symbol: char,
+    notation: &str,
+    bells_amount: usize,
+  ) -> Result<Call, PlaceNotationError> {
+    Ok(Call {
+      symbol,
+      bells_amount,
+      changes: parse_place_notation(notation, bells_amount)?,
+    })
+  }
+
+  pub fn get_symbol(&self) -> char {
+    self.symbol
+  }
+
+  pub fn get_changes(&self) -> &[Places] {
+    &self.changes
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CompositionError {
+  UnknownCall(char),
+  CallTooLong(char),
+  WrongNumberOfBells(char),
+  InvalidChange(usize),
+  TooManyBells(usize),
+}
+
+impl fmt::Display for CompositionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CompositionError::UnknownCall(symbol) => write!(f, "no call is written as '{symbol}'"),
+      CompositionError::CallTooLong(symbol) => {
+        write!(f, "call '{symbol}' has more changes than a lead")
+      }
+      CompositionError::WrongNumberOfBells(symbol) => {
+        write!(f, "call '{symbol}' is for a different number of bells")
+      }
+      CompositionError::InvalidChange(index) => {
+        write!(f, "change {index} moves a bell more than one place")
+      }
+      CompositionError::TooManyBells(bells_amount) => write!(
+        f,
+        "touches are searched on at most {MAX_TOUCH_SEARCH_BELLS} bells, not {bells_amount}"
+      ),
+    }
+  }
+}
+
+impl Error for CompositionError {}
+
+/// A touch: a method with calls at some of its lead ends.
+#[derive(Debug)]
+pub struct Composition {
+  calling: String,
+  rows: Vec<Vec<u8>>,
+  lead_count: usize,
+}
+
+impl Composition {
+  /// Rings one lead of `method` for each symbol of `calling`, where `p` or
+  /// `.` is a plain lead and any other symbol calls the matching call at the
+  /// lead end. Whitespace in the calling is ignored.
+  pub fn new(
+    method: &Method,
+    calls: &[Call],
+    calling: &str,
+  ) -> Result<Composition, CompositionError> {
+    Self::validate_calls(method, calls)?;
+
+    let calling: String = calling
+      .chars()
+      .filter(|symbol| !symbol.is_whitespace())
+      .collect();
+    let mut rows = vec![get_rounds(method.get_bells_amount())];
+    let mut lead_count = 0;
+
+    for symbol in calling.chars() {
+      let lead_changes = Self::get_lead_changes(method, calls, symbol)?;
+
+      for places in lead_changes {
+        let row = apply_change(rows.last().expect("Rows start with rounds"), places);
+        rows.push(row);
+      }
+
+      lead_count += 1;
+    }
+
+    Self::validate_changes(&rows)?;
+
+    Ok(Composition {
+      calling,
+      rows,
+      lead_count,
+    })
+  }
+
+  pub(crate) fn validate_calls(method: &Method, calls: &[Call]) -> Result<(), CompositionError> {
+    for call in calls.iter() {
+      if call.changes.len() > method.get_lead_length() {
+        return Err(CompositionError::CallTooLong(call.symbol));
+      }
+
+      if call.bells_amount != method.get_bells_amount() {
+        return Err(CompositionError::WrongNumberOfBells(call.symbol));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn get_lead_changes<'a>(
+    method: &'a Method,
+    calls: &'a [Call],
+    symbol: char,
+  ) -> Result<impl Iterator<Item = &'a Places>, CompositionError> {
+    let call_changes: &[Places] = if PLAIN_LEAD_SYMBOLS.contains(&symbol.to_ascii_lowercase()) {
+      &[]
+    } else {
+      calls
+        .iter()
+        .find(|call| call.symbol == symbol)
+        .map(|call| call.get_changes())
+        .ok_or(CompositionError::UnknownCall(symbol))?
+    };
+
+    let plain_changes = &method.get_changes()[..method.get_lead_length() - call_changes.len()];
+
+    Ok(plain_changes.iter().chain(call_changes.iter()))
+  }
+
+  fn validate_changes(rows: &[Vec<u8>]) -> Result<(), CompositionError> {
+    match rows
+      .windows(2)
+      .position(|pair| !is_valid_change(&pair[0], &pair[1]))
+    {
+      Some(index) => Err(CompositionError::InvalidChange(index + 1)),
+      None => Ok(()),
+    }
+  }
+
+  /// The calling with whitespace removed, one symbol per lead.
+  pub fn get_calling(&self) -> &str {
+    &self.calling
+  }
+
+  /// Every row from the opening rounds to the last row of the last lead.
+  pub fn get_rows(&self) -> &[Vec<u8>] {
+    &self.rows
+  }
+
+  /// Number of changes rung.
+  pub fn get_length(&self) -> usize {
+    self.rows.len() - 1
+  }
+
+  pub fn get_lead_count(&self) -> usize {
+    self.lead_count
+  }
+
+  /// Whether the last row is rounds.
+  pub fn comes_round(&self) -> bool {
+    let last_row = self.rows.last().expect("Rows start with rounds");
+    self.rows.len() > 1 && *last_row == self.rows[0]
+  }
+
+  /// Whether no row is rung twice, other than finishing in rounds.
+  pub fn is_true(&self) -> bool {
+    if self.comes_round() {
+      are_unique_rows(&self.rows[..self.rows.len() - 1])
+    } else {
+      are_unique_rows(&self.rows)
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn plain_bob_minor() -> (Method, Vec<Call>) {
+    let method = Method::from_place_notation("x16x16x16,12", 6).unwrap();
+    let calls = vec![
+      Call::new('-', "14", 6).unwrap(),
+      Call::new('s', "1234", 6).unwrap(),
+    ];
+
+    (method, calls)
+  }
+
+  #[test]
+  fn can_create_a_call() {
+    let call = Call::new('-', "14", 6).unwrap();
+
+    assert_eq!(call.get_symbol(), '-');
+    assert_eq!(call.get_changes(), &[vec![1, 4]]);
+    assert_eq!(
+      Call::new('s', "17", 6),
+      Err(PlaceNotationError::PlaceOutOfRange('7'))
+    );
+  }
+
+  #[test]
+  fn rings_the_plain_course() {
+    let (method, calls) = plain_bob_minor();
+    let composition = Composition::new(&method, &calls, "ppppp").unwrap();
+
+    assert_eq!(composition.get_length(), 60);
+    assert_eq!(composition.get_lead_count(), 5);
+    assert!(composition.comes_round());
+    assert!(composition.is_true());
+  }
+
+  #[test]
+  fn rings_calls_at_lead_ends() {
+    let (method, calls) = plain_bob_minor();
+    let composition = Composition::new(&method, &calls, "-").unwrap();
+    let rows = composition.get_rows();
+
+    assert_eq!(rows[11], vec![1, 3, 2, 5, 4, 6]);
+    assert_eq!(rows[12], vec![1, 2, 3, 5, 6, 4]);
+  }
+
+  #[test]
+  fn rings_a_bobbed_touch() {
+    let (method, calls) = plain_bob_minor();
+    let composition = Composition::new(&method, &calls, "- - -").unwrap();
+
+    assert_eq!(composition.get_length(), 36);
+    assert_eq!(composition.get_calling(), "---");
+    assert!(composition.comes_round());
+    assert!(composition.is_true());
+
+    let unfinished = Composition::new(&method, &calls, "-p-").unwrap();
+    assert!(!unfinished.comes_round());
+    assert!(unfinished.is_true());
+  }
+
+  #[test]
+  fn can_tell_a_false_touch() {
+    let (method, calls) = plain_bob_minor();
+    let composition = Composition::new(&method, &calls, "pppppp").unwrap();
+    let twice_round = Composition::new(&method, &calls, "pppppppppp").unwrap();
+
+    assert!(!composition.comes_round());
+    assert!(!composition.is_true());
+    assert!(twice_round.comes_round());
+    assert!(!twice_round.is_true());
+  }
+
+  #[test]
+  fn reports_unknown_calls() {
+    let (method, calls) = plain_bob_minor();
+
+    assert_eq!(
+      Composition::new(&method, &calls, "p-x").unwrap_err(),
+      CompositionError::UnknownCall('x')
+    );
+  }
+
+  #[test]
+  fn reports_calls_that_do_not_fit_the_method() {
+    let method = Method::from_place_notation("x14x14,12", 4).unwrap();
+    let long_call = Call::new('-', "x14x14x14x14x", 4).unwrap();
+    let major_call = Call::new('s', "18", 8).unwrap();
+
+    assert_eq!(
+      Composition::new(&method, &[long_call], "-").unwrap_err(),
+      CompositionError::CallTooLong('-')
+    );
+    assert_eq!(
+      Composition::new(&method, &[major_call], "s").unwrap_err(),
+      CompositionError::WrongNumberOfBells('s')
+    );
+  }
+
+  #[test]
+  fn handles_an_empty_calling() {
+    let (method, calls) = plain_bob_minor();
+    let composition = Composition::new(&method, &calls, "").unwrap();
+
+    assert_eq!(composition.get_length(), 0);
+    assert!(!composition.comes_round());
+    assert!(composition.is_true());
+  }
+}
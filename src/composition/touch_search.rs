@@ -0,0 +1,385 @@
+use std::collections::HashSet;
+
+use crate::{
+  composition::{Call, Composition, CompositionError, PLAIN_LEAD_SYMBOLS},
+  method::{Method, get_rounds},
+  permutation_graph::utility::{factorial, lexicographic_rank},
+  place_notation::apply_change,
+};
+
+/// The most bells a [`TouchSearch`] handles, as rows are ranked among all
+/// the rows of the stage, and 21! is too many to count.
+pub const MAX_TOUCH_SEARCH_BELLS: usize = 20;
+
+/// Limits on the touches a [`TouchSearch`] looks for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TouchSearchOptions {
+  min_length: usize,
+  max_length: Option<usize>,
+  extents_only: bool,
+}
+
+impl TouchSearchOptions {
+  /// Any length up to the extent.
+  pub fn new() -> TouchSearchOptions {
+    TouchSearchOptions {
+      min_length: 0,
+      max_length: None,
+      extents_only: false,
+    }
+  }
+
+  /// Fewest changes a touch may have.
+  pub fn min_length(mut self, changes: usize) -> Self {
+    self.min_length = changes;
+    self
+  }
+
+  /// Most changes a touch may have.
+  pub fn max_length(mut self, changes: usize) -> Self {
+    self.max_length = Some(changes);
+    self
+  }
+
+  /// Only look for touches ringing every row once.
+  pub fn extents_only(mut self, extents_only: bool) -> Self {
+    self.extents_only = extents_only;
+    self
+  }
+}
+
+impl Default for TouchSearchOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+enum PushStatus {
+  End,
+  Next,
+  Pruned,
+  Touch(String),
+}
+
+struct LeadAndCall {
+  lead_head: Vec<u8>,
+  next_option: usize,
+  option: Option<usize>,
+  rung_ranks: Vec<usize>,
+}
+
+/// Finds every true touch of a method that comes round at a lead end,
+/// trying a plain lead then each call at every lead end and backtracking as
+/// soon as a lead would repeat a row.
+pub struct TouchSearch {
+  method: Method,
+  calls: Vec<Call>,
+  symbols: Vec<char>,
+  lead_templates: Vec<Vec<Vec<u8>>>,
+  min_length: usize,
+  max_length: usize,
+  rung_ranks: HashSet<usize>,
+  lead_stack: Vec<LeadAndCall>,
+}
+
+impl TouchSearch {
+  /// Fails if the method has more than [`MAX_TOUCH_SEARCH_BELLS`], or a
+  /// call has more changes than a lead or is on another number of bells.
+  pub fn new(
+    method: Method,
+    calls: Vec<Call>,
+    options: &TouchSearchOptions,
+  ) -> Result<TouchSearch, CompositionError> {
+    if method.get_bells_amount() > MAX_TOUCH_SEARCH_BELLS {
+      return Err(CompositionError::TooManyBells(method.get_bells_amount()));
+    }
+    Composition::validate_calls(&method, &calls)?;
+
+    let extent_length = factorial(method.get_bells_amount());
+    let (min_length, max_length) = if options.extents_only {
+      (extent_length, extent_length)
+    } else {
+      (
+        options.min_length.max(1),
+        options
+          .max_length
+          .unwrap_or(extent_length)
+          .min(extent_length),
+      )
+    };
+
+    let symbols = std::iter::once(PLAIN_LEAD_SYMBOLS[0])
+      .chain(calls.iter().map(Call::get_symbol))
+      .collect();
+    let lead_templates = Self::build_lead_templates(&method, &calls);
+    let rounds = get_rounds(method.get_bells_amount());
+
+    Ok(TouchSearch {
+      method,
+      calls,
+      symbols,
+      lead_templates,
+      min_length,
+      max_length,
+      rung_ranks: HashSet::new(),
+      lead_stack: vec![LeadAndCall {
+        lead_head: rounds,
+        next_option: 0,
+        option: None,
+        rung_ranks: Vec::new(),
+      }],
+    })
+  }
+
+  // The rows of a plain lead and of each called lead, starting from rounds
+  fn build_lead_templates(method: &Method, calls: &[Call]) -> Vec<Vec<Vec<u8>>> {
+    let lead_length = method.get_lead_length();
+    let plain_changes = method.get_changes();
+
+    let call_changes = calls.iter().map(|call| {
+      plain_changes[..lead_length - call.get_changes().len()]
+        .iter()
+        .chain(call.get_changes().iter())
+        .collect::<Vec<_>>()
+    });
+
+    std::iter::once(plain_changes.iter().collect::<Vec<_>>())
+      .chain(call_changes)
+      .map(|changes| {
+        let mut rows = vec![get_rounds(method.get_bells_amount())];
+        for places in changes {
+          rows.push(apply_change(
+            rows.last().expect("Rows start with rounds"),
+            places,
+          ));
+        }
+        rows
+      })
+      .collect()
+  }
+
+  fn push_next(&mut self) -> PushStatus {
+    let lead_length = self.method.get_lead_length();
+    let touch_length = (self.lead_stack.len() - 1) * lead_length;
+    let top = self
+      .lead_stack
+      .last_mut()
+      .expect("Lead stack should always contain at least one lead");
+
+    if top.next_option == self.lead_templates.len() || lead_length == 0 {
+      return PushStatus::End;
+    }
+
+    let option = top.next_option;
+    top.next_option += 1;
+
+    let lead_rows: Vec<Vec<u8>> = self.lead_templates[option]
+      .iter()
+      .map(|row| {
+        row
+          .iter()
+          .map(|&bell| top.lead_head[bell as usize - 1])
+          .collect()
+      })
+      .collect();
+    let next_lead_head = lead_rows.last().expect("Leads have rows");
+    let lead_ranks: Vec<usize> = lead_rows[..lead_length]
+      .iter()
+      .map(|row| lexicographic_rank(row))
+      .collect();
+
+    if !self.are_unrung(&lead_ranks) {
+      return PushStatus::Pruned;
+    }
+
+    let new_length = touch_length + lead_length;
+
+    if *next_lead_head == self.lead_stack[0].lead_head {
+      return if new_length >= self.min_length && new_length <= self.max_length {
+        PushStatus::Touch(self.get_calling(option))
+      } else {
+        PushStatus::Pruned
+      };
+    }
+
+    let next_rank = lexicographic_rank(next_lead_head);
+    if new_length + lead_length > self.max_length
+      || self.rung_ranks.contains(&next_rank)
+      || lead_ranks.contains(&next_rank)
+    {
+      return PushStatus::Pruned;
+    }
+
+    self.rung_ranks.extend(lead_ranks.iter().copied());
+    self.lead_stack.push(LeadAndCall {
+      lead_head: next_lead_head.clone(),
+      next_option: 0,
+      option: Some(option),
+      rung_ranks: lead_ranks,
+    });
+
+    PushStatus::Next
+  }
+
+  fn are_unrung(&self, lead_ranks: &[usize]) -> bool {
+    let mut lead_set = HashSet::with_capacity(lead_ranks.len());
+    lead_ranks
+      .iter()
+      .all(|rank| !self.rung_ranks.contains(rank) && lead_set.insert(*rank))
+  }
+
+  fn pop(&mut self) {
+    if let Some(lead) = self.lead_stack.pop() {
+      for rank in lead.rung_ranks.iter() {
+        self.rung_ranks.remove(rank);
+      }
+    }
+  }
+
+  fn get_calling(&self, last_option: usize) -> String {
+    self
+      .lead_stack
+      .iter()
+      .filter_map(|lead| lead.option)
+      .chain(std::iter::once(last_option))
+      .map(|option| self.symbols[option])
+      .collect()
+  }
+}
+
+impl Iterator for TouchSearch {
+  type Item = Composition;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while !self.lead_stack.is_empty() {
+      match self.push_next() {
+        PushStatus::End => self.pop(),
+        PushStatus::Next | PushStatus::Pruned => (),
+        PushStatus::Touch(calling) => {
+          return Some(
+            Composition::new(&self.method, &self.calls, &calling)
+              .expect("Searched callings only use known calls"),
+          );
+        }
+      }
+    }
+
+    None
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn plain_bob(notation: &str, bells_amount: usize) -> (Method, Vec<Call>) {
+    let method = Method::from_place_notation(notation, bells_amount).unwrap();
+    let calls = vec![
+      Call::new('-', "14", bells_amount).unwrap(),
+      Call::new('s', "1234", bells_amount).unwrap(),
+    ];
+
+    (method, calls)
+  }
+
+  #[test]
+  fn can_set_options() {
+    let options = TouchSearchOptions::new()
+      .min_length(24)
+      .max_length(120)
+      .extents_only(true);
+
+    assert_eq!(options.min_length, 24);
+    assert_eq!(options.max_length, Some(120));
+    assert!(options.extents_only);
+    assert_eq!(TouchSearchOptions::default(), TouchSearchOptions::new());
+  }
+
+  #[test]
+  fn finds_true_touches_that_come_round() {
+    let (method, calls) = plain_bob("x16x16x16,12", 6);
+    let options = TouchSearchOptions::new().max_length(72);
+
+    let touches: Vec<Composition> = TouchSearch::new(method, calls, &options).unwrap().collect();
+    let callings: Vec<&str> = touches.iter().map(|touch| touch.get_calling()).collect();
+
+    assert!(callings.contains(&"---"));
+    assert!(callings.contains(&"ppppp"));
+    assert!(
+      touches
+        .iter()
+        .all(|touch| touch.is_true() && touch.comes_round() && touch.get_length() <= 72)
+    );
+  }
+
+  #[test]
+  fn keeps_to_the_length_range() {
+    let (method, calls) = plain_bob("x16x16x16,12", 6);
+    let options = TouchSearchOptions::new().min_length(48).max_length(60);
+
+    let lengths: HashSet<usize> = TouchSearch::new(method, calls, &options)
+      .unwrap()
+      .map(|touch| touch.get_length())
+      .collect();
+
+    assert!(lengths.contains(&60));
+    assert!(lengths.iter().all(|length| (48..=60).contains(length)));
+  }
+
+  #[test]
+  fn finds_extents() {
+    let (method, calls) = plain_bob("x14x14,12", 4);
+    let options = TouchSearchOptions::new().extents_only(true);
+
+    let touches: Vec<Composition> = TouchSearch::new(method, calls, &options).unwrap().collect();
+
+    assert!(touches.iter().any(|touch| touch.get_calling() == "ppp"));
+    assert!(
+      touches
+        .iter()
+        .all(|touch| touch.is_true() && touch.comes_round() && touch.get_length() == 24)
+    );
+  }
+
+  #[test]
+  fn finds_nothing_when_no_touch_fits() {
+    let (method, calls) = plain_bob("x16x16x16,12", 6);
+    let options = TouchSearchOptions::new().min_length(13).max_length(23);
+
+    assert_eq!(
+      TouchSearch::new(method, calls, &options).unwrap().count(),
+      0
+    );
+  }
+
+  #[test]
+  fn rejects_calls_that_do_not_fit_the_method() {
+    let method = Method::from_place_notation("x14x14,12", 4).unwrap();
+    let too_long = Call::new('-', "x14x14x14x14x", 4).unwrap();
+    let wrong_stage = Call::new('s', "14", 6).unwrap();
+    let options = TouchSearchOptions::new();
+
+    assert_eq!(
+      TouchSearch::new(method.clone(), vec![too_long], &options).err(),
+      Some(CompositionError::CallTooLong('-'))
+    );
+    assert_eq!(
+      TouchSearch::new(method, vec![wrong_stage], &options).err(),
+      Some(CompositionError::WrongNumberOfBells('s'))
+    );
+  }
+
+  #[test]
+  fn rejects_stages_too_big_to_rank() {
+    let (method, calls) = plain_bob("x1Lx1L,12", 22);
+
+    assert_eq!(
+      TouchSearch::new(method, calls, &TouchSearchOptions::new()).err(),
+      Some(CompositionError::TooManyBells(22))
+    );
+    assert_eq!(
+      CompositionError::TooManyBells(22).to_string(),
+      "touches are searched on at most 20 bells, not 22"
+    );
+  }
+}
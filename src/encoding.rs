@@ -0,0 +1,236 @@
+//! Compact encodings of methods for storing or sending between processes.
+//!
+//! A method's rows can be written as their lexicographic ranks, two bytes a
+//! row up to 8 bells, or as its changes, one byte each: the position of the
+//! next row among the rows the graph links the current one to. As rounds is
+//! never one of those, a position one past the end stands for coming back to
+//! rounds. Changes are only meaningful with the graph they came from.
+
+use std::{error::Error, fmt, sync::Arc};
+
+use crate::{
+  PermutationGraph,
+  method::get_rounds,
+  permutation_graph::{
+    permutation_node::{PermutationNode, StrongNodeVector, is_valid_change},
+    utility::{lexicographic_rank, row_from_lexicographic_rank},
+  },
+};
+
+/// The most bells whose row ranks fit in a `u16`.
+pub const MAX_RANK_BELLS: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub enum EncodingError {
+  NotFromRounds,
+  NotAChange(usize),
+  TooManyChanges(usize),
+  ChangeOutOfRange(usize),
+  NoRounds,
+}
+
+impl fmt::Display for EncodingError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      EncodingError::NotFromRounds => write!(f, "method does not start from rounds"),
+      EncodingError::NotAChange(index) => {
+        write!(f, "rows {index} and {} are not one change apart", index + 1)
+      }
+      EncodingError::TooManyChanges(index) => {
+        write!(f, "row {index} has too many changes to fit in a byte")
+      }
+      EncodingError::ChangeOutOfRange(index) => {
+        write!(f, "change {index} is not one of the row's changes")
+      }
+      EncodingError::NoRounds => write!(f, "graph has no rounds to start from"),
+    }
+  }
+}
+
+impl Error for EncodingError {}
+
+/// Each row as its lexicographic rank.
+pub fn encode_ranks<R: AsRef<[u8]>>(rows: &[R]) -> Vec<u16> {
+  rows
+    .iter()
+    .map(|row| {
+      let row = row.as_ref();
+      if row.len() > MAX_RANK_BELLS {
+        panic!("Row ranks only fit in a u16 up to {MAX_RANK_BELLS} bells");
+      }
+      lexicographic_rank(row) as u16
+    })
+    .collect()
+}
+
+/// The rows on `bells_amount` bells with the given lexicographic ranks.
+pub fn decode_ranks(ranks: &[u16], bells_amount: usize) -> Vec<Vec<u8>> {
+  ranks
+    .iter()
+    .map(|&rank| row_from_lexicographic_rank(rank as usize, bells_amount))
+    .collect()
+}
+
+/// Each change of a method starting from rounds, as the position of the
+/// next row among the current row's neighbours.
+pub fn encode_changes(method: &[Arc<PermutationNode>]) -> Result<Vec<u8>, EncodingError> {
+  if method.first().is_some_and(|node| !node.is_rounds()) {
+    return Err(EncodingError::NotFromRounds);
+  }
+
+  method
+    .windows(2)
+    .enumerate()
+    .map(|(index, pair)| {
+      let (current, next) = (&pair[0], &pair[1]);
+      let neighbours = current.get_valid_permutations();
+
+      let position = if next.is_rounds() {
+        (!current.is_rounds() && is_valid_change(current.get_permutation(), next.get_permutation()))
+          .then_some(neighbours.len())
+      } else {
+        neighbours
+          .iter()
+          .position(|neighbour| neighbour.as_ptr() == Arc::as_ptr(next))
+      };
+
+      let position = position.ok_or(EncodingError::NotAChange(index))?;
+      u8::try_from(position).map_err(|_| EncodingError::TooManyChanges(index))
+    })
+    .collect()
+}
+
+/// The method rung by following `changes` from rounds through `graph`.
+pub fn decode_changes(
+  graph: &PermutationGraph,
+  changes: &[u8],
+) -> Result<StrongNodeVector, EncodingError> {
+  let rounds = graph
+    .get_node(&get_rounds(graph.get_bells_amount()))
+    .ok_or(EncodingError::NoRounds)?;
+  let mut method = Vec::with_capacity(changes.len() + 1);
+  method.push(rounds.clone());
+
+  for (index, &change) in changes.iter().enumerate() {
+    let current = method.last().expect("Methods start with rounds");
+    let neighbours = current.get_valid_permutations();
+    let change = change as usize;
+
+    let next = if change == neighbours.len()
+      && !current.is_rounds()
+      && is_valid_change(current.get_permutation(), rounds.get_permutation())
+    {
+      Some(rounds.clone())
+    } else {
+      neighbours
+        .get(change)
+        .and_then(|neighbour| neighbour.upgrade())
+    };
+
+    method.push(next.ok_or(EncodingError::ChangeOutOfRange(index))?);
+  }
+
+  Ok(method)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::{MethodFilter, SearchOptions};
+
+  fn search_options() -> SearchOptions {
+    SearchOptions::new().filter(MethodFilter::NoLongPlaces.and(MethodFilter::RightPlace))
+  }
+
+  #[test]
+  fn ranks_round_trip_search_results() {
+    let graph = PermutationGraph::new(4);
+    let methods: Vec<StrongNodeVector> = graph.search(&search_options()).collect();
+    assert!(!methods.is_empty());
+
+    for method in methods {
+      let rows = crate::get_rows(&method);
+      let ranks = encode_ranks(&rows);
+
+      assert_eq!(ranks[0], 0);
+      assert_eq!(decode_ranks(&ranks, 4), rows);
+    }
+  }
+
+  #[test]
+  fn changes_round_trip_search_results() {
+    for (graph, options) in [
+      (PermutationGraph::new(3), SearchOptions::new()),
+      (PermutationGraph::new(4), search_options()),
+      (
+        PermutationGraph::with_fixed_bells(4, &[4]),
+        SearchOptions::new(),
+      ),
+    ] {
+      let methods: Vec<StrongNodeVector> = graph.search(&options).collect();
+      assert!(!methods.is_empty());
+
+      for method in methods {
+        let changes = encode_changes(&method).unwrap();
+        let decoded = decode_changes(&graph, &changes).unwrap();
+
+        assert_eq!(changes.len(), method.len() - 1);
+        assert!(
+          decoded
+            .iter()
+            .zip(method.iter())
+            .all(|(decoded, node)| Arc::ptr_eq(decoded, node))
+        );
+        assert_eq!(decoded.len(), method.len());
+      }
+    }
+  }
+
+  #[test]
+  fn encodes_the_way_back_to_rounds() {
+    let graph = PermutationGraph::new(3);
+    let method = graph.search(&SearchOptions::new()).next().unwrap();
+    let changes = encode_changes(&method).unwrap();
+    let last = &method[method.len() - 2];
+
+    assert_eq!(*changes.last().unwrap() as usize, last.get_degree());
+  }
+
+  #[test]
+  fn rejects_rows_not_a_change_apart() {
+    let graph = PermutationGraph::new(3);
+    let rows = [[1, 2, 3], [2, 1, 3], [3, 2, 1]];
+    let method: StrongNodeVector = rows
+      .iter()
+      .map(|row| graph.get_node(row).unwrap().clone())
+      .collect();
+
+    assert_eq!(encode_changes(&method), Err(EncodingError::NotAChange(1)));
+    assert_eq!(
+      encode_changes(&method[1..]),
+      Err(EncodingError::NotFromRounds)
+    );
+  }
+
+  #[test]
+  fn rejects_unknown_changes() {
+    let graph = PermutationGraph::new(3);
+
+    assert_eq!(
+      decode_changes(&graph, &[0, 9]),
+      Err(EncodingError::ChangeOutOfRange(1))
+    );
+    // Rounds can not follow rounds
+    assert_eq!(
+      decode_changes(&graph, &[2]),
+      Err(EncodingError::ChangeOutOfRange(0))
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "Row ranks only fit in a u16 up to 8 bells")]
+  fn panics_ranking_beyond_eight_bells() {
+    encode_ranks(&[get_rounds(9)]);
+  }
+}
@@ -0,0 +1,424 @@
+//! Which other leads and courses of a method would ring rows of its plain
+//! course or lead again.
+//!
+//! A lead starting from lead head `L` rings `L * r` for each row `r` of the
+//! plain lead, so it shares a row with the plain lead whenever
+//! `L = r * s⁻¹` for two of its rows. The same holds for courses and the
+//! plain course.
+//!
+//! On major, a method whose lead heads are those of Plain Bob rings the same
+//! course from `x` as from `x * h` for any of those lead heads `h`, and the
+//! same plain course from any of them. So when the course from `x` is false
+//! so is the course from every `h * x * h'`, and from `x⁻¹`. These sets of
+//! course heads are the course head groups: a method is false in whole
+//! groups or not at all.
+//!
+//! They are not the 28 standard falseness groups of Treble Dodging Major,
+//! which also join groups that symmetric methods are always false in
+//! together, so they split the course heads more finely and are lettered in
+//! the order they are found rather than as in the CCCBR tables.
+
+use std::{collections::BTreeSet, error::Error, fmt};
+
+use permutations::Permutations;
+
+use crate::{method::Method, row::Row};
+
+/// Tenors kept at home in the course heads of falseness tables on major,
+/// i.e. the 7th and 8th.
+pub const MAJOR_TENORS: usize = 2;
+
+const MAJOR_BELLS: usize = 8;
+const PLAIN_BOB_MAJOR_LEAD_HEAD: [u8; MAJOR_BELLS] = [1, 3, 5, 2, 7, 4, 8, 6];
+
+#[derive(Debug, PartialEq)]
+pub enum FalsenessError {
+  NotMajor(usize),
+  NotPlainBobLeadHead(Row),
+}
+
+impl fmt::Display for FalsenessError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      FalsenessError::NotMajor(bells_amount) => {
+        write!(f, "course head groups are for 8 bells, not {bells_amount}")
+      }
+      FalsenessError::NotPlainBobLeadHead(lead_head) => {
+        write!(f, "lead head {lead_head} is not a Plain Bob lead head")
+      }
+    }
+  }
+}
+
+impl Error for FalsenessError {}
+
+/// Course heads on major, with the treble and tenors at home, which are
+/// always false together.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CourseHeadGroup {
+  name: char,
+  course_heads: Vec<Row>,
+}
+
+impl CourseHeadGroup {
+  /// A capital letter for in-course groups and a small letter for
+  /// out-of-course ones, given in the order of the groups' first course heads
+  /// rather than the letters of the standard falseness groups.
+  pub fn get_name(&self) -> char {
+    self.name
+  }
+
+  /// Every course head of the group, sorted. The first one defines it.
+  pub fn get_course_heads(&self) -> &[Row] {
+    &self.course_heads
+  }
+
+  pub fn is_in_course(&self) -> bool {
+    self.course_heads[0].is_in_course()
+  }
+}
+
+/// Lead heads, other than rounds, whose lead rings a row of the plain lead.
+/// Rounds is included when the plain lead rings a row twice.
+pub fn get_false_lead_heads(method: &Method) -> Vec<Row> {
+  get_false_heads(&method.get_lead_rows(), |_| true)
+}
+
+/// Course heads, other than rounds, whose course rings a row of the plain
+/// course, keeping the treble and the last `tenors` bells at home as
+/// falseness tables do. Rounds is included when the plain course is false.
+pub fn get_false_course_heads(method: &Method, tenors: usize) -> Vec<Row> {
+  let fixed_from = method.get_bells_amount().saturating_sub(tenors);
+
+  get_false_heads(&method.get_plain_course(), |head| {
+    is_course_head(head, fixed_from)
+  })
+}
+
+fn is_course_head(head: &Row, fixed_from: usize) -> bool {
+  let bells = head.get_bells();
+  bells.first().is_none_or(|&bell| bell == 1)
+    && bells[fixed_from..]
+      .iter()
+      .enumerate()
+      .all(|(index, &bell)| bell as usize == fixed_from + index + 1)
+}
+
+fn get_false_heads<F>(rows: &[Vec<u8>], keep: F) -> Vec<Row>
+where
+  F: Fn(&Row) -> bool,
+{
+  let rows: Vec<Row> = rows
+    .iter()
+    .map(|row| Row::new(row.clone()).expect("Methods ring whole rows"))
+    .collect();
+  let inverses: Vec<Row> = rows.iter().map(Row::inverse).collect();
+  let mut heads = BTreeSet::new();
+
+  for (index, row) in rows.iter().enumerate() {
+    for (inverse_index, inverse) in inverses.iter().enumerate() {
+      if index == inverse_index {
+        continue;
+      }
+
+      let head = row * inverse;
+      if keep(&head) {
+        heads.insert(head);
+      }
+    }
+  }
+
+  heads.into_iter().collect()
+}
+
+/// Rounds and every lead head of Plain Bob Major, which with either a 2nds
+/// or an 8ths place lead end are the powers of the same row.
+fn get_plain_bob_lead_heads() -> Vec<Row> {
+  let lead_head =
+    Row::new(PLAIN_BOB_MAJOR_LEAD_HEAD.to_vec()).expect("Plain Bob's lead head is a row");
+  let mut lead_heads = vec![Row::rounds(MAJOR_BELLS)];
+
+  loop {
+    let next = lead_heads.last().expect("Lead heads start with rounds") * &lead_head;
+    if next.is_rounds() {
+      return lead_heads;
+    }
+    lead_heads.push(next);
+  }
+}
+
+/// Every course head group on major, splitting all the course heads other
+/// than rounds with the treble and tenors at home.
+pub fn get_major_course_head_groups() -> Vec<CourseHeadGroup> {
+  let lead_heads = get_plain_bob_lead_heads();
+  let moving_bells = MAJOR_BELLS - 1 - MAJOR_TENORS;
+  let course_heads: BTreeSet<Row> = Permutations::new(moving_bells)
+    .iter()
+    .map(|permutation| {
+      let moving = Row::from(&permutation);
+      let mut bells = vec![1];
+      bells.extend(moving.get_bells().iter().map(|bell| bell + 1));
+      bells.extend(MAJOR_BELLS as u8 - MAJOR_TENORS as u8 + 1..=MAJOR_BELLS as u8);
+      Row::new(bells).expect("Moving bells fill the places between the treble and tenors")
+    })
+    .filter(|head| !head.is_rounds())
+    .collect();
+
+  let mut remaining = course_heads.clone();
+  let mut names = (b'A'..=b'Z', b'a'..=b'z');
+  let mut groups = Vec::new();
+
+  while let Some(head) = remaining.pop_first() {
+    let mut group = BTreeSet::new();
+    for false_head in [head.inverse(), head.clone()] {
+      for left in lead_heads.iter() {
+        for right in lead_heads.iter() {
+          let member = &(left * &false_head) * right;
+          if course_heads.contains(&member) {
+            remaining.remove(&member);
+            group.insert(member);
+          }
+        }
+      }
+    }
+
+    let name = match head.is_in_course() {
+      true => names.0.next(),
+      false => names.1.next(),
+    };
+    groups.push(CourseHeadGroup {
+      name: name.expect("Major has fewer groups of each parity than letters") as char,
+      course_heads: group.into_iter().collect(),
+    });
+  }
+
+  groups
+}
+
+/// The course head groups a major method with Plain Bob lead heads is false
+/// in, with the tenors together. A false plain course is not in any group.
+pub fn get_course_head_groups(method: &Method) -> Result<Vec<CourseHeadGroup>, FalsenessError> {
+  if method.get_bells_amount() != MAJOR_BELLS {
+    return Err(FalsenessError::NotMajor(method.get_bells_amount()));
+  }
+
+  let lead_head = Row::new(method.get_lead_head()).expect("Methods ring whole rows");
+  if lead_head.is_rounds() || !get_plain_bob_lead_heads().contains(&lead_head) {
+    return Err(FalsenessError::NotPlainBobLeadHead(lead_head));
+  }
+
+  let false_course_heads: BTreeSet<Row> = get_false_course_heads(method, MAJOR_TENORS)
+    .into_iter()
+    .collect();
+
+  Ok(
+    get_major_course_head_groups()
+      .into_iter()
+      .filter(|group| {
+        group
+          .course_heads
+          .iter()
+          .any(|head| false_course_heads.contains(head))
+      })
+      .collect(),
+  )
+}
+
+/// Whether no other course with the tenors at home shares a row with the
+/// plain course, so any calling of whole courses that keeps the tenors
+/// together is true.
+pub fn is_clean_proof(method: &Method, tenors: usize) -> bool {
+  get_false_course_heads(method, tenors).is_empty()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  const CAMBRIDGE: &str = "x38x14x1258x36x14x58x16x78,12";
+  const BRISTOL: &str = "x58x14.58x58.36.14x14.58x14x18,18";
+
+  fn major(notation: &str) -> Method {
+    Method::from_place_notation(notation, 8).unwrap()
+  }
+
+  fn row(symbols: &str) -> Row {
+    symbols.parse().unwrap()
+  }
+
+  fn rings_a_shared_row(lead_head: &Row, rows: &[Vec<u8>]) -> bool {
+    rows.iter().any(|shared| {
+      let shared = Row::new(shared.clone()).unwrap();
+      rows.contains((lead_head * &shared).get_bells())
+    })
+  }
+
+  #[test]
+  fn finds_the_false_lead_heads_of_plain_bob_minimus() {
+    let method = Method::from_place_notation("x14x14,12", 4).unwrap();
+    let lead_rows = method.get_lead_rows();
+    let false_lead_heads = get_false_lead_heads(&method);
+
+    assert!(!false_lead_heads.contains(&row("1234")));
+    assert!(false_lead_heads.contains(&row("2143")));
+
+    for head in false_lead_heads.iter() {
+      assert!(rings_a_shared_row(head, &lead_rows));
+    }
+  }
+
+  #[test]
+  fn finds_every_false_lead_head() {
+    let method = Method::from_place_notation("x16x16x16,12", 6).unwrap();
+    let lead_rows = method.get_lead_rows();
+    let false_lead_heads = get_false_lead_heads(&method);
+
+    for head in method.get_plain_course().into_iter().skip(1) {
+      let head = Row::new(head).unwrap();
+      assert_eq!(
+        false_lead_heads.contains(&head),
+        rings_a_shared_row(&head, &lead_rows)
+      );
+    }
+  }
+
+  #[test]
+  fn keeps_the_tenors_home_in_false_course_heads() {
+    let cambridge = major(CAMBRIDGE);
+    let course = cambridge.get_plain_course();
+    let false_course_heads = get_false_course_heads(&cambridge, MAJOR_TENORS);
+
+    assert!(!false_course_heads.is_empty());
+    assert!(!false_course_heads.contains(&Row::rounds(8)));
+
+    for head in false_course_heads.iter() {
+      assert_eq!(head.get_bells()[0], 1);
+      assert_eq!(head.get_bells()[6..], [7, 8]);
+      assert!(rings_a_shared_row(head, &course));
+    }
+  }
+
+  #[test]
+  fn reports_a_false_plain_course() {
+    let method = Method::from_place_notation("x14x14x14x14,14", 4).unwrap();
+
+    assert!(get_false_course_heads(&method, 0).contains(&Row::rounds(4)));
+  }
+
+  #[test]
+  fn can_tell_clean_proof_methods() {
+    let plain_bob_major = major("x18x18x18x18,12");
+
+    assert!(is_clean_proof(&plain_bob_major, MAJOR_TENORS));
+    assert!(!is_clean_proof(&major(CAMBRIDGE), MAJOR_TENORS));
+    assert_eq!(get_course_head_groups(&plain_bob_major), Ok(Vec::new()));
+  }
+
+  #[test]
+  fn splits_every_major_course_head_into_groups() {
+    let groups = get_major_course_head_groups();
+    let course_heads: Vec<&Row> = groups
+      .iter()
+      .flat_map(|group| group.get_course_heads())
+      .collect();
+    let unique: BTreeSet<&Row> = course_heads.iter().copied().collect();
+
+    // 5! course heads with the treble and tenors at home, less rounds
+    assert_eq!(course_heads.len(), 119);
+    assert_eq!(unique.len(), 119);
+    assert_eq!(
+      groups.iter().filter(|group| group.is_in_course()).count(),
+      24
+    );
+    assert_eq!(groups.len(), 45);
+
+    let names: BTreeSet<char> = groups.iter().map(CourseHeadGroup::get_name).collect();
+    assert_eq!(names.len(), groups.len());
+    assert_eq!(groups[0].get_name(), 'a');
+    assert_eq!(groups[0].get_course_heads()[0], row("12346578"));
+  }
+
+  #[test]
+  fn groups_are_closed_under_lead_heads_and_inverses() {
+    let lead_heads = get_plain_bob_lead_heads();
+
+    for group in get_major_course_head_groups() {
+      let heads = group.get_course_heads();
+      assert!(
+        heads
+          .iter()
+          .all(|head| head.is_in_course() == group.is_in_course())
+      );
+
+      for head in heads {
+        assert!(heads.contains(&head.inverse()));
+        for left in lead_heads.iter() {
+          for right in lead_heads.iter() {
+            let member = &(left * head) * right;
+            assert!(!is_course_head(&member, 6) || heads.contains(&member));
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn methods_are_false_in_whole_groups() {
+    for notation in [CAMBRIDGE, BRISTOL, "x38x14x58x16x12x38x14x78,12"] {
+      let method = major(notation);
+      let false_course_heads = get_false_course_heads(&method, MAJOR_TENORS);
+      let mut grouped: Vec<Row> = get_course_head_groups(&method)
+        .unwrap()
+        .into_iter()
+        .flat_map(|group| group.course_heads)
+        .collect();
+      grouped.sort();
+
+      assert_eq!(grouped, false_course_heads);
+    }
+  }
+
+  #[test]
+  fn finds_the_groups_of_cambridge_and_bristol() {
+    let cambridge = get_course_head_groups(&major(CAMBRIDGE)).unwrap();
+    let bristol = get_course_head_groups(&major(BRISTOL)).unwrap();
+
+    assert_eq!(cambridge.len(), 9);
+    assert!(
+      cambridge
+        .iter()
+        .any(|group| group.get_course_heads().contains(&row("13245678")))
+    );
+
+    assert_eq!(bristol.len(), 1);
+    assert_eq!(
+      bristol[0].get_course_heads(),
+      ["13524678", "13625478", "14253678", "14265378"].map(row)
+    );
+    assert!(!bristol[0].is_in_course());
+    // Yorkshire shares Bristol's group
+    assert!(
+      get_course_head_groups(&major("x38x14x58x16x12x38x14x78,12"))
+        .unwrap()
+        .contains(&bristol[0])
+    );
+  }
+
+  #[test]
+  fn only_groups_major_methods_with_plain_bob_lead_heads() {
+    let minor = Method::from_place_notation("x16x16x16,12", 6).unwrap();
+
+    assert_eq!(
+      get_course_head_groups(&minor),
+      Err(FalsenessError::NotMajor(6))
+    );
+    assert_eq!(
+      get_course_head_groups(&major("x18x18x18x18,14")),
+      Err(FalsenessError::NotPlainBobLeadHead(row("12357486")))
+    );
+    assert_eq!(
+      get_course_head_groups(&major("x18x18x18x18,18")),
+      Err(FalsenessError::NotPlainBobLeadHead(Row::rounds(8)))
+    );
+  }
+}
@@ -0,0 +1,261 @@
+use std::{
+  collections::BTreeSet,
+  io::{Result, Write},
+  sync::Arc,
+};
+
+use crate::{
+  PermutationGraph, PermutationNode,
+  place_notation::{get_place_notation, row_to_string},
+};
+
+struct ExportEdge {
+  source: usize,
+  target: usize,
+  place_notation: String,
+  is_highlighted: bool,
+}
+
+/// Writes the graph as an undirected Graphviz DOT graph, with nodes labelled
+/// by row and edges by the place notation of the change. Rows and changes
+/// along `highlight` are drawn in red.
+pub fn write_dot(
+  graph: &PermutationGraph,
+  highlight: Option<&[Arc<PermutationNode>]>,
+  out: &mut impl Write,
+) -> Result<()> {
+  let highlighted_nodes = get_highlighted_nodes(highlight);
+
+  writeln!(out, "graph changes {{")?;
+
+  for node in graph.get_nodes().iter() {
+    let rank = node.get_rank();
+    let row = row_to_string(node.get_permutation());
+
+    if highlighted_nodes.contains(&rank) {
+      writeln!(out, "  n{rank} [label=\"{row}\", color=red, penwidth=2];")?;
+    } else {
+      writeln!(out, "  n{rank} [label=\"{row}\"];")?;
+    }
+  }
+
+  for edge in collect_edges(graph, highlight) {
+    let ExportEdge {
+      source,
+      target,
+      place_notation,
+      is_highlighted,
+    } = edge;
+
+    if is_highlighted {
+      writeln!(
+        out,
+        "  n{source} -- n{target} [label=\"{place_notation}\", color=red, penwidth=2];"
+      )?;
+    } else {
+      writeln!(
+        out,
+        "  n{source} -- n{target} [label=\"{place_notation}\"];"
+      )?;
+    }
+  }
+
+  writeln!(out, "}}")
+}
+
+/// Writes the graph as undirected GraphML, with a `row` attribute on nodes,
+/// a `place_notation` attribute on edges and a `highlighted` attribute on
+/// both marking the rows and changes along `highlight`.
+pub fn write_graphml(
+  graph: &PermutationGraph,
+  highlight: Option<&[Arc<PermutationNode>]>,
+  out: &mut impl Write,
+) -> Result<()> {
+  let highlighted_nodes = get_highlighted_nodes(highlight);
+
+  writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+  writeln!(
+    out,
+    "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+  )?;
+  writeln!(
+    out,
+    "  <key id=\"row\" for=\"node\" attr.name=\"row\" attr.type=\"string\"/>"
+  )?;
+  writeln!(
+    out,
+    "  <key id=\"place_notation\" for=\"edge\" attr.name=\"place_notation\" attr.type=\"string\"/>"
+  )?;
+  writeln!(
+    out,
+    "  <key id=\"highlighted\" for=\"all\" attr.name=\"highlighted\" attr.type=\"boolean\">"
+  )?;
+  writeln!(out, "    <default>false</default>")?;
+  writeln!(out, "  </key>")?;
+  writeln!(out, "  <graph id=\"changes\" edgedefault=\"undirected\">")?;
+
+  for node in graph.get_nodes().iter() {
+    let rank = node.get_rank();
+    let row = row_to_string(node.get_permutation());
+
+    write!(
+      out,
+      "    <node id=\"n{rank}\"><data key=\"row\">{row}</data>"
+    )?;
+    if highlighted_nodes.contains(&rank) {
+      write!(out, "<data key=\"highlighted\">true</data>")?;
+    }
+    writeln!(out, "</node>")?;
+  }
+
+  for edge in collect_edges(graph, highlight) {
+    let ExportEdge {
+      source,
+      target,
+      place_notation,
+      is_highlighted,
+    } = edge;
+
+    write!(
+      out,
+      "    <edge source=\"n{source}\" target=\"n{target}\"><data key=\"place_notation\">{place_notation}</data>"
+    )?;
+    if is_highlighted {
+      write!(out, "<data key=\"highlighted\">true</data>")?;
+    }
+    writeln!(out, "</edge>")?;
+  }
+
+  writeln!(out, "  </graph>")?;
+  writeln!(out, "</graphml>")
+}
+
+fn get_highlighted_nodes(highlight: Option<&[Arc<PermutationNode>]>) -> BTreeSet<usize> {
+  highlight
+    .unwrap_or_default()
+    .iter()
+    .map(|node| node.get_rank())
+    .collect()
+}
+
+fn get_highlighted_edges(highlight: Option<&[Arc<PermutationNode>]>) -> BTreeSet<(usize, usize)> {
+  highlight
+    .unwrap_or_default()
+    .windows(2)
+    .map(|pair| get_edge_key(&pair[0], &pair[1]))
+    .collect()
+}
+
+// Changes work in both directions, but rounds is never a valid
+// permutation, so edges are collected from both ends and deduplicated
+fn collect_edges(
+  graph: &PermutationGraph,
+  highlight: Option<&[Arc<PermutationNode>]>,
+) -> Vec<ExportEdge> {
+  let highlighted_edges = get_highlighted_edges(highlight);
+  let mut edge_keys = BTreeSet::new();
+
+  for node in graph.get_nodes().iter() {
+    for neighbour in node.get_neighbours().iter() {
+      edge_keys.insert(get_edge_key(node, neighbour));
+    }
+  }
+
+  let nodes = graph.get_nodes();
+
+  edge_keys
+    .into_iter()
+    .map(|(source, target)| ExportEdge {
+      source,
+      target,
+      place_notation: get_place_notation(
+        nodes[source].get_permutation(),
+        nodes[target].get_permutation(),
+      ),
+      is_highlighted: highlighted_edges.contains(&(source, target)),
+    })
+    .collect()
+}
+
+fn get_edge_key(node1: &PermutationNode, node2: &PermutationNode) -> (usize, usize) {
+  let (rank1, rank2) = (node1.get_rank(), node2.get_rank());
+  (rank1.min(rank2), rank1.max(rank2))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  type GraphWriter =
+    fn(&PermutationGraph, Option<&[Arc<PermutationNode>]>, &mut Vec<u8>) -> Result<()>;
+
+  fn get_output(
+    writer: GraphWriter,
+    graph: &PermutationGraph,
+    highlight: Option<&[Arc<PermutationNode>]>,
+  ) -> String {
+    let mut out = Vec::new();
+    writer(graph, highlight, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn can_write_dot() {
+    let graph = PermutationGraph::new(3);
+    let dot = get_output(write_dot, &graph, None);
+
+    assert!(dot.starts_with("graph changes {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("  n0 [label=\"123\"];\n"));
+    assert!(dot.contains("  n5 [label=\"321\"];\n"));
+    assert!(dot.contains("  n0 -- n1 [label=\"1\"];\n"));
+    assert!(dot.contains("  n0 -- n2 [label=\"3\"];\n"));
+    assert_eq!(dot.matches(" -- ").count(), 6);
+  }
+
+  #[test]
+  fn can_highlight_a_method_in_dot() {
+    let graph = PermutationGraph::new(3);
+    let method = graph.get_valid_full_methods().next().unwrap();
+    let dot = get_output(write_dot, &graph, Some(&method));
+
+    assert_eq!(dot.matches("color=red").count(), 12);
+    assert!(dot.contains("  n0 [label=\"123\", color=red, penwidth=2];\n"));
+  }
+
+  #[test]
+  fn can_write_graphml() {
+    let graph = PermutationGraph::new(3);
+    let graphml = get_output(write_graphml, &graph, None);
+
+    assert!(graphml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+    assert!(graphml.ends_with("</graphml>\n"));
+    assert!(graphml.contains("    <node id=\"n3\"><data key=\"row\">231</data></node>\n"));
+    assert!(graphml.contains(
+      "    <edge source=\"n0\" target=\"n1\"><data key=\"place_notation\">1</data></edge>\n"
+    ));
+    assert_eq!(graphml.matches("<node ").count(), 6);
+    assert_eq!(graphml.matches("<edge ").count(), 6);
+  }
+
+  #[test]
+  fn can_highlight_a_method_in_graphml() {
+    let graph = PermutationGraph::new(3);
+    let method = graph.get_valid_full_methods().next().unwrap();
+    let graphml = get_output(write_graphml, &graph, Some(&method));
+
+    assert_eq!(
+      graphml
+        .matches("<data key=\"highlighted\">true</data>")
+        .count(),
+      12
+    );
+  }
+
+  #[test]
+  fn handles_an_empty_graph() {
+    let graph = PermutationGraph::new(0);
+
+    assert_eq!(get_output(write_dot, &graph, None), "graph changes {\n}\n");
+  }
+}
@@ -1,12 +1,75 @@
-use crate::permutation_graph::permutation_node::StrongNodeVector;
+//! Finds every full change ringing method on a number of bells.
+//!
+//! A [`PermutationGraph`] holds every row on the given number of bells, each
+//! linked to the rows that can follow it in one change. Searching the graph
+//! yields full methods: chains of rows starting at rounds, visiting every
+//! other row once and coming back to rounds.
+//!
+//! ```
+//! use rusty_bells::{PermutationGraph, SearchOptions};
+//!
+//! let graph = PermutationGraph::new(3);
+//! let rounds = graph.get_node(&[1, 2, 3]).unwrap();
+//! assert_eq!(rounds.get_neighbours().len(), 2);
+//!
+//! let options = SearchOptions::new().include_reversed(false);
+//! assert_eq!(graph.search(&options).count(), 1);
+//! ```
+
+use std::sync::Arc;
 
 mod perms;
 mod permutation_graph;
 
-pub use permutation_graph::PermutationGraph;
+pub mod audio;
+pub mod blue_line;
+pub mod browser;
+pub mod classification;
+pub mod composition;
+pub mod encoding;
+pub mod falseness;
+pub mod graph_export;
+pub mod method;
+pub mod method_library;
+pub mod method_stream;
+pub mod midi;
+pub mod music;
+pub mod place_notation;
+pub mod result_store;
+pub mod row;
+pub mod service;
+pub mod striking;
+pub mod timing;
+
+#[cfg(test)]
+mod test_helpers;
 
+pub use permutation_graph::{
+  FixedBellError, PermutationGraph,
+  method_filter::{FilterError, MethodFilter},
+  method_iterator::MethodIterator,
+  permutation_node::{PermutationNode, StrongNodeVector, WeakNodeVector, is_valid_change},
+  sampler::{SampleOptions, Sampler},
+  search_options::SearchOptions,
+  treble_path::{TreblePath, TreblePathError},
+};
+
+/// The rows of a method, ready for drawing or notation.
+pub fn get_rows(method: &[Arc<PermutationNode>]) -> Vec<Vec<u8>> {
+  method
+    .iter()
+    .map(|node| node.get_permutation().clone())
+    .collect()
+}
+
+/// Prints a method's index followed by each of its rows.
 pub fn print_method(index: usize, method: &StrongNodeVector) {
-  println!("{index}");
+  print_named_method(&index.to_string(), method);
+}
+
+/// Prints a method's name, such as `Plain Bob Minimus`, followed by each of its rows.
+pub fn print_named_method(name: &str, method: &StrongNodeVector) {
+  println!("{name}");
   for node in method.iter() {
     println!("{:?},", node.get_permutation());
   }
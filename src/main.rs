@@ -1,21 +1,833 @@
-use clap::Parser;
+use std::{
+  cmp::Reverse,
+  error::Error,
+  fs::{self, File},
+  io::{self, BufReader, BufWriter, Write},
+  path::{Path, PathBuf},
+  process::ExitCode,
+  sync::mpsc,
+  thread,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::{Parser, Subcommand, ValueEnum, builder::RangedU64ValueParser, value_parser};
+use rusty_bells::{
+  MethodFilter, PermutationGraph, SampleOptions, Sampler, SearchOptions, TreblePath,
+  audio::{self, AudioOptions},
+  blue_line::{self, BlueLineOptions},
+  browser::{self, BrowsedMethod, Browser},
+  classification::Classification,
+  composition::{Call, TouchSearch, TouchSearchOptions},
+  falseness, graph_export,
+  method::{Method, get_rounds},
+  method_library::{MethodLibrary, NameIndex},
+  method_stream::{self, StreamOptions},
+  midi::{self, MidiOptions},
+  music::MusicScorer,
+  place_notation::row_to_string,
+  result_store::{self, ResultStore, ResultStoreWriter, StoreError, StoreQuery},
+  row::Row,
+  striking::{self, StrikingReport},
+  timing::Timing,
+};
 
 #[derive(Parser)]
 #[command(version)]
 #[command(about = "Calculates all possible full methods given a number of bells")]
 #[command(long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
+  #[arg(required = true)]
+  number_of_bells: Option<usize>,
+
+  /// Directory to write a blue line SVG of each method into
+  #[arg(long)]
+  svg_dir: Option<PathBuf>,
+
+  /// Bell to draw in blue on the SVG blue lines
+  #[arg(long, default_value_t = 2)]
+  working_bell: u8,
+
+  /// Changes in a lead, marking each lead end on the SVG blue lines [default: the length of --treble-path]
+  #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+  lead_length: Option<usize>,
+
+  /// Print methods from the most to the least musical, holding every method found in memory to sort them
+  #[arg(long)]
+  sort_by_music: bool,
+
+  /// Leave out methods scoring less than this for music
+  #[arg(long)]
+  min_music: Option<i32>,
+
+  /// Bells kept in their home places, e.g. --fixed 6 for Doubles with a tenor covering
+  #[arg(long, value_delimiter = ',')]
+  fixed: Vec<u8>,
+
+  /// Places the treble must follow: plain-hunt, treble-bob or a list such as 1,2,2,1
+  #[arg(long)]
+  treble_path: Option<String>,
+
+  /// Only find methods meeting every condition, e.g. max-places=2,no-long-places,right-place,treble-hunts
+  #[arg(long, default_value = "")]
+  filter: MethodFilter,
+
+  /// CCCBR methods library XML used to name the methods found
+  #[arg(long)]
+  library: Option<PathBuf>,
+
+  /// Saved index of method names, built from --library when the file does not exist yet
+  #[arg(long)]
+  name_index: Option<PathBuf>,
+
+  /// WAV file to write the sound of one of the methods into
+  #[arg(long)]
+  wav: Option<PathBuf>,
+
+  /// MIDI file to write one of the methods into
+  #[arg(long)]
+  midi: Option<PathBuf>,
+
+  /// Index of the method to write as audio or MIDI
+  #[arg(long, default_value_t = 0)]
+  audio_method: usize,
+
+  /// Note of the tenor in the audio or MIDI, e.g. C3 or Eb
+  #[arg(long, default_value = "C3")]
+  key: String,
+
+  /// Minutes to ring 5040 changes in the audio or MIDI
+  #[arg(long, default_value_t = 180.0, value_parser = parse_positive)]
+  peal_speed: f64,
+
+  /// Extra gaps between bells before each handstroke row in the audio or MIDI
+  #[arg(long, default_value_t = 1.0, value_parser = parse_non_negative)]
+  handstroke_gap: f64,
+
+  /// MIDI note of each bell, treble first, e.g. 72,71,69,67, instead of the scale up from --key
+  #[arg(long, value_delimiter = ',', value_parser = value_parser!(u8).range(..=127))]
+  notes: Vec<u8>,
+
+  /// Binary result store to write the methods found into, for the stored command to query
+  #[arg(long)]
+  store: Option<PathBuf>,
+
+  /// Write methods to this file, or - for stdout, as they are found, so full methods never queue up in memory (every half method is still held)
+  #[arg(long, conflicts_with_all = [
+    "min_music", "sort_by_music", "store", "svg_dir", "wav", "midi", "library", "name_index",
+  ])]
+  stream: Option<PathBuf>,
+
+  /// Most methods waiting to be written to --stream before the search waits
+  #[arg(long, default_value_t = 64, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+  stream_capacity: usize,
+
+  /// Print this many methods found by random searches instead of searching for every method
+  #[arg(long, conflicts_with_all = [
+    "min_music", "sort_by_music", "store", "svg_dir", "lead_length", "wav", "midi", "stream",
+  ])]
+  sample: Option<usize>,
+
+  /// Seed for --sample, printed to stderr when not given so a run can be repeated
+  #[arg(long)]
+  seed: Option<u64>,
+
+  /// Browse the methods in an interactive terminal browser instead of printing them
+  #[arg(long, conflicts_with_all = [
+    "min_music", "sort_by_music", "store", "svg_dir", "lead_length", "wav", "midi", "stream",
+    "sample",
+  ])]
+  browse: bool,
+
+  /// File the browser saves marked methods to
+  #[arg(long, default_value = "marked_methods.txt")]
+  save: PathBuf,
+
+  #[command(subcommand)]
+  command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Writes the graph of rows and the changes between them
+  ExportGraph {
+    number_of_bells: usize,
+
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+
+    /// File to write to instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Index of a method to highlight the path of
+    #[arg(long)]
+    highlight: Option<usize>,
+
+    /// Bells kept in their home places, e.g. --fixed 6 for Doubles with a tenor covering
+    #[arg(long, value_delimiter = ',')]
+    fixed: Vec<u8>,
+  },
+  /// Searches for true touches of a method that come round at a lead end
+  Touches {
+    number_of_bells: usize,
+
+    /// Place notation of one lead, e.g. x16x16x16,12
+    place_notation: String,
+
+    /// Place notation replacing the end of a lead at a bob, called with '-'
+    #[arg(long)]
+    bob: Option<String>,
+
+    /// Place notation replacing the end of a lead at a single, called with 's'
+    #[arg(long)]
+    single: Option<String>,
+
+    /// Fewest changes in a touch
+    #[arg(long)]
+    min_length: Option<usize>,
+
+    /// Most changes in a touch
+    #[arg(long)]
+    max_length: Option<usize>,
+
+    /// Only find touches ringing every row once
+    #[arg(long)]
+    extent: bool,
+
+    /// MIDI file to write one of the touches into
+    #[arg(long)]
+    midi: Option<PathBuf>,
+
+    /// Index of the touch to write as MIDI
+    #[arg(long, default_value_t = 0)]
+    midi_touch: usize,
+
+    /// Minutes to ring 5040 changes in the MIDI
+    #[arg(long, default_value_t = 180.0, value_parser = parse_positive)]
+    peal_speed: f64,
+
+    /// MIDI note of each bell, treble first, e.g. 72,71,69,67, instead of a major scale up from middle C
+    #[arg(long, value_delimiter = ',', value_parser = value_parser!(u8).range(..=127))]
+    notes: Vec<u8>,
+  },
+  /// Lists the course head groups of a major method with Plain Bob lead heads, or else its false course heads
+  Falseness {
+    number_of_bells: usize,
+
+    /// Place notation of one lead, e.g. x38x14x1258x36x14x58x16x78,12
+    place_notation: String,
+
+    /// Bells at the back kept at home in the course heads
+    #[arg(long, default_value_t = falseness::MAJOR_TENORS)]
+    tenors: usize,
+  },
+  /// Measures the striking of a plain course from recorded strike times
+  Striking {
+    number_of_bells: usize,
+
+    /// Place notation of one lead, e.g. x16x16x16,12
+    place_notation: String,
+
+    /// CSV of bell,milliseconds lines in the order the bells struck
+    strikes: PathBuf,
+
+    /// Rows of rounds rung before the method starts
+    #[arg(long, default_value_t = 0)]
+    rounds: usize,
+  },
+  /// Prints methods from a result store written with --store, most musical first
+  Stored {
+    /// Result store file
+    store: PathBuf,
+
+    /// Only methods of this class: plain, treble-dodging or unclassified
+    #[arg(long)]
+    class: Option<Classification>,
+
+    /// Leave out methods scoring less than this for music
+    #[arg(long)]
+    min_music: Option<i32>,
+
+    /// Leave out methods scoring more than this for music
+    #[arg(long)]
+    max_music: Option<i32>,
+
+    /// Most methods to print
+    #[arg(long)]
+    limit: Option<usize>,
+  },
+}
+
+#[derive(Clone, ValueEnum)]
+enum GraphFormat {
+  Dot,
+  Graphml,
+}
+
+fn parse_positive(value: &str) -> Result<f64, String> {
+  match value.parse::<f64>() {
+    Ok(number) if number > 0.0 && number.is_finite() => Ok(number),
+    Ok(_) => Err("must be a positive number".to_string()),
+    Err(error) => Err(error.to_string()),
+  }
+}
+
+fn parse_non_negative(value: &str) -> Result<f64, String> {
+  match value.parse::<f64>() {
+    Ok(number) if number >= 0.0 && number.is_finite() => Ok(number),
+    Ok(_) => Err("must not be negative".to_string()),
+    Err(error) => Err(error.to_string()),
+  }
+}
+
+/// Plays each bell on its note from --notes, when there are any.
+fn with_notes(
+  options: MidiOptions,
+  notes: Vec<u8>,
+  number_of_bells: usize,
+) -> io::Result<MidiOptions> {
+  match notes.len() {
+    0 => Ok(options),
+    amount if amount == number_of_bells => Ok(options.notes(notes)),
+    amount => Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      format!("--notes gives {amount} notes for {number_of_bells} bells"),
+    )),
+  }
+}
+
+fn main() -> ExitCode {
+  match run(Args::parse()) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(error) => {
+      eprintln!("error: {error}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+  match args.command {
+    Some(Command::ExportGraph {
+      number_of_bells,
+      format,
+      output,
+      highlight,
+      fixed,
+    }) => {
+      PermutationGraph::check_fixed_bells(number_of_bells, &fixed)?;
+      Ok(export_graph(
+        number_of_bells,
+        &fixed,
+        format,
+        output,
+        highlight,
+      )?)
+    }
+    Some(Command::Touches {
+      number_of_bells,
+      place_notation,
+      bob,
+      single,
+      min_length,
+      max_length,
+      extent,
+      midi,
+      midi_touch,
+      peal_speed,
+      notes,
+    }) => {
+      let method = Method::from_place_notation(&place_notation, number_of_bells)?;
+      let mut calls = Vec::new();
+      if let Some(bob) = bob {
+        calls.push(Call::new('-', &bob, number_of_bells)?);
+      }
+      if let Some(single) = single {
+        calls.push(Call::new('s', &single, number_of_bells)?);
+      }
+
+      let mut options = TouchSearchOptions::new().extents_only(extent);
+      if let Some(min_length) = min_length {
+        options = options.min_length(min_length);
+      }
+      if let Some(max_length) = max_length {
+        options = options.max_length(max_length);
+      }
+
+      let midi_options = with_notes(
+        MidiOptions::new().timing(Timing::new().peal_speed(peal_speed)),
+        notes,
+        number_of_bells,
+      )?;
+      for (index, touch) in TouchSearch::new(method, calls, &options)?.enumerate() {
+        println!("{} {}", touch.get_calling(), touch.get_length());
+
+        if let Some(path) = &midi
+          && index == midi_touch
+        {
+          let mut out = BufWriter::new(File::create(path)?);
+          midi::write_midi(touch.get_rows(), &midi_options, &mut out)?;
+          out.flush()?;
+        }
+      }
+
+      Ok(())
+    }
+    Some(Command::Falseness {
+      number_of_bells,
+      place_notation,
+      tenors,
+    }) => {
+      let method = Method::from_place_notation(&place_notation, number_of_bells)?;
+
+      match falseness::get_course_head_groups(&method) {
+        Ok(groups) if tenors == falseness::MAJOR_TENORS => {
+          for group in groups {
+            let heads: Vec<String> = group
+              .get_course_heads()
+              .iter()
+              .map(Row::to_string)
+              .collect();
+            println!("{} {}", group.get_name(), heads.join(" "));
+          }
+        }
+        result => {
+          if let Err(error) = result {
+            eprintln!("{error}, so listing false course heads");
+          }
+          for head in falseness::get_false_course_heads(&method, tenors) {
+            println!("{head}");
+          }
+        }
+      }
+
+      Ok(())
+    }
+    Some(Command::Striking {
+      number_of_bells,
+      place_notation,
+      strikes,
+      rounds,
+    }) => {
+      let method = Method::from_place_notation(&place_notation, number_of_bells)?;
+      let mut rows = vec![get_rounds(number_of_bells); rounds];
+      rows.extend(method.get_plain_course());
+      rows.push(get_rounds(number_of_bells));
+
+      let strikes = striking::read_strikes(BufReader::new(File::open(strikes)?), number_of_bells)?;
+      print_striking_report(&striking::analyse(&strikes, &rows)?);
+
+      Ok(())
+    }
+    Some(Command::Stored {
+      store,
+      class,
+      min_music,
+      max_music,
+      limit,
+    }) => {
+      let mut query = StoreQuery::new();
+      if let Some(class) = class {
+        query = query.classification(class);
+      }
+      if let Some(min_music) = min_music {
+        query = query.min_music(min_music);
+      }
+      if let Some(max_music) = max_music {
+        query = query.max_music(max_music);
+      }
+      if let Some(limit) = limit {
+        query = query.limit(limit);
+      }
+
+      Ok(print_stored_methods(
+        &mut ResultStore::open(store)?,
+        &query,
+      )?)
+    }
+    None => {
+      let number_of_bells = args
+        .number_of_bells
+        .expect("Clap requires the number of bells");
+      PermutationGraph::check_fixed_bells(number_of_bells, &args.fixed)?;
+      if args.store.is_some() {
+        result_store::check_store_bells(number_of_bells)?;
+      }
+      let mut search_options = SearchOptions::new().filter(args.filter);
+      if let Some(treble_path) = args.treble_path {
+        search_options =
+          search_options.treble_path(TreblePath::parse(&treble_path, number_of_bells)?);
+      }
+
+      let library = match args.library {
+        Some(path) => MethodLibrary::from_file(path)?,
+        None => MethodLibrary::default(),
+      };
+      let method_names = match args.name_index {
+        Some(path) if path.exists() => MethodNames::Index(NameIndex::from_file(path)?),
+        Some(path) => {
+          let index = NameIndex::from_library(&library);
+          index.save(path)?;
+          MethodNames::Index(index)
+        }
+        None => MethodNames::Library(library),
+      };
+
+      if args.browse {
+        return Ok(browse_methods(
+          number_of_bells,
+          args.working_bell,
+          search_options,
+          args.fixed,
+          method_names,
+          &args.save,
+        )?);
+      }
+
+      if let Some(path) = args.stream {
+        let stream_options = StreamOptions::new().capacity(args.stream_capacity);
+        let mut out: Box<dyn Write> = if path.as_os_str() == "-" {
+          Box::new(BufWriter::new(io::stdout().lock()))
+        } else {
+          Box::new(BufWriter::new(File::create(path)?))
+        };
+        method_stream::stream_methods(
+          number_of_bells,
+          &args.fixed,
+          &search_options,
+          &stream_options,
+          &mut out,
+        )?;
+        return Ok(());
+      }
+
+      if let Some(amount) = args.sample {
+        let seed = args.seed.unwrap_or_else(|| {
+          let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+          eprintln!("seed: {seed}");
+          seed
+        });
+        print_sampled_methods(
+          number_of_bells,
+          &args.fixed,
+          &search_options,
+          &SampleOptions::new().seed(seed),
+          amount,
+          &method_names,
+        );
+        return Ok(());
+      }
+
+      let mut blue_line_options = BlueLineOptions::new().working_bell(args.working_bell);
+      let lead_length = args.lead_length.or_else(|| {
+        search_options
+          .get_treble_path()
+          .map(|path| path.get_places().len())
+      });
+      if let Some(lead_length) = lead_length {
+        blue_line_options = blue_line_options.lead_length(lead_length);
+      }
+
+      let key = audio::parse_note(&args.key)?;
+      let timing = Timing::new()
+        .peal_speed(args.peal_speed)
+        .handstroke_gap(args.handstroke_gap);
+      let outputs = Outputs {
+        svg_dir: args.svg_dir,
+        blue_line_options,
+        wav: args.wav,
+        audio_method: args.audio_method,
+        audio_options: AudioOptions::new().key(key).timing(timing),
+        store: args.store,
+        midi: args.midi,
+        midi_options: with_notes(
+          MidiOptions::new()
+            .tenor_note(midi::note_number(key))
+            .timing(timing),
+          args.notes,
+          number_of_bells,
+        )?,
+      };
+
+      Ok(print_methods(
+        number_of_bells,
+        &outputs,
+        MusicOptions {
+          sort: args.sort_by_music,
+          min_score: args.min_music,
+        },
+        search_options,
+        &args.fixed,
+        &method_names,
+      )?)
+    }
+  }
+}
+
+enum MethodNames {
+  Library(MethodLibrary),
+  Index(NameIndex),
+}
+
+impl MethodNames {
+  fn name(&self, rows: &[Vec<u8>]) -> Option<String> {
+    match self {
+      MethodNames::Library(library) => library.name(rows),
+      MethodNames::Index(index) => index.name(rows).map(str::to_string),
+    }
+  }
+}
+
+fn print_stored_methods(
+  store: &mut ResultStore<BufReader<File>>,
+  query: &StoreQuery,
+) -> Result<(), StoreError> {
+  for id in store.query(query) {
+    let method = *store.get_method(id).expect("Queries only give stored ids");
+    println!(
+      "{id} {} music: {}",
+      method.get_classification(),
+      method.get_music()
+    );
+    for row in store.get_rows(id)? {
+      println!("{row:?},");
+    }
+  }
+
+  Ok(())
+}
+
+fn print_striking_report(report: &StrikingReport) {
+  println!(
+    "{} rows, {:.1}ms between bells",
+    report.get_rows_amount(),
+    report.get_bell_gap()
+  );
+
+  for mistake in report.get_mistakes() {
+    println!(
+      "row {}: expected {} but rang {}",
+      mistake.get_row_index(),
+      row_to_string(mistake.get_expected()),
+      row_to_string(mistake.get_observed())
+    );
+  }
+
+  for (index, error) in report.get_bell_errors().iter().enumerate() {
+    println!("bell {}: {error:.1}ms RMS error", index + 1);
+  }
+
+  for (stroke, gaps) in [
+    ("handstroke", report.get_handstroke_gaps()),
+    ("backstroke", report.get_backstroke_gaps()),
+  ] {
+    println!(
+      "{stroke} gap: {:.1}ms mean, {:.1}ms standard deviation over {}",
+      gaps.get_mean(),
+      gaps.get_standard_deviation(),
+      gaps.get_count()
+    );
+  }
+}
+
+fn browse_methods(
   number_of_bells: usize,
+  working_bell: u8,
+  search_options: SearchOptions,
+  fixed_bells: Vec<u8>,
+  method_names: MethodNames,
+  save_path: &Path,
+) -> io::Result<()> {
+  let (sender, methods) = mpsc::channel();
+
+  thread::spawn(move || {
+    let graph = PermutationGraph::with_fixed_bells(number_of_bells, &fixed_bells);
+    let music_scorer = MusicScorer::for_stage(number_of_bells);
+
+    for (index, method) in graph.search(&search_options).enumerate() {
+      let rows = rusty_bells::get_rows(&method);
+      let label = method_names
+        .name(&rows)
+        .unwrap_or_else(|| index.to_string());
+      let score = music_scorer.score(&rows);
+
+      if sender.send(BrowsedMethod::new(label, rows, score)).is_err() {
+        break;
+      }
+    }
+  });
+
+  browser::run(Browser::new(working_bell), methods, save_path)
+}
+
+struct Outputs {
+  svg_dir: Option<PathBuf>,
+  blue_line_options: BlueLineOptions,
+  wav: Option<PathBuf>,
+  audio_method: usize,
+  audio_options: AudioOptions,
+  midi: Option<PathBuf>,
+  midi_options: MidiOptions,
+  store: Option<PathBuf>,
+}
+
+struct MusicOptions {
+  sort: bool,
+  min_score: Option<i32>,
+}
+
+fn print_methods(
+  number_of_bells: usize,
+  outputs: &Outputs,
+  music_options: MusicOptions,
+  search_options: SearchOptions,
+  fixed_bells: &[u8],
+  method_names: &MethodNames,
+) -> io::Result<()> {
+  let graph = PermutationGraph::with_fixed_bells(number_of_bells, fixed_bells);
+  let music_scorer = MusicScorer::for_stage(number_of_bells);
+
+  if let Some(dir) = &outputs.svg_dir {
+    fs::create_dir_all(dir)?;
+  }
+  let mut store = match &outputs.store {
+    Some(path) => Some(ResultStoreWriter::create(path, number_of_bells)?),
+    None => None,
+  };
+
+  let scored_methods = graph
+    .search(&search_options)
+    .enumerate()
+    .map(|(index, method)| {
+      let score = music_scorer.score(&rusty_bells::get_rows(&method));
+      (index, method, score)
+    })
+    .filter(|(_, _, score)| music_options.min_score.is_none_or(|min| *score >= min));
+
+  let methods: Box<dyn Iterator<Item = _>> = if music_options.sort {
+    let mut methods: Vec<_> = scored_methods.collect();
+    methods.sort_by_key(|(_, _, score)| Reverse(*score));
+    Box::new(methods.into_iter())
+  } else {
+    Box::new(scored_methods)
+  };
+
+  for (index, method, score) in methods {
+    match method_names.name(&rusty_bells::get_rows(&method)) {
+      Some(name) => rusty_bells::print_named_method(&name, &method),
+      None => rusty_bells::print_method(index, &method),
+    }
+    if music_options.sort || music_options.min_score.is_some() {
+      println!("music: {score}");
+    }
+
+    if let Some(store) = &mut store {
+      store.add(&rusty_bells::get_rows(&method), score)?;
+    }
+
+    if let Some(dir) = &outputs.svg_dir {
+      let mut out = BufWriter::new(File::create(dir.join(format!("method_{index}.svg")))?);
+      blue_line::write_blue_line(
+        &rusty_bells::get_rows(&method),
+        &outputs.blue_line_options,
+        &mut out,
+      )?;
+      out.flush()?;
+    }
+
+    if let Some(path) = &outputs.wav
+      && index == outputs.audio_method
+    {
+      let mut out = BufWriter::new(File::create(path)?);
+      audio::write_audio(
+        &rusty_bells::get_rows(&method),
+        &outputs.audio_options,
+        &mut out,
+      )?;
+      out.flush()?;
+    }
+
+    if let Some(path) = &outputs.midi
+      && index == outputs.audio_method
+    {
+      let mut out = BufWriter::new(File::create(path)?);
+      midi::write_midi(
+        &rusty_bells::get_rows(&method),
+        &outputs.midi_options,
+        &mut out,
+      )?;
+      out.flush()?;
+    }
+  }
+
+  if let Some(store) = store {
+    store.finish()?;
+  }
+
+  Ok(())
 }
 
-fn main() {
-  let args = Args::parse();
+fn print_sampled_methods(
+  number_of_bells: usize,
+  fixed_bells: &[u8],
+  search_options: &SearchOptions,
+  sample_options: &SampleOptions,
+  amount: usize,
+  method_names: &MethodNames,
+) {
+  let sampler = Sampler::new(number_of_bells, fixed_bells, search_options, sample_options);
+  let mut count = 0;
+
+  for (index, method) in sampler.take(amount).enumerate() {
+    // Printed as print_method prints a searched method
+    let name = method_names
+      .name(&method)
+      .unwrap_or_else(|| index.to_string());
+    println!("{name}");
+    for row in method.iter() {
+      println!("{row:?},");
+    }
+    count += 1;
+  }
+
+  if count < amount {
+    eprintln!("Only found {count} of {amount} methods");
+  }
+}
 
-  let graph = rusty_bells::PermutationGraph::new(args.number_of_bells);
+fn export_graph(
+  number_of_bells: usize,
+  fixed_bells: &[u8],
+  format: GraphFormat,
+  output: Option<PathBuf>,
+  highlight: Option<usize>,
+) -> io::Result<()> {
+  let graph = PermutationGraph::with_fixed_bells(number_of_bells, fixed_bells);
+  let highlight_method = match highlight {
+    Some(index) => Some(graph.get_valid_full_methods().nth(index).ok_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("there is no method {index} to highlight"),
+      )
+    })?),
+    None => None,
+  };
 
-  let method_iterator = graph.get_valid_full_methods();
+  let mut out: BufWriter<Box<dyn Write>> = BufWriter::new(match output {
+    Some(path) => Box::new(File::create(path)?),
+    None => Box::new(io::stdout().lock()),
+  });
 
-  for (index, method) in method_iterator.enumerate() {
-    rusty_bells::print_method(index, &method);
+  match format {
+    GraphFormat::Dot => graph_export::write_dot(&graph, highlight_method.as_deref(), &mut out)?,
+    GraphFormat::Graphml => {
+      graph_export::write_graphml(&graph, highlight_method.as_deref(), &mut out)?
+    }
   }
+
+  out.flush()
 }
@@ -0,0 +1,154 @@
+use crate::{
+  place_notation::{PlaceNotationError, Places, apply_change, parse_place_notation},
+  row::Row,
+};
+
+/// A method as the changes rung in one lead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Method {
+  bells_amount: usize,
+  changes: Vec<Places>,
+}
+
+impl Method {
+  pub fn from_place_notation(
+    notation: &str,
+    bells_amount: usize,
+  ) -> Result<Method, PlaceNotationError> {
+    Ok(Method {
+      bells_amount,
+      changes: parse_place_notation(notation, bells_amount)?,
+    })
+  }
+
+  pub fn get_bells_amount(&self) -> usize {
+    self.bells_amount
+  }
+
+  pub fn get_changes(&self) -> &[Places] {
+    &self.changes
+  }
+
+  pub fn get_lead_length(&self) -> usize {
+    self.changes.len()
+  }
+
+  /// Every row of the first lead, from rounds up to but not including the lead head.
+  pub fn get_lead_rows(&self) -> Vec<Vec<u8>> {
+    let mut rows = vec![get_rounds(self.bells_amount)];
+    for places in self.changes[..self.changes.len().saturating_sub(1)].iter() {
+      rows.push(apply_change(
+        rows.last().expect("Rows start with rounds"),
+        places,
+      ));
+    }
+    rows
+  }
+
+  /// Every row rung until the lead heads come back to rounds, rounds
+  /// included once at the start.
+  pub fn get_plain_course(&self) -> Vec<Vec<u8>> {
+    let lead_rows: Vec<Row> = self
+      .get_lead_rows()
+      .into_iter()
+      .map(|row| Row::new(row).expect("Changes only swap bells"))
+      .collect();
+    let lead_head = Row::new(self.get_lead_head()).expect("Changes only swap bells");
+    let mut course_head = Row::rounds(self.bells_amount);
+    let mut rows = Vec::new();
+
+    loop {
+      rows.extend(
+        lead_rows
+          .iter()
+          .map(|row| (&course_head * row).get_bells().clone()),
+      );
+      course_head = &course_head * &lead_head;
+      if course_head.is_rounds() {
+        return rows;
+      }
+    }
+  }
+
+  /// The row reached after one lead from rounds.
+  pub fn get_lead_head(&self) -> Vec<u8> {
+    self
+      .changes
+      .iter()
+      .fold(get_rounds(self.bells_amount), |row, places| {
+        apply_change(&row, places)
+      })
+  }
+}
+
+pub fn get_rounds(bells_amount: usize) -> Vec<u8> {
+  if bells_amount > u8::MAX as usize {
+    panic!("Rows hold at most {} bells", u8::MAX);
+  }
+
+  (1..=bells_amount as u8).collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn can_create_from_place_notation() {
+    let method = Method::from_place_notation("x14x14,12", 4).unwrap();
+
+    assert_eq!(method.get_bells_amount(), 4);
+    assert_eq!(method.get_lead_length(), 8);
+    assert_eq!(method.get_changes()[1], vec![1, 4]);
+  }
+
+  #[test]
+  fn reports_invalid_place_notation() {
+    assert_eq!(
+      Method::from_place_notation("x1?", 4),
+      Err(PlaceNotationError::UnknownSymbol('?'))
+    );
+  }
+
+  #[test]
+  fn can_get_lead_head() {
+    let plain_bob_minor = Method::from_place_notation("x16x16x16,12", 6).unwrap();
+    let plain_bob_minimus = Method::from_place_notation("x14x14,12", 4).unwrap();
+
+    assert_eq!(plain_bob_minor.get_lead_head(), vec![1, 3, 5, 2, 6, 4]);
+    assert_eq!(plain_bob_minimus.get_lead_head(), vec![1, 3, 4, 2]);
+  }
+
+  #[test]
+  fn can_get_lead_rows() {
+    let plain_bob_minimus = Method::from_place_notation("x14x14,12", 4).unwrap();
+    let rows = plain_bob_minimus.get_lead_rows();
+
+    assert_eq!(rows.len(), 8);
+    assert_eq!(rows[0], vec![1, 2, 3, 4]);
+    assert_eq!(rows[7], vec![1, 3, 2, 4]);
+  }
+
+  #[test]
+  fn can_get_plain_course() {
+    let plain_bob_minor = Method::from_place_notation("x16x16x16,12", 6).unwrap();
+    let course = plain_bob_minor.get_plain_course();
+
+    assert_eq!(course.len(), 60);
+    assert_eq!(course[12], vec![1, 3, 5, 2, 6, 4]);
+    assert_eq!(course[59], vec![1, 2, 4, 3, 6, 5]);
+  }
+
+  #[test]
+  fn can_get_rounds() {
+    assert_eq!(get_rounds(4), vec![1, 2, 3, 4]);
+    assert_eq!(get_rounds(0), Vec::<u8>::new());
+    assert_eq!(get_rounds(255).last(), Some(&255));
+  }
+
+  #[test]
+  #[should_panic(expected = "Rows hold at most 255 bells")]
+  fn panics_on_rounds_beyond_255_bells() {
+    get_rounds(256);
+  }
+}
@@ -0,0 +1,394 @@
+//! Naming methods from the Central Council's methods library.
+//!
+//! The library is the XML file published by the CCCBR, in which methods
+//! are grouped into `methodSet`s sharing a stage, and each `method` has a
+//! `title` such as `Plain Bob Minor` and its place notation. A method found
+//! by the search is named when ringing a known method's plain course gives
+//! the same changes, either as written, reversed, reflected or both.
+
+use std::{
+  error::Error,
+  fmt,
+  fs::File,
+  io::{BufRead, BufReader},
+  path::Path,
+};
+
+mod name_index;
+
+use quick_xml::{Reader, escape::unescape, events::Event};
+
+use crate::{
+  method::Method,
+  place_notation::{PlaceNotationError, Places, get_places},
+  row::Row,
+};
+
+pub use name_index::{NameIndex, NameIndexError, method_hash};
+
+#[derive(Debug)]
+pub enum LibraryError {
+  Io(std::io::Error),
+  Xml(quick_xml::Error),
+  InvalidStage(String),
+  MissingField {
+    method: String,
+    field: &'static str,
+  },
+  InvalidNotation {
+    method: String,
+    error: PlaceNotationError,
+  },
+}
+
+impl fmt::Display for LibraryError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      LibraryError::Io(error) => write!(f, "could not read the library: {error}"),
+      LibraryError::Xml(error) => write!(f, "invalid library XML: {error}"),
+      LibraryError::InvalidStage(stage) => write!(f, "'{stage}' is not a stage"),
+      LibraryError::MissingField { method, field } => {
+        write!(f, "method '{method}' has no {field}")
+      }
+      LibraryError::InvalidNotation { method, error } => {
+        write!(f, "method '{method}' has invalid place notation: {error}")
+      }
+    }
+  }
+}
+
+impl Error for LibraryError {}
+
+impl From<std::io::Error> for LibraryError {
+  fn from(error: std::io::Error) -> Self {
+    LibraryError::Io(error)
+  }
+}
+
+impl From<quick_xml::Error> for LibraryError {
+  fn from(error: quick_xml::Error) -> Self {
+    LibraryError::Xml(error)
+  }
+}
+
+/// A method from the library with its full title.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LibraryMethod {
+  title: String,
+  method: Method,
+}
+
+impl LibraryMethod {
+  pub fn get_title(&self) -> &str {
+    &self.title
+  }
+
+  pub fn get_method(&self) -> &Method {
+    &self.method
+  }
+
+  /// The changes of the plain course, if it is `course_length` changes long.
+  fn plain_course_changes(&self, course_length: usize) -> Option<impl Iterator<Item = &Places>> {
+    let lead_head = Row::new(self.method.get_lead_head()).ok()?;
+    let leads = lead_head.order();
+
+    (self.method.get_lead_length() * leads == course_length)
+      .then(|| self.method.get_changes().iter().cycle().take(course_length))
+  }
+}
+
+/// Known methods, looked up by the rows a method rings.
+#[derive(Clone, Debug, Default)]
+pub struct MethodLibrary {
+  methods: Vec<LibraryMethod>,
+}
+
+impl MethodLibrary {
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<MethodLibrary, LibraryError> {
+    Self::from_reader(BufReader::new(File::open(path)?))
+  }
+
+  pub fn from_reader<R: BufRead>(reader: R) -> Result<MethodLibrary, LibraryError> {
+    let mut reader = Reader::from_reader(reader);
+
+    let mut buffer = Vec::new();
+    let mut elements: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut set_stage = None;
+    let mut method = MethodFields::default();
+    let mut methods = Vec::new();
+
+    loop {
+      match reader.read_event_into(&mut buffer)? {
+        Event::Start(element) => {
+          let name = element.local_name().as_ref().to_string();
+          if name == "method" {
+            method = MethodFields::default();
+          }
+          elements.push(name);
+          text.clear();
+        }
+        Event::Text(content) => text.push_str(&content.xml10_content()),
+        Event::GeneralRef(reference) => {
+          let reference = format!("&{};", reference.xml10_content());
+          text.push_str(&unescape(&reference).unwrap_or(reference.as_str().into()));
+        }
+        Event::End(_) => {
+          let name = elements.pop().unwrap_or_default();
+          let in_method = elements.iter().any(|element| element == "method");
+
+          match name.as_str() {
+            "stage" => {
+              let stage = Some(
+                text
+                  .trim()
+                  .parse()
+                  .map_err(|_| LibraryError::InvalidStage(text.clone()))?,
+              );
+              if in_method {
+                method.stage = stage;
+              } else {
+                set_stage = stage;
+              }
+            }
+            "title" if in_method => method.title = Some(text.trim().to_string()),
+            "notation" if in_method => method.notation = Some(text.trim().to_string()),
+            "method" => methods.push(std::mem::take(&mut method).build(set_stage)?),
+            "methodSet" => set_stage = None,
+            _ => {}
+          }
+          text.clear();
+        }
+        Event::Eof => break,
+        _ => {}
+      }
+      buffer.clear();
+    }
+
+    Ok(MethodLibrary { methods })
+  }
+
+  pub fn get_methods(&self) -> &[LibraryMethod] {
+    &self.methods
+  }
+
+  /// The title of the known method ringing the same changes as `rows`,
+  /// which start from rounds and may end with it. Methods matching only
+  /// when reversed or reflected say so after the title.
+  pub fn name<R: AsRef<[u8]>>(&self, rows: &[R]) -> Option<String> {
+    let changes = get_course_changes(rows);
+    let bells_amount = rows.first()?.as_ref().len();
+    let reversed: Vec<Places> = changes.iter().rev().cloned().collect();
+    let variants = [
+      ("", changes.clone()),
+      (" reversed", reversed.clone()),
+      (" reflected", reflect(&changes, bells_amount)),
+      (" reversed and reflected", reflect(&reversed, bells_amount)),
+    ];
+
+    variants.iter().find_map(|(suffix, changes)| {
+      self
+        .methods
+        .iter()
+        .filter(|known| known.method.get_bells_amount() == bells_amount)
+        .find(|known| {
+          known
+            .plain_course_changes(changes.len())
+            .is_some_and(|course| course.eq(changes.iter()))
+        })
+        .map(|known| format!("{}{suffix}", known.title))
+    })
+  }
+}
+
+/// Every change between `rows`, including the one from the last row back
+/// to the first. A last row repeating the first is left out.
+fn get_course_changes<R: AsRef<[u8]>>(rows: &[R]) -> Vec<Places> {
+  let mut rows: Vec<&[u8]> = rows.iter().map(AsRef::as_ref).collect();
+  if rows.len() > 1 && rows.first() == rows.last() {
+    rows.pop();
+  }
+
+  (0..rows.len())
+    .map(|index| get_places(rows[index], rows[(index + 1) % rows.len()]))
+    .collect()
+}
+
+/// The changes rung with the bells' places counted from the back.
+fn reflect(changes: &[Places], bells_amount: usize) -> Vec<Places> {
+  changes
+    .iter()
+    .map(|places| {
+      places
+        .iter()
+        .rev()
+        .map(|&place| bells_amount as u8 + 1 - place)
+        .collect()
+    })
+    .collect()
+}
+
+#[derive(Default)]
+struct MethodFields {
+  title: Option<String>,
+  notation: Option<String>,
+  stage: Option<usize>,
+}
+
+impl MethodFields {
+  fn build(self, set_stage: Option<usize>) -> Result<LibraryMethod, LibraryError> {
+    let title = self.title.ok_or(LibraryError::MissingField {
+      method: String::from("untitled"),
+      field: "title",
+    })?;
+    let missing = |field| LibraryError::MissingField {
+      method: title.clone(),
+      field,
+    };
+    let notation = self.notation.ok_or_else(|| missing("notation"))?;
+    let stage = self.stage.or(set_stage).ok_or_else(|| missing("stage"))?;
+
+    let method = Method::from_place_notation(&notation, stage).map_err(|error| {
+      LibraryError::InvalidNotation {
+        method: title.clone(),
+        error,
+      }
+    })?;
+
+    Ok(LibraryMethod { title, method })
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::test_helpers::plain_course;
+
+  const LIBRARY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<collection xmlns="http://www.cccbr.org.uk/methods/schemas/2007/05/methods">
+  <collectionName>Test methods</collectionName>
+  <methodSet>
+    <properties>
+      <stage>4</stage>
+      <classification>Bob</classification>
+    </properties>
+    <method id="m1">
+      <title>Plain Bob Minimus</title>
+      <name>Plain</name>
+      <notation>-14-14,12</notation>
+    </method>
+    <method id="m2">
+      <title>Reverse Canterbury Pleasure Place Minimus</title>
+      <notation>14-14.34-34,12</notation>
+    </method>
+  </methodSet>
+  <methodSet>
+    <properties>
+      <stage>6</stage>
+    </properties>
+    <method id="m3">
+      <title>Cambridge Surprise Minor</title>
+      <notation>-36-14-12-36.14-14.36,12</notation>
+    </method>
+    <method id="m4">
+      <title>Bells &amp; Whistles Bob Minimus</title>
+      <stage>4</stage>
+      <notation>-12-14,14</notation>
+    </method>
+  </methodSet>
+</collection>"#;
+
+  fn library() -> MethodLibrary {
+    MethodLibrary::from_reader(LIBRARY.as_bytes()).unwrap()
+  }
+
+  #[test]
+  fn can_read_the_library() {
+    let library = library();
+    let titles: Vec<&str> = library
+      .get_methods()
+      .iter()
+      .map(LibraryMethod::get_title)
+      .collect();
+
+    assert_eq!(
+      titles,
+      vec![
+        "Plain Bob Minimus",
+        "Reverse Canterbury Pleasure Place Minimus",
+        "Cambridge Surprise Minor",
+        "Bells & Whistles Bob Minimus"
+      ]
+    );
+    assert_eq!(library.get_methods()[2].get_method().get_bells_amount(), 6);
+    assert_eq!(library.get_methods()[3].get_method().get_bells_amount(), 4);
+    assert_eq!(library.get_methods()[0].get_method().get_lead_length(), 8);
+  }
+
+  #[test]
+  fn reports_invalid_methods() {
+    let no_notation = "<methodSet><stage>4</stage><method><title>A</title></method></methodSet>";
+    let no_stage = "<method><title>A</title><notation>x</notation></method>";
+    let bad_notation = "<method><title>A</title><stage>4</stage><notation>x5</notation></method>";
+
+    assert!(matches!(
+      MethodLibrary::from_reader(no_notation.as_bytes()),
+      Err(LibraryError::MissingField {
+        field: "notation",
+        ..
+      })
+    ));
+    assert!(matches!(
+      MethodLibrary::from_reader(no_stage.as_bytes()),
+      Err(LibraryError::MissingField { field: "stage", .. })
+    ));
+    assert!(matches!(
+      MethodLibrary::from_reader(bad_notation.as_bytes()),
+      Err(LibraryError::InvalidNotation { .. })
+    ));
+    assert!(matches!(
+      MethodLibrary::from_reader("<stage>four</stage>".as_bytes()),
+      Err(LibraryError::InvalidStage(_))
+    ));
+  }
+
+  #[test]
+  fn names_a_plain_course() {
+    let mut rows = plain_course("x14x14,12", 4);
+
+    assert_eq!(
+      library().name(&rows),
+      Some(String::from("Plain Bob Minimus"))
+    );
+    rows.push(vec![1, 2, 3, 4]);
+    assert_eq!(
+      library().name(&rows),
+      Some(String::from("Plain Bob Minimus"))
+    );
+  }
+
+  #[test]
+  fn names_reversed_and_reflected_methods() {
+    let mut reversed = plain_course("-12-14,14", 4);
+    reversed.push(vec![1, 2, 3, 4]);
+    reversed.reverse();
+    let reflected = plain_course("-34-14,14", 4);
+
+    assert_eq!(
+      library().name(&reversed),
+      Some(String::from("Bells & Whistles Bob Minimus reversed"))
+    );
+    assert_eq!(
+      library().name(&reflected),
+      Some(String::from("Bells & Whistles Bob Minimus reflected"))
+    );
+  }
+
+  #[test]
+  fn leaves_unknown_methods_unnamed() {
+    assert_eq!(library().name(&plain_course("34x34.14x12,14", 4)), None);
+    assert_eq!(library().name(&plain_course("x1,12", 3)), None);
+    assert_eq!(library().name::<Vec<u8>>(&[]), None);
+  }
+}
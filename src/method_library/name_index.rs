@@ -0,0 +1,294 @@
+use std::{
+  cmp::Ordering,
+  collections::HashMap,
+  error::Error,
+  fmt,
+  fs::File,
+  io::{self, BufRead, BufReader, BufWriter, Write},
+  path::Path,
+};
+
+use crate::{
+  method_library::{MethodLibrary, get_course_changes, reflect},
+  place_notation::Places,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+const CHANGE_SEPARATOR: u8 = u8::MAX;
+
+#[derive(Debug)]
+pub enum NameIndexError {
+  Io(io::Error),
+  InvalidLine(String),
+}
+
+impl fmt::Display for NameIndexError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      NameIndexError::Io(error) => write!(f, "could not read the name index: {error}"),
+      NameIndexError::InvalidLine(line) => write!(f, "invalid name index line '{line}'"),
+    }
+  }
+}
+
+impl Error for NameIndexError {}
+
+impl From<io::Error> for NameIndexError {
+  fn from(error: io::Error) -> Self {
+    NameIndexError::Io(error)
+  }
+}
+
+/// Names looked up by [`method_hash`], so naming a method costs one hash of
+/// its rows however big the library is.
+///
+/// Saved as one line per name, the hash in hex followed by the name, e.g.
+/// `5f0e5a9b1c3d2e4f Plain Bob Minimus`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NameIndex {
+  names: HashMap<u64, String>,
+}
+
+impl NameIndex {
+  pub fn new() -> NameIndex {
+    NameIndex::default()
+  }
+
+  /// Every method of the library by its plain course, and reflected. A
+  /// method keeps the first name it is given.
+  pub fn from_library(library: &MethodLibrary) -> NameIndex {
+    let mut index = NameIndex::new();
+
+    for known in library.get_methods() {
+      let method = known.get_method();
+      let changes = get_course_changes(&method.get_plain_course());
+      let reflected = reflect(&changes, method.get_bells_amount());
+
+      let bells_amount = method.get_bells_amount();
+      index.insert_changes(&changes, bells_amount, known.get_title());
+      index.insert_changes(
+        &reflected,
+        bells_amount,
+        &format!("{} reflected", known.get_title()),
+      );
+    }
+
+    index
+  }
+
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<NameIndex, NameIndexError> {
+    Self::read(BufReader::new(File::open(path)?))
+  }
+
+  pub fn read<R: BufRead>(input: R) -> Result<NameIndex, NameIndexError> {
+    let mut index = NameIndex::new();
+
+    for line in input.lines() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      let (hash, name) = line
+        .split_once(' ')
+        .and_then(|(hash, name)| Some((u64::from_str_radix(hash, 16).ok()?, name)))
+        .ok_or_else(|| NameIndexError::InvalidLine(line.clone()))?;
+      index.names.insert(hash, name.to_string());
+    }
+
+    Ok(index)
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    self.write(&mut out)?;
+    out.flush()
+  }
+
+  /// Writes the names ordered by hash, so the same index is always written the same way.
+  pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    let mut names: Vec<(&u64, &String)> = self.names.iter().collect();
+    names.sort();
+
+    for (hash, name) in names {
+      writeln!(out, "{hash:016x} {name}")?;
+    }
+
+    Ok(())
+  }
+
+  /// Names the method rung by `rows`, unless it is already named.
+  pub fn insert<R: AsRef<[u8]>>(&mut self, rows: &[R], name: &str) {
+    self.insert_changes(&get_course_changes(rows), stage_of(rows), name);
+  }
+
+  fn insert_changes(&mut self, changes: &[Places], bells_amount: usize, name: &str) {
+    self
+      .names
+      .entry(hash_changes(changes, bells_amount))
+      .or_insert_with(|| name.to_string());
+  }
+
+  pub fn name<R: AsRef<[u8]>>(&self, rows: &[R]) -> Option<&str> {
+    self.names.get(&method_hash(rows)).map(String::as_str)
+  }
+
+  pub fn len(&self) -> usize {
+    self.names.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.names.is_empty()
+  }
+}
+
+/// A hash of the changes rung by `rows` and the number of bells, the same
+/// whichever row the method is started from and whichever way round it is
+/// rung. Stable between runs so it can be saved.
+pub fn method_hash<R: AsRef<[u8]>>(rows: &[R]) -> u64 {
+  hash_changes(&get_course_changes(rows), stage_of(rows))
+}
+
+fn stage_of<R: AsRef<[u8]>>(rows: &[R]) -> usize {
+  rows.first().map_or(0, |row| row.as_ref().len())
+}
+
+/// Starts from the number of bells, as the same changes can be rung on
+/// several stages, e.g. x14x14,12 on 4 and 6 bells.
+fn hash_changes(changes: &[Places], bells_amount: usize) -> u64 {
+  let reversed: Vec<Places> = changes.iter().rev().cloned().collect();
+  let forwards = rotate(changes, least_rotation(changes));
+  let backwards = rotate(&reversed, least_rotation(&reversed));
+  let canonical = forwards.min(backwards);
+
+  let stage = [bells_amount as u8, CHANGE_SEPARATOR];
+  stage
+    .iter()
+    .chain(
+      canonical
+        .iter()
+        .flat_map(|places| places.iter().chain([&CHANGE_SEPARATOR])),
+    )
+    .fold(FNV_OFFSET_BASIS, |hash, &byte| {
+      (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+fn rotate<T>(items: &[T], start: usize) -> Vec<&T> {
+  items[start..].iter().chain(items[..start].iter()).collect()
+}
+
+/// Where the lexicographically least rotation of `items` starts, found in
+/// linear time by comparing two candidate starts and skipping past the
+/// loser's matched prefix.
+fn least_rotation<T: Ord>(items: &[T]) -> usize {
+  let length = items.len();
+  let (mut first, mut second, mut matched) = (0, 1, 0);
+
+  while first < length && second < length && matched < length {
+    match items[(first + matched) % length].cmp(&items[(second + matched) % length]) {
+      Ordering::Equal => matched += 1,
+      Ordering::Greater => {
+        first = (first + matched + 1).max(second + 1);
+        matched = 0;
+      }
+      Ordering::Less => {
+        second = (second + matched + 1).max(first + 1);
+        matched = 0;
+      }
+    }
+  }
+
+  first.min(second).min(length.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::test_helpers::plain_course;
+
+  const LIBRARY: &str = "<methodSet><stage>4</stage>
+    <method><title>Plain Bob Minimus</title><notation>-14-14,12</notation></method>
+    <method><title>Double Court Bob Minimus</title><notation>-14-12,12</notation></method>
+  </methodSet>";
+
+  fn index() -> NameIndex {
+    NameIndex::from_library(&MethodLibrary::from_reader(LIBRARY.as_bytes()).unwrap())
+  }
+
+  #[test]
+  fn finds_the_least_rotation() {
+    assert_eq!(least_rotation(&[3, 1, 2, 1, 1]), 3);
+    assert_eq!(least_rotation(&[2, 2, 2]), 0);
+    assert_eq!(least_rotation(&[1]), 0);
+    assert_eq!(least_rotation::<u8>(&[]), 0);
+  }
+
+  #[test]
+  fn hashes_the_same_from_any_start_either_way_round() {
+    let rows = plain_course("x14x14,12", 4);
+    let hash = method_hash(&rows);
+    let mut rotated = rows.clone();
+    rotated.rotate_left(5);
+    let mut reversed = rows.clone();
+    reversed.reverse();
+
+    assert_eq!(method_hash(&rotated), hash);
+    assert_eq!(method_hash(&reversed), hash);
+    assert_ne!(method_hash(&plain_course("x14x12,12", 4)), hash);
+  }
+
+  #[test]
+  fn hashes_the_same_changes_on_other_stages_apart() {
+    let minimus = plain_course("x14x14,12", 4);
+    let minor = plain_course("x14x14,12", 6);
+
+    assert_eq!(get_course_changes(&minimus), get_course_changes(&minor));
+    assert_ne!(method_hash(&minimus), method_hash(&minor));
+  }
+
+  #[test]
+  fn names_methods_from_the_library() {
+    let index = index();
+    let mut plain_bob = plain_course("x14x14,12", 4);
+    plain_bob.rotate_left(3);
+
+    assert_eq!(index.len(), 4);
+    assert_eq!(index.name(&plain_bob), Some("Plain Bob Minimus"));
+    assert_eq!(
+      index.name(&plain_course("x14x14,34", 4)),
+      Some("Plain Bob Minimus reflected")
+    );
+    assert_eq!(
+      index.name(&plain_course("x14x12,12", 4)),
+      Some("Double Court Bob Minimus")
+    );
+    assert_eq!(index.name(&plain_course("34x34.14x12,14", 4)), None);
+  }
+
+  #[test]
+  fn can_save_and_read_an_index() {
+    let index = index();
+    let mut saved = Vec::new();
+    index.write(&mut saved).unwrap();
+
+    assert_eq!(NameIndex::read(saved.as_slice()).unwrap(), index);
+    assert!(matches!(
+      NameIndex::read("not-hex Plain Bob".as_bytes()),
+      Err(NameIndexError::InvalidLine(_))
+    ));
+  }
+
+  #[test]
+  fn keeps_the_first_name() {
+    let mut index = NameIndex::new();
+    let rows = plain_course("x14x14,12", 4);
+    index.insert(&rows, "Plain Bob Minimus");
+    index.insert(&rows, "Another name");
+
+    assert_eq!(index.name(&rows), Some("Plain Bob Minimus"));
+    assert!(!index.is_empty());
+  }
+}
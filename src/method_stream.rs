@@ -0,0 +1,254 @@
+//! Writing methods to a file or stdout as they are found, so the full
+//! methods found never queue up in memory. The half methods are not
+//! streamed.
+//!
+//! Each method is written as soon as the search yields it. The search's
+//! comparison threads wait on a bounded channel once [`capacity`] methods
+//! are waiting, so when the sink is slower than the search the search waits
+//! rather than queuing methods. Only the full methods are bounded: every half
+//! method is still found and held before pairing starts, so memory still
+//! grows with the number of bells.
+//!
+//! [`capacity`]: StreamOptions::capacity
+
+use std::{
+  io::{self, Write},
+  sync::Arc,
+};
+
+use crate::{PermutationGraph, PermutationNode, SearchOptions};
+
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Settings for [`stream_methods`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamOptions {
+  capacity: usize,
+}
+
+impl StreamOptions {
+  pub fn new() -> StreamOptions {
+    StreamOptions {
+      capacity: DEFAULT_CAPACITY,
+    }
+  }
+
+  /// Most methods waiting to be written before the search waits for the sink.
+  pub fn capacity(mut self, capacity: usize) -> Self {
+    if capacity == 0 {
+      panic!("A stream must hold at least one method");
+    }
+
+    self.capacity = capacity;
+    self
+  }
+
+  pub fn get_capacity(&self) -> usize {
+    self.capacity
+  }
+}
+
+impl Default for StreamOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Searches the rows on `bells_amount` bells and writes each method to `out`
+/// as [`print_method`](crate::print_method) prints it, returning how many
+/// were written.
+pub fn stream_methods(
+  bells_amount: usize,
+  fixed_bells: &[u8],
+  search_options: &SearchOptions,
+  stream_options: &StreamOptions,
+  out: &mut impl Write,
+) -> io::Result<usize> {
+  let graph = PermutationGraph::with_fixed_bells(bells_amount, fixed_bells);
+  write_methods(&graph, search_options, stream_options, out)
+}
+
+fn write_methods(
+  graph: &PermutationGraph,
+  search_options: &SearchOptions,
+  stream_options: &StreamOptions,
+  out: &mut impl Write,
+) -> io::Result<usize> {
+  let search_options = search_options
+    .clone()
+    .channel_capacity(stream_options.capacity);
+
+  let mut count = 0;
+  // Dropping the search on an error stops its comparison threads
+  for method in graph.search(&search_options) {
+    out.write_all(&format_method(count, &method))?;
+    count += 1;
+  }
+  out.flush()?;
+
+  Ok(count)
+}
+
+fn format_method(index: usize, method: &[Arc<PermutationNode>]) -> Vec<u8> {
+  let mut text = format!("{index}\n");
+  for node in method {
+    text.push_str(&format!("{:?},\n", node.get_permutation()));
+  }
+  text.into_bytes()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use std::{thread::sleep, time::Duration};
+
+  use crate::MethodFilter;
+
+  // One comparison thread finds methods in the same order every time
+  fn search_options() -> SearchOptions {
+    SearchOptions::new()
+      .comparison_threads(1)
+      .filter(MethodFilter::NoLongPlaces.and(MethodFilter::RightPlace))
+  }
+
+  fn printed_methods(search_options: &SearchOptions) -> String {
+    let graph = PermutationGraph::new(4);
+    graph
+      .search(search_options)
+      .enumerate()
+      .map(|(index, method)| String::from_utf8(format_method(index, &method)).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn writes_what_the_iterator_finds() {
+    for options in [search_options(), search_options().include_reversed(false)] {
+      let mut out = Vec::new();
+      let count = stream_methods(4, &[], &options, &StreamOptions::new(), &mut out).unwrap();
+
+      assert!(count > 0);
+      assert_eq!(String::from_utf8(out).unwrap(), printed_methods(&options));
+    }
+  }
+
+  struct SlowSink {
+    written: Vec<u8>,
+  }
+
+  impl Write for SlowSink {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+      sleep(Duration::from_millis(2));
+      self.written.extend_from_slice(bytes);
+      Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn waits_for_a_slow_sink() {
+    let options = StreamOptions::new().capacity(1);
+    let mut sink = SlowSink {
+      written: Vec::new(),
+    };
+    stream_methods(4, &[], &search_options(), &options, &mut sink).unwrap();
+
+    assert_eq!(
+      String::from_utf8(sink.written).unwrap(),
+      printed_methods(&search_options())
+    );
+  }
+
+  /// Counts the methods alive each time one is written, by the references
+  /// each holds to rounds, past those held by the half methods.
+  struct CountingSink {
+    rounds: Arc<PermutationNode>,
+    baseline: usize,
+    peak: usize,
+  }
+
+  impl Write for CountingSink {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+      sleep(Duration::from_millis(5));
+      // Each method starts and ends with rounds
+      let alive = (Arc::strong_count(&self.rounds) - self.baseline) / 2;
+      self.peak = self.peak.max(alive);
+      Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn holds_only_a_few_methods_for_a_slow_sink() {
+    let graph = PermutationGraph::new(4);
+    let search_options = SearchOptions::new()
+      .comparison_threads(1)
+      .filter(MethodFilter::RightPlace);
+    let rounds = Arc::clone(graph.get_node(&[1, 2, 3, 4]).unwrap());
+    let half_methods = graph.get_half_methods(&search_options).len();
+
+    for capacity in [1, 2] {
+      let mut sink = CountingSink {
+        rounds: Arc::clone(&rounds),
+        baseline: 0,
+        peak: 0,
+      };
+      sink.baseline = Arc::strong_count(&rounds) + half_methods;
+      let stream_options = StreamOptions::new().capacity(capacity);
+      let count = write_methods(&graph, &search_options, &stream_options, &mut sink).unwrap();
+
+      // The method being written and its buffered reverse, the queued pairs,
+      // and a pair waiting to be sent and a pair being built by the thread
+      let bound = 2 + 2 * capacity + 4;
+      assert!(count > bound);
+      assert!(
+        sink.peak <= bound,
+        "{} methods alive, expected at most {bound}",
+        sink.peak
+      );
+    }
+  }
+
+  struct BrokenSink;
+
+  impl Write for BrokenSink {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+      Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn stops_when_the_sink_fails() {
+    let result = stream_methods(
+      4,
+      &[],
+      &search_options(),
+      &StreamOptions::new().capacity(1),
+      &mut BrokenSink,
+    );
+
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+  }
+
+  #[test]
+  fn has_a_default_capacity() {
+    assert_eq!(StreamOptions::new().get_capacity(), 64);
+    assert_eq!(StreamOptions::default(), StreamOptions::new());
+  }
+
+  #[test]
+  #[should_panic(expected = "A stream must hold at least one method")]
+  fn panics_without_capacity() {
+    StreamOptions::new().capacity(0);
+  }
+}
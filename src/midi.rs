@@ -0,0 +1,234 @@
+//! Writing rows as a Standard MIDI File, one note for each bell in each row.
+//!
+//! The file has a single track with one tick a millisecond, so it can be
+//! loaded into a DAW or ringing simulator as it is.
+
+use std::io::{Result, Write};
+
+use crate::{audio::Scale, timing::Timing};
+
+const TICKS_PER_QUARTER: u16 = 500;
+const MICROSECONDS_PER_QUARTER: u32 = 500_000;
+const MAX_NOTE: u8 = 127;
+const NOTE_ON: u8 = 0x90;
+const NOTE_OFF: u8 = 0x80;
+const VELOCITY: u8 = 100;
+
+/// Settings for the speed and notes of the bells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiOptions {
+  timing: Timing,
+  tenor_note: u8,
+  scale: Scale,
+  notes: Option<Vec<u8>>,
+}
+
+impl MidiOptions {
+  /// The default [`Timing`], and the tenor on middle C with the other bells
+  /// up a major scale.
+  pub fn new() -> MidiOptions {
+    MidiOptions {
+      timing: Timing::new(),
+      tenor_note: 60,
+      scale: Scale::Major,
+      notes: None,
+    }
+  }
+
+  pub fn timing(mut self, timing: Timing) -> Self {
+    self.timing = timing;
+    self
+  }
+
+  /// The MIDI note of the tenor, the other bells climbing the scale above it.
+  pub fn tenor_note(mut self, note: u8) -> Self {
+    if note > MAX_NOTE {
+      panic!("MIDI notes only go up to {MAX_NOTE}");
+    }
+
+    self.tenor_note = note;
+    self
+  }
+
+  pub fn scale(mut self, scale: Scale) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  /// The MIDI note of each bell, treble first, instead of the scale.
+  pub fn notes(mut self, notes: Vec<u8>) -> Self {
+    if notes.iter().any(|&note| note > MAX_NOTE) {
+      panic!("MIDI notes only go up to {MAX_NOTE}");
+    }
+
+    self.notes = Some(notes);
+    self
+  }
+
+  pub fn get_timing(&self) -> &Timing {
+    &self.timing
+  }
+
+  pub fn get_note(&self, bell: u8, bells_amount: usize) -> u8 {
+    match &self.notes {
+      Some(notes) => *notes
+        .get(bell as usize - 1)
+        .unwrap_or_else(|| panic!("No note for bell {bell}")),
+      None => {
+        let note = self.tenor_note as i32 + self.scale.get_semitones(bell, bells_amount);
+        note.min(MAX_NOTE as i32) as u8
+      }
+    }
+  }
+}
+
+impl Default for MidiOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// The MIDI note nearest a frequency in hertz, e.g. 69 for 440.
+pub fn note_number(frequency: f64) -> u8 {
+  let note = 69.0 + 12.0 * (frequency / 440.0).log2();
+  note.round().clamp(0.0, MAX_NOTE as f64) as u8
+}
+
+/// Writes the rows as a format 0 MIDI file, each bell sounding until the
+/// next bell strikes.
+pub fn write_midi(rows: &[Vec<u8>], options: &MidiOptions, out: &mut impl Write) -> Result<()> {
+  let bells_amount = rows.first().map_or(0, Vec::len);
+  let to_ticks = |seconds: f64| (seconds * 1000.0).round() as u32;
+  let note_length = to_ticks(options.timing.get_bell_gap(bells_amount));
+  let mut events: Vec<(u32, [u8; 3])> = Vec::new();
+
+  for (row_index, row) in rows.iter().enumerate() {
+    for (place, &bell) in row.iter().enumerate() {
+      let time = to_ticks(
+        options
+          .timing
+          .get_strike_time(row_index, place, bells_amount),
+      );
+      let note = options.get_note(bell, bells_amount);
+
+      events.push((time, [NOTE_ON, note, VELOCITY]));
+      events.push((time + note_length, [NOTE_OFF, note, 0]));
+    }
+  }
+  // Notes ending as others start are let go first
+  events.sort_by_key(|&(time, [status, _, _])| (time, status != NOTE_OFF));
+
+  let mut track = Vec::new();
+  write_variable_length(0, &mut track);
+  track.extend([0xff, 0x51, 0x03]);
+  track.extend(&MICROSECONDS_PER_QUARTER.to_be_bytes()[1..]);
+
+  let mut last_time = 0;
+  for (time, event) in events {
+    write_variable_length(time - last_time, &mut track);
+    track.extend(event);
+    last_time = time;
+  }
+
+  write_variable_length(0, &mut track);
+  track.extend([0xff, 0x2f, 0x00]);
+
+  out.write_all(b"MThd")?;
+  out.write_all(&6u32.to_be_bytes())?;
+  out.write_all(&0u16.to_be_bytes())?;
+  out.write_all(&1u16.to_be_bytes())?;
+  out.write_all(&TICKS_PER_QUARTER.to_be_bytes())?;
+  out.write_all(b"MTrk")?;
+  out.write_all(&(track.len() as u32).to_be_bytes())?;
+  out.write_all(&track)
+}
+
+/// Writes a number seven bits at a time, most significant first, with the
+/// top bit set on every byte but the last.
+fn write_variable_length(value: u32, out: &mut Vec<u8>) {
+  let mut bytes = vec![(value & 0x7f) as u8];
+  let mut rest = value >> 7;
+  while rest > 0 {
+    bytes.push((rest & 0x7f) as u8 | 0x80);
+    rest >>= 7;
+  }
+
+  out.extend(bytes.iter().rev());
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn variable_length(value: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_variable_length(value, &mut out);
+    out
+  }
+
+  #[test]
+  fn writes_variable_length_numbers() {
+    assert_eq!(variable_length(0), vec![0x00]);
+    assert_eq!(variable_length(0x7f), vec![0x7f]);
+    assert_eq!(variable_length(0x80), vec![0x81, 0x00]);
+    assert_eq!(variable_length(0x3fff), vec![0xff, 0x7f]);
+    assert_eq!(variable_length(0x200000), vec![0x81, 0x80, 0x80, 0x00]);
+  }
+
+  #[test]
+  fn tunes_bells_to_notes() {
+    let options = MidiOptions::new();
+    let tuned = MidiOptions::new().notes(vec![72, 71, 69, 67]);
+
+    assert_eq!(options.get_note(8, 8), 60);
+    assert_eq!(options.get_note(1, 8), 72);
+    assert_eq!(options.get_note(5, 8), 65);
+    assert_eq!(options.scale(Scale::Minor).get_note(6, 8), 63);
+    assert_eq!(tuned.get_note(4, 4), 67);
+  }
+
+  #[test]
+  fn finds_the_nearest_note() {
+    assert_eq!(note_number(440.0), 69);
+    assert_eq!(note_number(261.6), 60);
+    assert_eq!(note_number(1.0), 0);
+  }
+
+  #[test]
+  #[should_panic(expected = "No note for bell 5")]
+  fn panics_without_a_note_for_a_bell() {
+    MidiOptions::new()
+      .notes(vec![64, 62, 60, 59])
+      .get_note(5, 5);
+  }
+
+  #[test]
+  fn writes_a_midi_file() {
+    let options = MidiOptions::new()
+      .timing(Timing::new().peal_speed(2520.0 * 5.0 / 60.0))
+      .notes(vec![62, 60]);
+    let mut out = Vec::new();
+    write_midi(&[vec![1, 2], vec![2, 1]], &options, &mut out).unwrap();
+
+    assert_eq!(&out[0..4], b"MThd");
+    assert_eq!(&out[8..14], &[0, 0, 0, 1, 0x01, 0xf4]);
+    assert_eq!(&out[14..18], b"MTrk");
+    assert_eq!(
+      u32::from_be_bytes(out[18..22].try_into().unwrap()) as usize,
+      out.len() - 22
+    );
+    assert_eq!(
+      &out[22..],
+      &[
+        0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, // tempo
+        0x00, 0x90, 62, 100, // treble at 0ms
+        0x87, 0x68, 0x80, 62, 0, // treble off at 1000ms
+        0x00, 0x90, 60, 100, // second
+        0x87, 0x68, 0x80, 60, 0, // second off at 2000ms
+        0x00, 0x90, 60, 100, // backstroke: second
+        0x87, 0x68, 0x80, 60, 0, 0x00, 0x90, 62, 100, // then treble at 3000ms
+        0x87, 0x68, 0x80, 62, 0, 0x00, 0xff, 0x2f, 0x00 // end of track
+      ]
+    );
+  }
+}
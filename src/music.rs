@@ -0,0 +1,429 @@
+//! Counting musical rows, such as runs, queens and rollups.
+//!
+//! Music is described with patterns written like rows, where `x` or `?`
+//! matches any one bell and `*` matches any number of bells. For example
+//! `*5678` matches every row ending in 5678 and `1234*` every row starting
+//! with 1234.
+
+use std::{error::Error, fmt};
+
+use crate::place_notation::{bell_to_char, char_to_bell};
+
+const ANY_BELL_SYMBOLS: [char; 2] = ['x', '?'];
+const ANY_BELLS_SYMBOL: char = '*';
+const RUN_LENGTH: usize = 4;
+
+#[derive(Debug, PartialEq)]
+pub enum PatternError {
+  UnknownSymbol(char),
+  BellOutOfRange(char),
+}
+
+impl fmt::Display for PatternError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      PatternError::UnknownSymbol(symbol) => write!(f, "unknown pattern symbol '{symbol}'"),
+      PatternError::BellOutOfRange(symbol) => {
+        write!(f, "bell '{symbol}' is beyond the number of bells")
+      }
+    }
+  }
+}
+
+impl Error for PatternError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PatternElement {
+  Bell(u8),
+  AnyBell,
+  AnyBells,
+}
+
+/// A row pattern with wildcards, see the module docs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+  elements: Vec<PatternElement>,
+}
+
+impl Pattern {
+  pub fn parse(pattern: &str, bells_amount: usize) -> Result<Pattern, PatternError> {
+    let elements = pattern
+      .chars()
+      .map(|symbol| {
+        if symbol == ANY_BELLS_SYMBOL {
+          Ok(PatternElement::AnyBells)
+        } else if ANY_BELL_SYMBOLS.contains(&symbol.to_ascii_lowercase()) {
+          Ok(PatternElement::AnyBell)
+        } else {
+          match char_to_bell(symbol) {
+            Some(bell) if bell as usize <= bells_amount => Ok(PatternElement::Bell(bell)),
+            Some(_) => Err(PatternError::BellOutOfRange(symbol)),
+            None => Err(PatternError::UnknownSymbol(symbol)),
+          }
+        }
+      })
+      .collect::<Result<Vec<PatternElement>, PatternError>>()?;
+
+    Ok(Pattern { elements })
+  }
+
+  pub fn matches(&self, row: &[u8]) -> bool {
+    Self::matches_from(&self.elements, row)
+  }
+
+  fn matches_from(elements: &[PatternElement], row: &[u8]) -> bool {
+    match elements.split_first() {
+      None => row.is_empty(),
+      Some((PatternElement::AnyBells, rest)) => {
+        (0..=row.len()).any(|skipped| Self::matches_from(rest, &row[skipped..]))
+      }
+      Some((element, rest)) => match row.split_first() {
+        None => false,
+        Some((bell, rest_of_row)) => {
+          (*element == PatternElement::AnyBell || *element == PatternElement::Bell(*bell))
+            && Self::matches_from(rest, rest_of_row)
+        }
+      },
+    }
+  }
+}
+
+/// A named kind of music, scoring `weight` for each row matching any of its patterns.
+#[derive(Clone, Debug)]
+pub struct MusicType {
+  name: String,
+  patterns: Vec<Pattern>,
+  weight: i32,
+}
+
+impl MusicType {
+  pub fn new(
+    name: &str,
+    patterns: &[&str],
+    weight: i32,
+    bells_amount: usize,
+  ) -> Result<MusicType, PatternError> {
+    Ok(MusicType {
+      name: name.to_string(),
+      patterns: patterns
+        .iter()
+        .map(|pattern| Pattern::parse(pattern, bells_amount))
+        .collect::<Result<Vec<Pattern>, PatternError>>()?,
+      weight,
+    })
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_weight(&self) -> i32 {
+    self.weight
+  }
+
+  pub fn matches(&self, row: &[u8]) -> bool {
+    self.patterns.iter().any(|pattern| pattern.matches(row))
+  }
+
+  pub fn count<R: AsRef<[u8]>>(&self, rows: &[R]) -> usize {
+    rows.iter().filter(|row| self.matches(row.as_ref())).count()
+  }
+}
+
+/// Scores rows by the music types added to it.
+#[derive(Clone, Debug, Default)]
+pub struct MusicScorer {
+  music_types: Vec<MusicType>,
+}
+
+impl MusicScorer {
+  pub fn new() -> MusicScorer {
+    MusicScorer::default()
+  }
+
+  pub fn music_type(mut self, music_type: MusicType) -> MusicScorer {
+    self.music_types.push(music_type);
+    self
+  }
+
+  /// Runs of four at the front and back, queens, tittums, rollups and the
+  /// 56s and 65s with the tenors behind, where the stage is big enough for each.
+  /// On major the rollup is the 56, so it is only looked for as a 56.
+  pub fn for_stage(bells_amount: usize) -> MusicScorer {
+    let bells: Vec<char> = (1..=bells_amount as u8).map(bell_to_char).collect();
+    let mut scorer = MusicScorer::new();
+    let mut add = |name: &str, patterns: Vec<String>| {
+      let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+      let music_type = MusicType::new(name, &patterns, 1, bells_amount)
+        .expect("Default patterns only use bells of the stage");
+      scorer.music_types.push(music_type);
+    };
+
+    let runs: Vec<String> = match bells_amount > RUN_LENGTH {
+      true => bells
+        .windows(RUN_LENGTH)
+        .flat_map(|run| {
+          let run: String = run.iter().collect();
+          [run.clone(), run.chars().rev().collect()]
+        })
+        .collect(),
+      false => Vec::new(),
+    };
+    let fifty_sixes: Vec<String> = match bells_amount >= 6 {
+      true => {
+        let tenors: String = bells[6..].iter().collect();
+        vec![format!("*56{tenors}"), format!("*65{tenors}")]
+      }
+      false => Vec::new(),
+    };
+
+    if !runs.is_empty() {
+      add(
+        "runs at the front",
+        runs.iter().map(|run| format!("{run}*")).collect(),
+      );
+    }
+    // Before the rollups and runs at the back, as on major they also end 5678
+    if !fifty_sixes.is_empty() {
+      add("56s and 65s", fifty_sixes.clone());
+    }
+    if !runs.is_empty() {
+      let rollup = format!(
+        "*{}",
+        bells[bells_amount - RUN_LENGTH..]
+          .iter()
+          .collect::<String>()
+      );
+      // On major the rollup is the 56, which already scores it
+      if !fifty_sixes.contains(&rollup) {
+        // Before the runs at the back, so the rollup is the one scoring it
+        add("rollups", vec![rollup]);
+      }
+      add(
+        "runs at the back",
+        runs.iter().map(|run| format!("*{run}")).collect(),
+      );
+    }
+
+    if bells_amount >= 4 && bells_amount.is_multiple_of(2) {
+      let odd_bells: String = bells.iter().step_by(2).collect();
+      let even_bells: String = bells.iter().skip(1).step_by(2).collect();
+      let (front_half, back_half) = bells.split_at(bells_amount / 2);
+      let tittums: String = front_half
+        .iter()
+        .zip(back_half.iter())
+        .flat_map(|(front, back)| [front, back])
+        .collect();
+
+      add("queens", vec![format!("{odd_bells}{even_bells}")]);
+      add("tittums", vec![tittums]);
+    }
+
+    scorer
+  }
+
+  pub fn get_music_types(&self) -> &[MusicType] {
+    &self.music_types
+  }
+
+  /// Whether each music type scores for `row`. A type only matching the row
+  /// with patterns an earlier type already matched it with does not, so a
+  /// row is scored once for 5678 however many types look for it.
+  fn scoring_types(&self, row: &[u8]) -> Vec<bool> {
+    let mut matched: Vec<&Pattern> = Vec::new();
+
+    self
+      .music_types
+      .iter()
+      .map(|music_type| {
+        let patterns: Vec<&Pattern> = music_type
+          .patterns
+          .iter()
+          .filter(|pattern| pattern.matches(row))
+          .collect();
+        let scores = patterns.iter().any(|pattern| !matched.contains(pattern));
+        matched.extend(patterns);
+        scores
+      })
+      .collect()
+  }
+
+  /// How many rows score for each music type, in the order they were added.
+  pub fn count<R: AsRef<[u8]>>(&self, rows: &[R]) -> Vec<(&str, usize)> {
+    let mut counts: Vec<(&str, usize)> = self
+      .music_types
+      .iter()
+      .map(|music_type| (music_type.get_name(), 0))
+      .collect();
+
+    for row in rows {
+      for (count, scores) in counts.iter_mut().zip(self.scoring_types(row.as_ref())) {
+        count.1 += scores as usize;
+      }
+    }
+
+    counts
+  }
+
+  pub fn score<R: AsRef<[u8]>>(&self, rows: &[R]) -> i32 {
+    rows
+      .iter()
+      .map(|row| {
+        self
+          .music_types
+          .iter()
+          .zip(self.scoring_types(row.as_ref()))
+          .filter(|(_, scores)| *scores)
+          .map(|(music_type, _)| music_type.weight)
+          .sum::<i32>()
+      })
+      .sum()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn matches(pattern: &str, row: &[u8]) -> bool {
+    Pattern::parse(pattern, row.len()).unwrap().matches(row)
+  }
+
+  #[test]
+  fn can_match_patterns() {
+    assert!(matches("*5678", &[3, 1, 2, 4, 5, 6, 7, 8]));
+    assert!(!matches("*5678", &[3, 1, 2, 5, 4, 6, 7, 8]));
+    assert!(matches("1234*", &[1, 2, 3, 4, 6, 5, 8, 7]));
+    assert!(matches("1x3?", &[1, 4, 3, 2]));
+    assert!(matches("*", &[2, 1]));
+    assert!(matches("1*4*6", &[1, 4, 2, 3, 5, 6]));
+    assert!(!matches("123", &[1, 2, 3, 4]));
+  }
+
+  #[test]
+  fn reports_invalid_patterns() {
+    assert_eq!(
+      Pattern::parse("*56!", 6),
+      Err(PatternError::UnknownSymbol('!'))
+    );
+    assert_eq!(
+      Pattern::parse("*5678", 6),
+      Err(PatternError::BellOutOfRange('7'))
+    );
+  }
+
+  #[test]
+  fn can_count_music_types() {
+    let music_type = MusicType::new("65s", &["*6578"], 2, 8).unwrap();
+    let rows = vec![
+      vec![1, 2, 3, 4, 6, 5, 7, 8],
+      vec![2, 1, 4, 3, 6, 5, 7, 8],
+      vec![1, 2, 3, 4, 5, 6, 7, 8],
+    ];
+
+    assert_eq!(music_type.get_name(), "65s");
+    assert_eq!(music_type.count(&rows), 2);
+
+    let scorer = MusicScorer::new().music_type(music_type);
+    assert_eq!(scorer.count(&rows), vec![("65s", 2)]);
+    assert_eq!(scorer.score(&rows), 4);
+  }
+
+  #[test]
+  fn has_default_music_for_a_stage() {
+    let scorer = MusicScorer::for_stage(8);
+    let names: Vec<&str> = scorer
+      .get_music_types()
+      .iter()
+      .map(MusicType::get_name)
+      .collect();
+
+    assert_eq!(
+      names,
+      vec![
+        "runs at the front",
+        "56s and 65s",
+        "runs at the back",
+        "queens",
+        "tittums"
+      ]
+    );
+    assert_eq!(
+      scorer.count(&[vec![1, 3, 5, 7, 2, 4, 6, 8]]),
+      vec![
+        ("runs at the front", 0),
+        ("56s and 65s", 0),
+        ("runs at the back", 0),
+        ("queens", 1),
+        ("tittums", 0)
+      ]
+    );
+    assert_eq!(scorer.score(&[vec![8, 7, 6, 5, 1, 2, 3, 4]]), 2);
+    assert_eq!(scorer.score(&[vec![1, 5, 2, 6, 3, 7, 4, 8]]), 1);
+    // A 56, a rollup and a run at the back in one, scored once
+    assert_eq!(scorer.score(&[vec![2, 1, 4, 3, 5, 6, 7, 8]]), 1);
+    assert_eq!(
+      scorer.count(&[
+        vec![2, 1, 4, 3, 5, 6, 7, 8],
+        vec![1, 2, 4, 3, 6, 5, 7, 8],
+        vec![1, 2, 8, 7, 3, 4, 5, 6]
+      ]),
+      vec![
+        ("runs at the front", 0),
+        ("56s and 65s", 2),
+        ("runs at the back", 1),
+        ("queens", 0),
+        ("tittums", 0)
+      ]
+    );
+  }
+
+  #[test]
+  fn scores_rollups_and_56s_apart_above_major() {
+    let scorer = MusicScorer::for_stage(10);
+    let names: Vec<&str> = scorer
+      .get_music_types()
+      .iter()
+      .map(MusicType::get_name)
+      .collect();
+
+    assert_eq!(
+      names,
+      vec![
+        "runs at the front",
+        "56s and 65s",
+        "rollups",
+        "runs at the back",
+        "queens",
+        "tittums"
+      ]
+    );
+    // A 56 ending in a rollup scores as both
+    assert_eq!(scorer.score(&[vec![2, 1, 4, 3, 5, 6, 7, 8, 9, 10]]), 2);
+  }
+
+  #[test]
+  fn scores_a_row_once_for_the_same_pattern() {
+    // Queens and tittums are both 1324 on four bells
+    let scorer = MusicScorer::for_stage(4);
+
+    assert_eq!(scorer.score(&[vec![1, 3, 2, 4]]), 1);
+    assert_eq!(
+      scorer.count(&[vec![1, 3, 2, 4]]),
+      vec![("queens", 1), ("tittums", 0)]
+    );
+
+    let doubled = MusicScorer::new()
+      .music_type(MusicType::new("a", &["*78"], 2, 8).unwrap())
+      .music_type(MusicType::new("b", &["*78", "1*"], 3, 8).unwrap());
+    assert_eq!(doubled.score(&[vec![2, 1, 3, 4, 5, 6, 7, 8]]), 2);
+    assert_eq!(doubled.score(&[vec![1, 2, 3, 4, 5, 6, 7, 8]]), 5);
+  }
+
+  #[test]
+  fn leaves_out_music_too_big_for_the_stage() {
+    assert!(MusicScorer::for_stage(3).get_music_types().is_empty());
+    assert_eq!(MusicScorer::for_stage(4).get_music_types().len(), 2);
+    assert_eq!(MusicScorer::for_stage(5).get_music_types().len(), 3);
+  }
+}
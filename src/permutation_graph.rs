@@ -1,10 +1,19 @@
-mod method_iterator;
 mod node_stack;
-mod utility;
+mod row_set;
 
+pub(crate) mod utility;
+
+pub mod method_filter;
+pub mod method_iterator;
 pub mod permutation_node;
+pub mod sampler;
+pub mod search_options;
+pub mod treble_path;
 
 use std::{
+  collections::BTreeMap,
+  error::Error,
+  fmt, iter,
   sync::{Arc, Weak},
   thread,
 };
@@ -14,29 +23,107 @@ use permutations::Permutations;
 use method_iterator::MethodIterator;
 use permutation_node::PermutationNode;
 
-use crate::permutation_graph::{
-  node_stack::{NodeChain, NodeStack, PushStatus},
-  permutation_node::StrongNodeVector,
+use crate::{
+  permutation_graph::{
+    method_filter::MethodFilter,
+    node_stack::{NodeChain, NodeStack, PushStatus, path_directions::PathDirections},
+    permutation_node::StrongNodeVector,
+    search_options::SearchOptions,
+    utility::lexicographic_rank,
+  },
+  row::Row,
 };
 
+/// A fixed bell, and the number of bells it is not one of.
+#[derive(Debug, PartialEq)]
+pub struct FixedBellError(u8, usize);
+
+impl fmt::Display for FixedBellError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "fixed bell {} is not one of the {} bells",
+      self.0, self.1
+    )
+  }
+}
+
+impl Error for FixedBellError {}
+
+/// Every row on a number of bells, each linked to the rows that can follow
+/// it in a single change.
 pub struct PermutationGraph {
+  bells_amount: usize,
+  fixed_bells: Vec<u8>,
   nodes: Vec<Arc<PermutationNode>>,
 }
 
 impl PermutationGraph {
   pub fn new(bells_amount: usize) -> PermutationGraph {
+    Self::with_fixed_bells(bells_amount, &[])
+  }
+
+  /// A graph of the rows keeping `fixed_bells` in their home places, such
+  /// as a tenor covering behind on an odd number of bells. Only the other
+  /// bells are permuted, but rows still include every bell. Panics if a
+  /// fixed bell fails [`check_fixed_bells`](Self::check_fixed_bells).
+  pub fn with_fixed_bells(bells_amount: usize, fixed_bells: &[u8]) -> PermutationGraph {
+    if let Err(FixedBellError(bell, bells_amount)) =
+      Self::check_fixed_bells(bells_amount, fixed_bells)
+    {
+      panic!("Fixed bell {bell} is not one of the {bells_amount} bells");
+    }
+
+    let mut fixed_bells = fixed_bells.to_vec();
+    fixed_bells.sort_unstable();
+    fixed_bells.dedup();
+
+    let moving_bells: Vec<u8> = (1..=bells_amount as u8)
+      .filter(|bell| !fixed_bells.contains(bell))
+      .collect();
+
     let nodes = if bells_amount == 0 {
       Vec::new()
     } else {
-      Permutations::new(bells_amount)
+      Permutations::new(moving_bells.len())
         .iter()
-        .map(|perm| Arc::new(PermutationNode::new(perm)))
+        .enumerate()
+        .map(|(rank, perm)| {
+          let row = Self::place_moving_bells(bells_amount, &moving_bells, &Row::from(&perm));
+          Arc::new(PermutationNode::from_row(row, rank))
+        })
         .collect()
     };
 
     PermutationGraph::build_node_graph(&nodes);
 
-    PermutationGraph { nodes }
+    PermutationGraph {
+      bells_amount,
+      fixed_bells,
+      nodes,
+    }
+  }
+
+  /// Checks each fixed bell is one of the `bells_amount` bells, for input
+  /// that should be reported rather than panic in
+  /// [`with_fixed_bells`](Self::with_fixed_bells).
+  pub fn check_fixed_bells(bells_amount: usize, fixed_bells: &[u8]) -> Result<(), FixedBellError> {
+    match fixed_bells
+      .iter()
+      .find(|&&bell| bell == 0 || bell as usize > bells_amount)
+    {
+      Some(&bell) => Err(FixedBellError(bell, bells_amount)),
+      None => Ok(()),
+    }
+  }
+
+  fn place_moving_bells(bells_amount: usize, moving_bells: &[u8], moving_row: &Row) -> Row {
+    let mut row = Row::rounds(bells_amount).get_bells().clone();
+    for (&home, &index) in moving_bells.iter().zip(moving_row.get_bells().iter()) {
+      row[home as usize - 1] = moving_bells[index as usize - 1];
+    }
+
+    Row::new(row).expect("Moving bells are swapped among their own places")
   }
 
   fn build_node_graph(nodes: &StrongNodeVector) {
@@ -45,17 +132,115 @@ impl PermutationGraph {
     }
   }
 
+  pub fn get_bells_amount(&self) -> usize {
+    self.bells_amount
+  }
+
+  /// Bells kept in their home places, in order.
+  pub fn get_fixed_bells(&self) -> &[u8] {
+    &self.fixed_bells
+  }
+
+  /// Every row in lexicographic order, starting with rounds.
+  pub fn get_nodes(&self) -> &[Arc<PermutationNode>] {
+    &self.nodes
+  }
+
+  /// The node for a row such as `[2, 1, 3]`, or `None` if it is not a row on
+  /// this number of bells with the fixed bells at home.
+  pub fn get_node(&self, row: &[u8]) -> Option<&Arc<PermutationNode>> {
+    let fixed_bells_at_home = self
+      .fixed_bells
+      .iter()
+      .all(|&bell| row.get(bell as usize - 1) == Some(&bell));
+
+    if Self::is_row(row, self.bells_amount) && fixed_bells_at_home {
+      // Fixed bells don't change the order, so rank only the moving bells
+      let moving_bells: Vec<u8> = row
+        .iter()
+        .filter(|bell| !self.fixed_bells.contains(bell))
+        .copied()
+        .collect();
+      self.nodes.get(lexicographic_rank(&moving_bells))
+    } else {
+      None
+    }
+  }
+
+  fn is_row(row: &[u8], bells_amount: usize) -> bool {
+    let mut sorted_row = row.to_vec();
+    sorted_row.sort_unstable();
+
+    row.len() == bells_amount
+      && sorted_row
+        .iter()
+        .enumerate()
+        .all(|(index, &bell)| bell as usize == index + 1)
+  }
+
+  /// The rows that can follow a row in a single change, rounds excepted.
+  pub fn get_neighbours(&self, row: &[u8]) -> Option<StrongNodeVector> {
+    self.get_node(row).map(|node| node.get_neighbours())
+  }
+
+  /// How many nodes have each number of neighbours, keyed by that number.
+  pub fn get_degree_distribution(&self) -> BTreeMap<usize, usize> {
+    let mut distribution = BTreeMap::new();
+    for node in self.nodes.iter() {
+      *distribution.entry(node.get_degree()).or_insert(0) += 1;
+    }
+
+    distribution
+  }
+
+  /// Searches for every full method using the default [`SearchOptions`].
   pub fn get_valid_full_methods(&self) -> MethodIterator {
-    if self.nodes.is_empty() {
-      method_iterator::new(vec![])
+    self.search(&SearchOptions::default())
+  }
+
+  /// Searches for every full method, each visiting all rows once before
+  /// returning to rounds.
+  pub fn search(&self, options: &SearchOptions) -> MethodIterator {
+    let half_methods = self.get_half_methods(options);
+    if half_methods.is_empty() && !self.nodes.is_empty() {
+      // Everything was pruned, which is not the same as having no bells
+      return Box::new(iter::empty());
+    }
+
+    let methods = Self::pair_half_methods(half_methods, options);
+    let filter = options.get_filter().clone();
+    let treble_path = options.get_treble_path().cloned();
+
+    if filter == MethodFilter::default() && treble_path.is_none() {
+      methods
     } else {
-      let half_methods = self.get_valid_half_methods();
-      method_iterator::new(half_methods)
+      Box::new(methods.filter(move |method| {
+        filter.matches(method) && treble_path.as_ref().is_none_or(|path| path.matches(method))
+      }))
     }
   }
 
-  fn get_valid_half_methods(&self) -> Vec<StrongNodeVector> {
-    let rounds_node = Arc::clone(&self.nodes[0]);
+  /// Joins half methods that end on the same row and share no other rows
+  /// besides rounds into full methods.
+  pub fn pair_half_methods(
+    half_methods: Vec<StrongNodeVector>,
+    options: &SearchOptions,
+  ) -> MethodIterator {
+    method_iterator::new(half_methods, options)
+  }
+
+  /// Every chain of unique rows from rounds covering half of the rows.
+  pub fn get_valid_half_methods(&self) -> Vec<StrongNodeVector> {
+    self.get_half_methods(&SearchOptions::default())
+  }
+
+  /// Every chain of unique rows from rounds covering half of the rows in
+  /// which each change is allowed by the options' filter, and which keeps
+  /// the treble on the options' path either forwards or backwards.
+  pub fn get_half_methods(&self, options: &SearchOptions) -> Vec<StrongNodeVector> {
+    let Some(rounds_node) = self.nodes.first().map(Arc::clone) else {
+      return Vec::new();
+    };
 
     if self.nodes.len() == 1 {
       return vec![vec![Arc::clone(&rounds_node)]];
@@ -64,7 +249,11 @@ impl PermutationGraph {
     let join_handles: Vec<thread::JoinHandle<Vec<StrongNodeVector>>> = rounds_node
       .get_valid_permutations()
       .iter()
-      .map(|change| self.spawn_method_traversal_thread(change))
+      .filter(|change| {
+        let first_row = change.upgrade().expect("Node should exist");
+        self.allows_first_row(&rounds_node, &first_row, options)
+      })
+      .map(|change| self.spawn_method_traversal_thread(change, options.clone()))
       .collect();
 
     join_handles
@@ -73,23 +262,57 @@ impl PermutationGraph {
       .collect()
   }
 
+  fn allows_first_row(
+    &self,
+    rounds_node: &PermutationNode,
+    first_row: &PermutationNode,
+    options: &SearchOptions,
+  ) -> bool {
+    let follows_treble_path = options.get_treble_path().is_none_or(|path| {
+      PathDirections::any()
+        .follow(path, self.nodes.len(), 1, first_row.get_permutation())
+        .is_some()
+    });
+
+    follows_treble_path
+      && options.get_filter().allows_change(
+        None,
+        rounds_node.get_permutation(),
+        first_row.get_permutation(),
+      )
+  }
+
   fn spawn_method_traversal_thread(
     &self,
     first_change: &Weak<PermutationNode>,
+    options: SearchOptions,
   ) -> thread::JoinHandle<Vec<StrongNodeVector>> {
     let rounds_node = Arc::clone(&self.nodes[0]);
     let change_clone = Weak::clone(first_change);
+    let method_length = self.nodes.len();
 
-    thread::spawn(move || Self::get_node_half_methods(change_clone, rounds_node))
+    thread::spawn(move || {
+      Self::get_node_half_methods(change_clone, rounds_node, method_length, options)
+    })
   }
 
   fn get_node_half_methods(
     node: Weak<PermutationNode>,
     rounds_node: Arc<PermutationNode>,
+    method_length: usize,
+    options: SearchOptions,
   ) -> Vec<StrongNodeVector> {
     let strong_node = node.upgrade().expect("Node should exist");
 
-    let mut node_stack = NodeStack::new(&strong_node);
+    let mut node_stack = NodeStack::new(
+      &strong_node,
+      method_length / 2,
+      Some(&rounds_node),
+      options.get_filter().clone(),
+    );
+    if let Some(path) = options.get_treble_path() {
+      node_stack = node_stack.with_treble_path(path.clone(), method_length);
+    }
 
     let mut half_methods = Vec::new();
     while !node_stack.is_empty() {
@@ -115,6 +338,8 @@ impl PermutationGraph {
 mod test {
   use crate::permutation_graph::utility::test::get_valid_permutation;
 
+  use crate::permutation_graph::treble_path::TreblePath;
+
   use super::*;
 
   #[test]
@@ -138,6 +363,237 @@ mod test {
     assert_eq!(graph2.nodes.len(), 720);
   }
 
+  #[test]
+  fn can_iterate_nodes() {
+    let graph = PermutationGraph::new(3);
+    let rows: Vec<&Vec<u8>> = graph
+      .get_nodes()
+      .iter()
+      .map(|node| node.get_permutation())
+      .collect();
+
+    assert_eq!(graph.get_bells_amount(), 3);
+    assert_eq!(
+      rows,
+      vec![
+        &[1, 2, 3],
+        &[1, 3, 2],
+        &[2, 1, 3],
+        &[2, 3, 1],
+        &[3, 1, 2],
+        &[3, 2, 1],
+      ]
+    );
+  }
+
+  #[test]
+  fn can_look_up_a_node_by_row() {
+    let graph = PermutationGraph::new(4);
+
+    let node = graph.get_node(&[2, 4, 1, 3]).expect("Row should exist");
+
+    assert_eq!(node.get_permutation(), &[2, 4, 1, 3]);
+    assert!(graph.get_node(&[1, 2, 3, 4]).unwrap().is_rounds());
+    assert!(graph.get_node(&[1, 2, 3]).is_none());
+    assert!(graph.get_node(&[1, 2, 2, 4]).is_none());
+    assert!(graph.get_node(&[1, 2, 3, 5]).is_none());
+  }
+
+  #[test]
+  fn can_get_neighbours_of_a_row() {
+    let graph = PermutationGraph::new(3);
+
+    let neighbours: Vec<Vec<u8>> = graph
+      .get_neighbours(&[2, 3, 1])
+      .expect("Row should exist")
+      .iter()
+      .map(|node| node.get_permutation().clone())
+      .collect();
+
+    assert_eq!(neighbours, vec![vec![2, 1, 3], vec![3, 2, 1]]);
+    assert!(graph.get_neighbours(&[1, 1, 1]).is_none());
+  }
+
+  #[test]
+  fn can_get_degree_distribution() {
+    let graph = PermutationGraph::new(3);
+    let distribution = graph.get_degree_distribution();
+
+    assert_eq!(distribution, BTreeMap::from([(1, 2), (2, 4)]));
+    assert!(
+      PermutationGraph::new(0)
+        .get_degree_distribution()
+        .is_empty()
+    );
+  }
+
+  #[test]
+  fn can_search_with_options() {
+    let graph = PermutationGraph::new(3);
+    let options = SearchOptions::new().include_reversed(false);
+
+    let methods = collect_full_methods(graph.search(&options));
+
+    assert_eq!(methods.len(), 1);
+  }
+
+  #[test]
+  fn finds_the_same_methods_without_grouping() {
+    let graph = PermutationGraph::new(4);
+    let options = SearchOptions::new().filter(MethodFilter::RightPlace);
+    let rows = |iterator: MethodIterator| {
+      let mut methods: Vec<Vec<Vec<u8>>> =
+        iterator.map(|method| crate::get_rows(&method)).collect();
+      methods.sort();
+      methods
+    };
+
+    let grouped = rows(graph.search(&options));
+    let ungrouped = rows(graph.search(&options.clone().group_by_end_row(false)));
+
+    assert!(!grouped.is_empty());
+    assert_eq!(grouped, ungrouped);
+  }
+
+  #[test]
+  fn can_keep_bells_fixed() {
+    let graph = PermutationGraph::with_fixed_bells(4, &[4, 1]);
+
+    assert_eq!(graph.get_bells_amount(), 4);
+    assert_eq!(graph.get_fixed_bells(), &[1, 4]);
+    assert_eq!(graph.get_nodes().len(), 2);
+    assert_eq!(graph.get_nodes()[1].get_permutation(), &vec![1, 3, 2, 4]);
+    assert_eq!(graph.get_nodes()[1].get_rank(), 1);
+    assert!(graph.get_node(&[1, 3, 2, 4]).is_some());
+    assert!(graph.get_node(&[2, 1, 3, 4]).is_none());
+  }
+
+  #[test]
+  fn finds_methods_with_a_cover_bell() {
+    let graph = PermutationGraph::with_fixed_bells(4, &[4]);
+    let uncovered_graph = PermutationGraph::new(3);
+
+    let methods = collect_full_methods(graph.get_valid_full_methods());
+    let uncovered_methods = collect_full_methods(uncovered_graph.get_valid_full_methods());
+
+    assert_eq!(graph.get_nodes().len(), 6);
+    assert_eq!(methods.len(), uncovered_methods.len());
+    for (method, uncovered_method) in methods.iter().zip(uncovered_methods.iter()) {
+      for (node, uncovered_node) in method.iter().zip(uncovered_method.iter()) {
+        assert_eq!(
+          node.get_permutation()[..3],
+          uncovered_node.get_permutation()[..]
+        );
+        assert_eq!(node.get_permutation()[3], 4);
+      }
+    }
+  }
+
+  #[test]
+  fn checks_fixed_bells() {
+    assert_eq!(PermutationGraph::check_fixed_bells(4, &[4, 1]), Ok(()));
+    assert_eq!(
+      PermutationGraph::check_fixed_bells(3, &[2, 9]),
+      Err(FixedBellError(9, 3))
+    );
+    assert_eq!(
+      PermutationGraph::check_fixed_bells(3, &[0])
+        .unwrap_err()
+        .to_string(),
+      "fixed bell 0 is not one of the 3 bells"
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "Fixed bell 5 is not one of the 4 bells")]
+  fn panics_if_a_fixed_bell_is_out_of_range() {
+    PermutationGraph::with_fixed_bells(4, &[5]);
+  }
+
+  #[test]
+  fn filters_methods_while_searching() {
+    let graph = PermutationGraph::new(4);
+    let filter = MethodFilter::NoLongPlaces.and(MethodFilter::RightPlace);
+    let options = SearchOptions::new().filter(filter.clone());
+
+    let filtered_half_methods =
+      graph.get_half_methods(&SearchOptions::new().filter(filter.clone()));
+    let filtered_methods = collect_full_methods(graph.search(&options));
+
+    assert!(!filtered_half_methods.is_empty());
+    assert!(
+      filtered_half_methods
+        .iter()
+        .all(|half_method| filter.matches(half_method))
+    );
+    assert!(!filtered_methods.is_empty());
+    assert!(filtered_methods.iter().all(|method| filter.matches(method)));
+  }
+
+  #[test]
+  fn keeps_the_treble_on_its_path() {
+    let graph = PermutationGraph::new(3);
+    let path = TreblePath::plain_hunt(3);
+    let options = SearchOptions::new().treble_path(path.clone());
+
+    let methods = collect_full_methods(graph.search(&options));
+
+    // Rung backwards the treble would lead for the first two rows
+    assert_eq!(methods.len(), 1);
+    assert!(methods.iter().all(|method| path.matches(method)));
+    assert!(
+      collect_full_methods(
+        graph.search(&SearchOptions::new().treble_path(TreblePath::custom(vec![1], 4).unwrap()))
+      )
+      .is_empty()
+    );
+  }
+
+  #[test]
+  fn keeps_half_methods_following_the_treble_path_either_way() {
+    let graph = PermutationGraph::new(3);
+    let path = TreblePath::plain_hunt(3);
+    let method_length = graph.get_nodes().len();
+    let follows_path = |half_method: &StrongNodeVector, reversed: bool| {
+      half_method.iter().enumerate().all(|(index, node)| {
+        let row_index = if reversed {
+          method_length - index
+        } else {
+          index
+        };
+        index == 0 || path.allows_row(row_index, node.get_permutation())
+      })
+    };
+
+    let expected_half_methods: Vec<StrongNodeVector> = graph
+      .get_valid_half_methods()
+      .into_iter()
+      .filter(|half_method| follows_path(half_method, false) || follows_path(half_method, true))
+      .collect();
+
+    assert_eq!(
+      graph.get_half_methods(&SearchOptions::new().treble_path(path.clone())),
+      expected_half_methods
+    );
+  }
+
+  #[test]
+  fn filtering_half_methods_keeps_every_allowed_half_method() {
+    let graph = PermutationGraph::new(3);
+    let filter = MethodFilter::TrebleHunts;
+
+    let expected_half_methods: Vec<StrongNodeVector> = graph
+      .get_valid_half_methods()
+      .into_iter()
+      .filter(|half_method| filter.matches(half_method))
+      .collect();
+
+    assert_eq!(
+      graph.get_half_methods(&SearchOptions::new().filter(filter.clone())),
+      expected_half_methods
+    );
+  }
+
   #[test]
   fn permutation_nodes_have_all_valid_changes() {
     let graph = PermutationGraph::new(2);
@@ -146,16 +602,22 @@ mod test {
 
     assert_eq!(valid_permutations1.len(), 1);
     assert_eq!(
-      get_valid_permutation(&valid_permutations1, 0).get_permutation(),
+      get_valid_permutation(valid_permutations1, 0).get_permutation(),
       &vec![2, 1]
     );
     assert!(
-      get_valid_permutation(&valid_permutations1, 0)
+      get_valid_permutation(valid_permutations1, 0)
         .get_valid_permutations()
         .is_empty()
     );
   }
 
+  #[test]
+  fn has_no_half_methods_without_bells() {
+    let graph = PermutationGraph::new(0);
+    assert!(graph.get_valid_half_methods().is_empty());
+  }
+
   #[test]
   fn can_get_all_half_methods_without_repeats() {
     let graph = PermutationGraph::new(3);
@@ -169,19 +631,19 @@ mod test {
     assert_eq!(half_methods.len(), 2);
 
     let expected_method1: Vec<&[u8; 3]> = vec![&[1, 2, 3], &[1, 3, 2], &[3, 1, 2], &[3, 2, 1]];
-    let expected_method2 = vec![&[1, 2, 3], &[2, 1, 3], &[2, 3, 1], &[3, 2, 1]];
+    let expected_method2 = [&[1, 2, 3], &[2, 1, 3], &[2, 3, 1], &[3, 2, 1]];
 
     assert!(half_methods.iter().any(|method| {
       method
         .iter()
         .enumerate()
-        .all(|(index, node)| node.get_permutation() == &expected_method1[index])
+        .all(|(index, node)| node.get_permutation() == expected_method1[index])
     }));
     assert!(half_methods.iter().any(|method| {
       method
         .iter()
         .enumerate()
-        .all(|(index, node)| node.get_permutation() == &expected_method2[index])
+        .all(|(index, node)| node.get_permutation() == expected_method2[index])
     }));
   }
 
@@ -198,7 +660,7 @@ mod test {
       [2, 1, 3],
       [1, 2, 3],
     ];
-    let method2: Vec<[u8; 3]> = method1.iter().rev().map(|slice| slice.clone()).collect();
+    let method2: Vec<[u8; 3]> = method1.iter().rev().copied().collect();
     let valid_methods = collect_full_methods(method_iterator);
 
     assert_eq!(valid_methods.len(), 2);
@@ -279,10 +741,18 @@ mod test {
     let valid_change1 = &valid_changes[0];
     let valid_change2 = &valid_changes[1];
 
-    let half_methods1 =
-      PermutationGraph::get_node_half_methods(Weak::clone(valid_change1), Arc::clone(&rounds_node));
-    let half_methods2 =
-      PermutationGraph::get_node_half_methods(Weak::clone(valid_change2), Arc::clone(&rounds_node));
+    let half_methods1 = PermutationGraph::get_node_half_methods(
+      Weak::clone(valid_change1),
+      Arc::clone(rounds_node),
+      graph.nodes.len(),
+      SearchOptions::default(),
+    );
+    let half_methods2 = PermutationGraph::get_node_half_methods(
+      Weak::clone(valid_change2),
+      Arc::clone(rounds_node),
+      graph.nodes.len(),
+      SearchOptions::default(),
+    );
 
     assert_eq!(half_methods1.len(), 1);
     assert_eq!(half_methods2.len(), 1);
@@ -0,0 +1,279 @@
+use std::{error::Error, fmt, str::FromStr, sync::Arc};
+
+use crate::permutation_graph::permutation_node::PermutationNode;
+
+/// A structural condition on a method, made of conditions on each change
+/// and the two rows before it. Since no condition depends on where in the
+/// method a change is rung, or which way the method is rung, a search can
+/// drop any half method breaking one.
+///
+/// Written on the command line as a comma separated list, e.g.
+/// `max-places=2,no-long-places,right-place,treble-hunts`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MethodFilter {
+  /// No change makes more than this many places.
+  MaxPlaces(usize),
+  /// No bell stays in the same place for more than two rows.
+  NoLongPlaces,
+  /// Changes alternate between making as few places as possible (`x` on an
+  /// even number of bells) and making more.
+  RightPlace,
+  /// The treble plain hunts, leading and lying for two rows.
+  TrebleHunts,
+  /// Every filter holds, with no filters allowing any method.
+  All(Vec<MethodFilter>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FilterError {
+  UnknownFilter(String),
+  InvalidNumber(String),
+}
+
+impl fmt::Display for FilterError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      FilterError::UnknownFilter(name) => write!(f, "unknown filter '{name}'"),
+      FilterError::InvalidNumber(value) => write!(f, "'{value}' is not a number"),
+    }
+  }
+}
+
+impl Error for FilterError {}
+
+impl MethodFilter {
+  /// A filter which also requires `other`.
+  pub fn and(self, other: MethodFilter) -> MethodFilter {
+    match self {
+      MethodFilter::All(mut filters) => {
+        filters.push(other);
+        MethodFilter::All(filters)
+      }
+      filter => MethodFilter::All(vec![filter, other]),
+    }
+  }
+
+  /// Whether `next` may follow `previous`, which followed `before_previous`
+  /// unless it is the first row.
+  pub fn allows_change(
+    &self,
+    before_previous: Option<&[u8]>,
+    previous: &[u8],
+    next: &[u8],
+  ) -> bool {
+    match self {
+      MethodFilter::MaxPlaces(max_places) => count_places(previous, next) <= *max_places,
+      MethodFilter::NoLongPlaces => before_previous.is_none_or(|before_previous| {
+        (0..next.len()).all(|place| {
+          !(before_previous[place] == previous[place] && previous[place] == next[place])
+        })
+      }),
+      MethodFilter::RightPlace => before_previous.is_none_or(|before_previous| {
+        is_least_places(before_previous, previous) != is_least_places(previous, next)
+      }),
+      MethodFilter::TrebleHunts => treble_hunts(before_previous, previous, next),
+      MethodFilter::All(filters) => filters
+        .iter()
+        .all(|filter| filter.allows_change(before_previous, previous, next)),
+    }
+  }
+
+  /// Whether every change of the rows is allowed.
+  pub fn matches_rows<R: AsRef<[u8]>>(&self, rows: &[R]) -> bool {
+    (1..rows.len()).all(|index| {
+      let before_previous = index.checked_sub(2).map(|before| rows[before].as_ref());
+      self.allows_change(
+        before_previous,
+        rows[index - 1].as_ref(),
+        rows[index].as_ref(),
+      )
+    })
+  }
+
+  pub fn matches(&self, method: &[Arc<PermutationNode>]) -> bool {
+    let rows: Vec<&[u8]> = method
+      .iter()
+      .map(|node| node.get_permutation().as_slice())
+      .collect();
+    self.matches_rows(&rows)
+  }
+}
+
+impl Default for MethodFilter {
+  fn default() -> Self {
+    MethodFilter::All(Vec::new())
+  }
+}
+
+impl FromStr for MethodFilter {
+  type Err = FilterError;
+
+  fn from_str(expression: &str) -> Result<MethodFilter, FilterError> {
+    let filters = expression
+      .split(',')
+      .map(str::trim)
+      .filter(|term| !term.is_empty())
+      .map(parse_term)
+      .collect::<Result<Vec<MethodFilter>, FilterError>>()?;
+
+    Ok(MethodFilter::All(filters))
+  }
+}
+
+fn parse_term(term: &str) -> Result<MethodFilter, FilterError> {
+  match term.split_once('=') {
+    Some(("max-places", value)) => value
+      .trim()
+      .parse()
+      .map(MethodFilter::MaxPlaces)
+      .map_err(|_| FilterError::InvalidNumber(value.to_string())),
+    None if term == "no-long-places" => Ok(MethodFilter::NoLongPlaces),
+    None if term == "right-place" => Ok(MethodFilter::RightPlace),
+    None if term == "treble-hunts" => Ok(MethodFilter::TrebleHunts),
+    _ => Err(FilterError::UnknownFilter(term.to_string())),
+  }
+}
+
+fn count_places(previous: &[u8], next: &[u8]) -> usize {
+  previous
+    .iter()
+    .zip(next.iter())
+    .filter(|(before, after)| before == after)
+    .count()
+}
+
+fn is_least_places(previous: &[u8], next: &[u8]) -> bool {
+  count_places(previous, next) == previous.len() % 2
+}
+
+fn treble_hunts(before_previous: Option<&[u8]>, previous: &[u8], next: &[u8]) -> bool {
+  let treble_place = |row: &[u8]| {
+    row
+      .iter()
+      .position(|&bell| bell == 1)
+      .expect("Rows contain the treble") as isize
+  };
+  let last_place = previous.len() as isize - 1;
+  let is_at_edge = |place: isize| place == 0 || place == last_place;
+
+  let previous_place = treble_place(previous);
+  let step = treble_place(next) - previous_place;
+
+  match before_previous {
+    None => step.abs() == 1 || (step == 0 && is_at_edge(previous_place)),
+    Some(before_previous) => {
+      let last_step = previous_place - treble_place(before_previous);
+      if last_step == 0 {
+        step != 0 && is_at_edge(previous_place)
+      } else if step == 0 {
+        is_at_edge(previous_place)
+      } else {
+        step == last_step
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::place_notation::expand_lead;
+
+  fn lead(notation: &str, bells_amount: usize) -> Vec<Vec<u8>> {
+    expand_lead(notation, bells_amount).unwrap()
+  }
+
+  #[test]
+  fn can_parse_filters() {
+    assert_eq!(
+      "max-places=2, no-long-places".parse(),
+      Ok(MethodFilter::All(vec![
+        MethodFilter::MaxPlaces(2),
+        MethodFilter::NoLongPlaces
+      ]))
+    );
+    assert_eq!(
+      "right-place,treble-hunts".parse(),
+      Ok(MethodFilter::All(vec![
+        MethodFilter::RightPlace,
+        MethodFilter::TrebleHunts
+      ]))
+    );
+    assert_eq!("".parse(), Ok(MethodFilter::default()));
+    assert_eq!(
+      "max-places=two".parse::<MethodFilter>(),
+      Err(FilterError::InvalidNumber("two".to_string()))
+    );
+    assert_eq!(
+      "wrong-place".parse::<MethodFilter>(),
+      Err(FilterError::UnknownFilter("wrong-place".to_string()))
+    );
+  }
+
+  #[test]
+  fn can_combine_filters() {
+    let filter = MethodFilter::MaxPlaces(2)
+      .and(MethodFilter::NoLongPlaces)
+      .and(MethodFilter::RightPlace);
+
+    assert_eq!(
+      filter,
+      MethodFilter::All(vec![
+        MethodFilter::MaxPlaces(2),
+        MethodFilter::NoLongPlaces,
+        MethodFilter::RightPlace
+      ])
+    );
+  }
+
+  #[test]
+  fn checks_places_per_change() {
+    let plain_bob = lead("x16x16x16,12", 6);
+    let with_four_places = lead("x1234x16x16,12", 6);
+
+    assert!(MethodFilter::MaxPlaces(2).matches_rows(&plain_bob));
+    assert!(!MethodFilter::MaxPlaces(2).matches_rows(&with_four_places));
+    assert!(MethodFilter::MaxPlaces(4).matches_rows(&with_four_places));
+  }
+
+  #[test]
+  fn checks_long_places() {
+    let plain_bob = lead("x16x16x16,12", 6);
+    let long_place = lead("x16.16x16,12", 6);
+
+    assert!(MethodFilter::NoLongPlaces.matches_rows(&plain_bob));
+    assert!(!MethodFilter::NoLongPlaces.matches_rows(&long_place));
+  }
+
+  #[test]
+  fn checks_right_place() {
+    let plain_bob = lead("x16x16x16,12", 6);
+    let wrong_place = lead("x16x16x14.16,12", 6);
+
+    assert!(MethodFilter::RightPlace.matches_rows(&plain_bob));
+    assert!(!MethodFilter::RightPlace.matches_rows(&wrong_place));
+  }
+
+  #[test]
+  fn checks_the_treble_hunts() {
+    let plain_bob = lead("x16x16x16,12", 6);
+    let treble_dodges = lead("x16x12x16,12", 6);
+
+    assert!(MethodFilter::TrebleHunts.matches_rows(&plain_bob));
+    assert!(!MethodFilter::TrebleHunts.matches_rows(&treble_dodges));
+    assert!(MethodFilter::default().matches_rows(&treble_dodges));
+  }
+
+  #[test]
+  fn allows_changes_either_way_round() {
+    let filter: MethodFilter = "max-places=2,no-long-places,right-place,treble-hunts"
+      .parse()
+      .unwrap();
+    let mut plain_bob = lead("x16x16x16,12", 6);
+
+    assert!(filter.matches_rows(&plain_bob));
+    plain_bob.reverse();
+    assert!(filter.matches_rows(&plain_bob));
+  }
+}
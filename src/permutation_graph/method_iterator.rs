@@ -1,40 +1,42 @@
 mod channel_helper;
 mod comparison_index_store;
 mod comparison_status;
+mod half_method_groups;
 mod method_matcher;
 mod thread_and_cup;
 
 use std::{
   cmp::min,
   collections::HashMap,
-  mem::swap,
+  mem::{replace, swap},
   sync::{
     Arc,
-    mpsc::{Receiver, Sender, channel},
+    mpsc::{Receiver, SyncSender, channel, sync_channel},
   },
   thread,
 };
 
 use crate::permutation_graph::{
   method_iterator::{
-    channel_helper::{recv_or_error, send_or_error},
+    channel_helper::sync_send_or_stop,
     comparison_index_store::{ComparisonIndexStore, ComparisonIndexStoreBuilder},
     comparison_status::ComparisonStatus,
+    half_method_groups::HalfMethodGroups,
     method_matcher::MethodMatcher,
     thread_and_cup::ThreadAndCup,
   },
   permutation_node::StrongNodeVector,
-  utility::are_unique,
+  search_options::SearchOptions,
 };
 
+/// Yields every full method found by a search, each starting and ending at rounds.
 pub type MethodIterator = Box<dyn Iterator<Item = StrongNodeVector>>;
 
-const MAX_THREADS: usize = 4;
-
 struct FullMethodIterator {
   shared_index: usize,
-  status_channel: (Sender<ComparisonStatus>, Receiver<ComparisonStatus>),
+  status_receiver: Receiver<ComparisonStatus>,
   thread_and_cups: HashMap<usize, ThreadAndCup>,
+  include_reversed: bool,
   last_method_reverse: Option<StrongNodeVector>,
 }
 
@@ -47,26 +49,27 @@ struct ZeroMethodIterator {
   done: bool,
 }
 
-pub fn new(half_methods: Vec<StrongNodeVector>) -> MethodIterator {
+pub fn new(half_methods: Vec<StrongNodeVector>, options: &SearchOptions) -> MethodIterator {
   if half_methods.is_empty() {
     Box::new(ZeroMethodIterator::new())
   } else if half_methods.len() == 1 {
     Box::new(OneMethodIterator::new(half_methods))
   } else {
-    Box::new(FullMethodIterator::new(half_methods))
+    Box::new(FullMethodIterator::new(half_methods, options))
   }
 }
 
 impl FullMethodIterator {
-  fn new(half_methods: Vec<StrongNodeVector>) -> FullMethodIterator {
+  fn new(half_methods: Vec<StrongNodeVector>, options: &SearchOptions) -> FullMethodIterator {
     Self::validate_half_method_input(&half_methods);
-    let (shared_index, thread_and_cups, status_channel) =
-      Self::build_threads_start_index_and_status_receiver(half_methods);
+    let (shared_index, thread_and_cups, status_receiver) =
+      Self::build_threads_start_index_and_status_receiver(half_methods, options);
 
     FullMethodIterator {
       shared_index,
-      status_channel,
+      status_receiver,
       thread_and_cups,
+      include_reversed: options.get_include_reversed(),
       last_method_reverse: None,
     }
   }
@@ -79,14 +82,20 @@ impl FullMethodIterator {
 
   fn build_threads_start_index_and_status_receiver(
     half_methods: Vec<StrongNodeVector>,
+    options: &SearchOptions,
   ) -> (
     usize,
     HashMap<usize, ThreadAndCup>,
-    (Sender<ComparisonStatus>, Receiver<ComparisonStatus>),
+    Receiver<ComparisonStatus>,
   ) {
-    let thread_total = min(half_methods.len() - 1, MAX_THREADS);
-    let arc_half_methods = Arc::new(half_methods);
-    let (status_sender, status_receiver) = channel();
+    let thread_total = min(half_methods.len() - 1, options.get_comparison_threads());
+    let arc_half_methods = Arc::new(match options.get_group_by_end_row() {
+      true => HalfMethodGroups::new(half_methods),
+      false => HalfMethodGroups::ungrouped(half_methods),
+    });
+    // Bounded so comparison threads wait for a slow consumer rather than
+    // queuing every method they find
+    let (status_sender, status_receiver) = sync_channel(options.get_channel_capacity());
     let mut hash_map = HashMap::with_capacity(thread_total);
 
     for thread_num in 0..thread_total {
@@ -97,7 +106,7 @@ impl FullMethodIterator {
       );
     }
 
-    (thread_total + 1, hash_map, (status_sender, status_receiver))
+    (thread_total, hash_map, status_receiver)
   }
 
   fn get_reverse_method(method: &StrongNodeVector) -> StrongNodeVector {
@@ -112,20 +121,25 @@ impl FullMethodIterator {
   }
 
   fn pair_matching_half_methods(
-    half_methods: &[StrongNodeVector],
+    half_methods: &HalfMethodGroups,
     index_store: &mut ComparisonIndexStore,
   ) -> Option<StrongNodeVector> {
-    let matcher = MethodMatcher::new(half_methods, index_store.get_current_index());
+    let matcher = MethodMatcher::new(
+      half_methods.get_half_methods(),
+      index_store.get_current_index(),
+    );
 
     while index_store.is_running_comparison() {
-      let full_method_match =
-        matcher.get_full_method_from_end_node_match(index_store.get_comparison_index());
+      let comparison_index = index_store.get_comparison_index();
+      let is_disjoint =
+        half_methods.are_disjoint(index_store.get_current_index(), comparison_index);
 
       index_store.increment_indexes();
 
-      if let Some(method) = full_method_match {
-        if are_unique(&method[..method.len() - 1]) {
-          return Some(method);
+      if is_disjoint {
+        let full_method_match = matcher.get_full_method_from_end_node_match(comparison_index);
+        if full_method_match.is_some() {
+          return full_method_match;
         }
       }
     }
@@ -135,8 +149,8 @@ impl FullMethodIterator {
 
   fn spawn_comparison_thread(
     thread_num: usize,
-    status_sender: Sender<ComparisonStatus>,
-    half_methods: Arc<Vec<StrongNodeVector>>,
+    status_sender: SyncSender<ComparisonStatus>,
+    half_methods: Arc<HalfMethodGroups>,
   ) -> ThreadAndCup {
     let (index_sender, index_receiver) = channel();
 
@@ -152,36 +166,40 @@ impl FullMethodIterator {
 
   fn run_comparisons(
     thread_num: usize,
-    half_methods: Arc<Vec<StrongNodeVector>>,
-    status_sender: Sender<ComparisonStatus>,
+    half_methods: Arc<HalfMethodGroups>,
+    status_sender: SyncSender<ComparisonStatus>,
     index_receiver: Receiver<usize>,
   ) {
     let mut index_store = ComparisonIndexStoreBuilder::new()
       .last_index(half_methods.len() - 1)
       .start_index(thread_num)
+      .group_last_indexes(Arc::clone(half_methods.get_group_last_indexes()))
       .status_sender(status_sender.clone())
       .index_receiver(index_receiver)
       .build();
 
     while index_store.is_running() {
-      if let Some(method) = Self::pair_matching_half_methods(&half_methods, &mut index_store) {
-        let reverse_method = Self::get_reverse_method(&method);
-        send_or_error(
-          &status_sender,
-          ComparisonStatus::Methods(method, reverse_method),
-        );
+      // Pairing has already moved past a matched comparison, so only
+      // increment once the current method has run out of comparisons
+      match Self::pair_matching_half_methods(&half_methods, &mut index_store) {
+        Some(method) => {
+          let reverse_method = Self::get_reverse_method(&method);
+          let sent = sync_send_or_stop(
+            &status_sender,
+            ComparisonStatus::Methods(method, reverse_method),
+          );
+
+          // The iterator has been dropped, so nobody wants any more methods
+          if !sent {
+            return;
+          }
+        }
+        None => index_store.increment_indexes(),
       }
-
-      index_store.increment_indexes();
     }
 
-    send_or_error(&status_sender, ComparisonStatus::End(thread_num));
-  }
-
-  fn are_comparisons_running(thread_and_cups: &HashMap<usize, ThreadAndCup>) -> bool {
-    thread_and_cups
-      .values()
-      .any(|thread_and_cup| thread_and_cup.is_thread_running())
+    // Nobody is left to tell if the iterator has been dropped
+    sync_send_or_stop(&status_sender, ComparisonStatus::End(thread_num));
   }
 }
 
@@ -215,8 +233,9 @@ impl Iterator for FullMethodIterator {
   fn next(&mut self) -> Option<Self::Item> {
     let Self {
       shared_index,
-      status_channel: (_, status_receiver),
+      status_receiver,
       thread_and_cups,
+      include_reversed,
       last_method_reverse,
     } = self;
 
@@ -224,8 +243,16 @@ impl Iterator for FullMethodIterator {
       return self.consume_reverse_method();
     }
 
-    while Self::are_comparisons_running(thread_and_cups) {
-      match recv_or_error(status_receiver) {
+    // Each thread ends by sending End, so its methods still queued are
+    // taken before it is dropped, even once the thread has finished
+    while !thread_and_cups.is_empty() {
+      let Ok(status) = status_receiver.recv() else {
+        // Every comparison thread has gone without ending, e.g. by panicking
+        thread_and_cups.clear();
+        break;
+      };
+
+      match status {
         ComparisonStatus::End(thread_num) => {
           let thread_and_cup = thread_and_cups
             .remove(&thread_num)
@@ -233,7 +260,9 @@ impl Iterator for FullMethodIterator {
           thread_and_cup.join_thread().unwrap();
         }
         ComparisonStatus::Methods(method, reversed) => {
-          *last_method_reverse = Some(reversed);
+          if *include_reversed {
+            *last_method_reverse = Some(reversed);
+          }
           return Some(method);
         }
         ComparisonStatus::NextIndex(thread_num) => {
@@ -251,6 +280,23 @@ impl Iterator for FullMethodIterator {
   }
 }
 
+impl Drop for FullMethodIterator {
+  /// Stops the comparison threads when the iterator is dropped before the
+  /// last method, e.g. by `take`.
+  fn drop(&mut self) {
+    // Threads waiting to send a status stop once the receiver has gone
+    let (_, closed_receiver) = sync_channel(0);
+    drop(replace(&mut self.status_receiver, closed_receiver));
+
+    for (_, thread_and_cup) in self.thread_and_cups.drain() {
+      // A panic while already unwinding would abort
+      if thread_and_cup.join_thread().is_err() && !thread::panicking() {
+        panic!("Comparison thread panicked");
+      }
+    }
+  }
+}
+
 impl Iterator for OneMethodIterator {
   type Item = StrongNodeVector;
 
@@ -294,9 +340,11 @@ impl Iterator for ZeroMethodIterator {
 
 #[cfg(test)]
 mod test {
-  use std::sync::Arc;
+  use std::{sync::Arc, thread::sleep, time::Duration};
 
-  use crate::permutation_graph::utility::test::set_up_node_vector;
+  use crate::{
+    MethodFilter, PermutationGraph, permutation_graph::utility::test::set_up_node_vector,
+  };
 
   use super::*;
 
@@ -316,7 +364,7 @@ mod test {
   fn can_create_a_full_method_iterator() {
     let mock_nodes = set_up_node_vector(2);
     let methods = vec![mock_nodes.clone(), mock_nodes];
-    FullMethodIterator::new(methods);
+    FullMethodIterator::new(methods, &SearchOptions::default());
   }
 
   #[test]
@@ -347,7 +395,7 @@ mod test {
   fn full_method_iterator_panics_if_less_than_two_half_methods() {
     let mock_nodes = set_up_node_vector(2);
     let methods = vec![mock_nodes];
-    FullMethodIterator::new(methods);
+    FullMethodIterator::new(methods, &SearchOptions::default());
   }
 
   #[test]
@@ -374,7 +422,7 @@ mod test {
       Arc::clone(node6),
     ];
 
-    let mut iterator = new(vec![half_method1, half_method2]);
+    let mut iterator = new(vec![half_method1, half_method2], &SearchOptions::default());
     let full_method1 = iterator.next().expect("There should be a method");
     let full_method2 = iterator.next().expect("There should be a method");
     let end = iterator.next();
@@ -406,13 +454,65 @@ mod test {
     assert_eq!(end, None);
   }
 
+  #[test]
+  fn pairs_a_half_method_with_every_match_in_its_group() {
+    let nodes = set_up_node_vector(4);
+    let rounds = &nodes[0];
+    let end_node = &nodes[9];
+
+    let half_methods = [(1, 2), (3, 4), (5, 6), (7, 8)]
+      .iter()
+      .enumerate()
+      .map(|(index, &(first, second))| {
+        // The last half method ends elsewhere so is never paired
+        let last_node = if index == 3 { &nodes[10] } else { end_node };
+        vec![
+          Arc::clone(rounds),
+          Arc::clone(&nodes[first]),
+          Arc::clone(&nodes[second]),
+          Arc::clone(last_node),
+        ]
+      })
+      .collect();
+
+    let methods: Vec<StrongNodeVector> = new(half_methods, &SearchOptions::default()).collect();
+
+    assert_eq!(methods.len(), 6);
+    assert!(methods.iter().all(|method| method[3] == *end_node));
+  }
+
+  #[test]
+  fn can_leave_out_reversed_methods() {
+    let nodes = set_up_node_vector(3);
+    let half_method1 = vec![
+      Arc::clone(&nodes[0]),
+      Arc::clone(&nodes[1]),
+      Arc::clone(&nodes[4]),
+      Arc::clone(&nodes[5]),
+    ];
+    let half_method2 = vec![
+      Arc::clone(&nodes[0]),
+      Arc::clone(&nodes[2]),
+      Arc::clone(&nodes[3]),
+      Arc::clone(&nodes[5]),
+    ];
+    let options = SearchOptions::new()
+      .comparison_threads(1)
+      .include_reversed(false);
+
+    let methods: Vec<StrongNodeVector> = new(vec![half_method1, half_method2], &options).collect();
+
+    assert_eq!(methods.len(), 1);
+    assert_eq!(methods[0][1], nodes[1]);
+  }
+
   #[test]
   fn can_iterate_through_two_bell_methods() {
     let nodes = set_up_node_vector(2);
     let node1 = &nodes[0]; // [1, 2]
     let node2 = &nodes[1]; // [2, 1]
     let two_bell_half = vec![Arc::clone(node1), Arc::clone(node2)];
-    let mut iterator = new(vec![two_bell_half]);
+    let mut iterator = new(vec![two_bell_half], &SearchOptions::default());
 
     let full_method = iterator.next().expect("There should be a method");
     let end = iterator.next();
@@ -429,7 +529,7 @@ mod test {
     let nodes = set_up_node_vector(1);
     let node1 = &nodes[0]; // [1]
     let one_bell_half = vec![Arc::clone(node1)];
-    let mut iterator = new(vec![one_bell_half]);
+    let mut iterator = new(vec![one_bell_half], &SearchOptions::default());
 
     let full_method = iterator.next().expect("There should be a method");
     let end = iterator.next();
@@ -440,7 +540,7 @@ mod test {
 
   #[test]
   fn can_iterate_through_zero_bell_methods() {
-    let mut iterator = new(vec![]);
+    let mut iterator = new(vec![], &SearchOptions::default());
 
     let full_method = iterator.next().expect("There should be a method");
     let end = iterator.next();
@@ -474,9 +574,9 @@ mod test {
     ];
 
     let thread_num = 0;
-    let half_methods = Arc::new(vec![half_method1, half_method2]);
+    let half_methods = Arc::new(HalfMethodGroups::new(vec![half_method1, half_method2]));
     let (index_sender, index_receiver) = channel();
-    let (status_sender, status_receiver) = channel();
+    let (status_sender, status_receiver) = sync_channel(16);
     let full_method1 = vec![
       Arc::clone(node1),
       Arc::clone(node2),
@@ -508,4 +608,81 @@ mod test {
       ComparisonStatus::NextIndex(thread_num)
     );
   }
+
+  #[test]
+  fn stops_comparisons_once_the_consumer_has_gone() {
+    let graph = PermutationGraph::new(4);
+    let options = SearchOptions::new().filter(MethodFilter::RightPlace);
+    let half_methods = Arc::new(HalfMethodGroups::new(graph.get_half_methods(&options)));
+    let (_index_sender, index_receiver) = channel();
+    let (status_sender, status_receiver) = sync_channel(0);
+    drop(status_receiver);
+
+    // Returns rather than panicking on the closed channel
+    FullMethodIterator::run_comparisons(0, half_methods, status_sender, index_receiver);
+  }
+
+  #[test]
+  fn can_drop_the_iterator_after_one_method() {
+    let graph = PermutationGraph::new(4);
+    let options = SearchOptions::new()
+      .comparison_threads(4)
+      .channel_capacity(0)
+      .filter(MethodFilter::RightPlace);
+    let rounds = Arc::clone(graph.get_node(&[1, 2, 3, 4]).unwrap());
+    let baseline = Arc::strong_count(&rounds);
+    let half_methods = graph.get_half_methods(&options);
+    let mut iterator = FullMethodIterator::new(half_methods, &options);
+
+    assert!(iterator.next().is_some());
+    // Joins every comparison thread, panicking if any of them panicked
+    drop(iterator);
+
+    // Every method and half method has been dropped with the threads
+    assert_eq!(Arc::strong_count(&rounds), baseline);
+  }
+
+  /// The most methods alive at once while a slow consumer takes every
+  /// method, counted by the references each holds to rounds, and how many
+  /// methods were taken.
+  fn peak_methods_alive(threads: usize, capacity: usize) -> (usize, usize) {
+    let graph = PermutationGraph::new(4);
+    let options = SearchOptions::new()
+      .comparison_threads(threads)
+      .channel_capacity(capacity)
+      .filter(MethodFilter::RightPlace);
+    let half_methods = graph.get_half_methods(&options);
+    let rounds = Arc::clone(graph.get_node(&[1, 2, 3, 4]).unwrap());
+    let baseline = Arc::strong_count(&rounds);
+
+    let mut peak = 0;
+    let mut count = 0;
+    for method in FullMethodIterator::new(half_methods, &options) {
+      sleep(Duration::from_millis(5));
+      // Each method starts and ends with rounds
+      peak = peak.max((Arc::strong_count(&rounds) - baseline) / 2);
+      count += 1;
+      drop(method);
+    }
+
+    (peak, count)
+  }
+
+  #[test]
+  fn waits_for_a_slow_consumer() {
+    let (_, expected_count) = peak_methods_alive(1, 1000);
+
+    for (threads, capacity) in [(1, 0), (2, 1), (4, 2)] {
+      let (peak, count) = peak_methods_alive(threads, capacity);
+
+      assert_eq!(count, expected_count);
+      // The consumer's method and its buffered reverse, the queued pairs,
+      // and for each thread a pair waiting to be sent and a pair being built
+      let bound = 2 + 2 * capacity + 4 * threads;
+      assert!(
+        peak <= bound,
+        "{peak} methods alive, expected at most {bound}"
+      );
+    }
+  }
 }
@@ -1,4 +1,4 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
 
 pub fn send_or_error<T>(sender: &Sender<T>, msg: T) {
   sender.send(msg).unwrap_or_else(|error| {
@@ -6,15 +6,20 @@ pub fn send_or_error<T>(sender: &Sender<T>, msg: T) {
   });
 }
 
-pub fn recv_or_error<T>(receiver: &Receiver<T>) -> T {
-  receiver.recv().unwrap_or_else(|error| {
-    panic!("{}", error.to_string());
-  })
+/// Sends on a bounded channel, waiting while it is full. Returns false if
+/// the receiver has gone, which means the consumer has stopped listening.
+pub fn sync_send_or_stop<T>(sender: &SyncSender<T>, msg: T) -> bool {
+  sender.send(msg).is_ok()
+}
+
+/// Receives the next message, or None once the sender has gone.
+pub fn recv_or_stop<T>(receiver: &Receiver<T>) -> Option<T> {
+  receiver.recv().ok()
 }
 
 #[cfg(test)]
 mod test {
-  use std::sync::mpsc::channel;
+  use std::sync::mpsc::{channel, sync_channel};
 
   use super::*;
 
@@ -36,21 +41,36 @@ mod test {
     send_or_error(&sender, 8);
   }
 
+  #[test]
+  fn can_send_a_message_on_a_bounded_channel() {
+    let (sender, receiver) = sync_channel(1);
+
+    assert!(sync_send_or_stop(&sender, 3));
+    assert_eq!(receiver.recv().unwrap(), 3);
+  }
+
+  #[test]
+  fn stops_sending_on_a_closed_bounded_channel() {
+    let (sender, receiver) = sync_channel(1);
+    drop(receiver);
+
+    assert!(!sync_send_or_stop(&sender, 8));
+  }
+
   #[test]
   fn can_receive_a_message() {
     let msg = 7;
     let (sender, receiver) = channel();
 
     sender.send(msg).unwrap();
-    assert_eq!(recv_or_error(&receiver), msg);
+    assert_eq!(recv_or_stop(&receiver), Some(msg));
   }
 
   #[test]
-  #[should_panic(expected = "receiving on a closed channel")]
-  fn receiving_panics_with_the_error_message() {
+  fn stops_receiving_on_a_closed_channel() {
     let (sender, receiver) = channel::<i32>();
     drop(sender);
 
-    recv_or_error(&receiver);
+    assert_eq!(recv_or_stop(&receiver), None);
   }
 }
@@ -1,11 +1,14 @@
 mod comparison_index_store_builder;
 
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{
+  Arc,
+  mpsc::{Receiver, SyncSender},
+};
 
 pub use comparison_index_store_builder::ComparisonIndexStoreBuilder;
 
 use crate::permutation_graph::method_iterator::{
-  channel_helper::{recv_or_error, send_or_error},
+  channel_helper::{recv_or_stop, sync_send_or_stop},
   comparison_status::ComparisonStatus,
 };
 
@@ -13,25 +16,30 @@ pub struct ComparisonIndexStore {
   last_index: usize,
   start_index: usize,
   current_index: usize,
-  status_sender: Sender<ComparisonStatus>,
+  group_last_indexes: Arc<Vec<usize>>,
+  status_sender: SyncSender<ComparisonStatus>,
   index_receiver: Receiver<usize>,
   comparison_index: usize,
+  stopped: bool,
 }
 
 impl ComparisonIndexStore {
   pub fn new(
     last_index: usize,
     start_index: usize,
-    status_sender: Sender<ComparisonStatus>,
+    group_last_indexes: Arc<Vec<usize>>,
+    status_sender: SyncSender<ComparisonStatus>,
     index_receiver: Receiver<usize>,
   ) -> ComparisonIndexStore {
     ComparisonIndexStore {
       last_index,
       start_index,
       current_index: start_index,
+      group_last_indexes,
       status_sender,
       index_receiver,
       comparison_index: Self::get_start_comparison_index(last_index, start_index),
+      stopped: false,
     }
   }
 
@@ -44,19 +52,33 @@ impl ComparisonIndexStore {
   }
 
   pub fn increment_indexes(&mut self) {
+    let comparison_last_index = self.get_comparison_last_index();
     let ComparisonIndexStore {
-      last_index,
       start_index,
       current_index,
       status_sender,
       index_receiver,
       comparison_index,
+      stopped,
+      ..
     } = self;
 
-    if *comparison_index == *last_index + 1 {
-      send_or_error(status_sender, ComparisonStatus::NextIndex(*start_index));
-      *current_index = recv_or_error(index_receiver);
-      *comparison_index = *current_index + 1;
+    if *comparison_index > comparison_last_index {
+      let next_index =
+        if sync_send_or_stop(status_sender, ComparisonStatus::NextIndex(*start_index)) {
+          recv_or_stop(index_receiver)
+        } else {
+          None
+        };
+
+      match next_index {
+        Some(next_index) => {
+          *current_index = next_index;
+          *comparison_index = *current_index + 1;
+        }
+        // The iterator has been dropped, so nobody wants any more methods
+        None => *stopped = true,
+      }
     } else {
       *comparison_index += 1;
     };
@@ -71,11 +93,20 @@ impl ComparisonIndexStore {
   }
 
   pub fn is_running(&self) -> bool {
-    self.current_index < self.last_index
+    !self.stopped && self.current_index < self.last_index
   }
 
   pub fn is_running_comparison(&self) -> bool {
-    self.comparison_index <= self.last_index
+    !self.stopped && self.comparison_index <= self.get_comparison_last_index()
+  }
+
+  // Only methods in the same group as the current one are compared against it
+  fn get_comparison_last_index(&self) -> usize {
+    self
+      .group_last_indexes
+      .get(self.current_index)
+      .copied()
+      .unwrap_or(self.last_index)
   }
 }
 
@@ -87,6 +118,8 @@ mod test {
 
   use super::*;
 
+  const TEST_CHANNEL_CAPACITY: usize = 16;
+
   struct BuilderWithChannels {
     builder: ComparisonIndexStoreBuilder,
     index_sender: Sender<usize>,
@@ -96,8 +129,8 @@ mod test {
   #[test]
   fn can_create_new() {
     let (_, test_receiver) = mpsc::channel();
-    let (test_sender, _) = mpsc::channel();
-    ComparisonIndexStore::new(5, 0, test_sender, test_receiver);
+    let (test_sender, _) = mpsc::sync_channel(1);
+    ComparisonIndexStore::new(5, 0, Arc::new(vec![5; 6]), test_sender, test_receiver);
   }
 
   #[test]
@@ -240,6 +273,81 @@ mod test {
     assert!(index_store.is_running_comparison());
   }
 
+  #[test]
+  fn only_compares_within_the_current_group() {
+    let BuilderWithChannels {
+      builder,
+      index_sender,
+      status_receiver,
+    } = create_builder_with_channels();
+
+    let mut index_store = builder
+      .last_index(4)
+      .start_index(0)
+      .group_last_indexes(Arc::new(vec![1, 1, 4, 4, 4]))
+      .build();
+
+    assert_indexes(&index_store, 0, 1);
+    assert!(index_store.is_running_comparison());
+
+    index_store.increment_indexes();
+    assert!(!index_store.is_running_comparison());
+
+    index_sender.send(2).unwrap();
+    index_store.increment_indexes();
+
+    assert_eq!(
+      status_receiver.recv().unwrap(),
+      ComparisonStatus::NextIndex(0)
+    );
+    assert_indexes(&index_store, 2, 3);
+
+    index_store.increment_indexes();
+    assert_indexes(&index_store, 2, 4);
+    assert!(index_store.is_running_comparison());
+
+    index_store.increment_indexes();
+    assert!(!index_store.is_running_comparison());
+  }
+
+  #[test]
+  fn stops_once_the_consumer_has_gone() {
+    let BuilderWithChannels {
+      builder,
+      index_sender,
+      status_receiver,
+    } = create_builder_with_channels();
+
+    let mut index_store = builder.last_index(3).start_index(0).build();
+    index_store.increment_indexes();
+    index_store.increment_indexes();
+    index_store.increment_indexes();
+    drop(status_receiver);
+    drop(index_sender);
+    index_store.increment_indexes();
+
+    assert!(!index_store.is_running());
+    assert!(!index_store.is_running_comparison());
+  }
+
+  #[test]
+  fn stops_once_no_next_index_is_sent() {
+    let BuilderWithChannels {
+      builder,
+      index_sender,
+      status_receiver: _status_receiver,
+    } = create_builder_with_channels();
+
+    let mut index_store = builder.last_index(2).start_index(0).build();
+    index_store.increment_indexes();
+    index_store.increment_indexes();
+    drop(index_sender);
+    index_store.increment_indexes();
+
+    assert!(!index_store.is_running());
+    assert!(!index_store.is_running_comparison());
+  }
+
   #[test]
   fn does_not_run_comparison_if_starting_index_is_greater_than_or_equal_to_last_index() {
     let builder1 = create_builder_with_channels().builder;
@@ -261,7 +369,7 @@ mod test {
 
   fn create_builder_with_channels() -> BuilderWithChannels {
     let (index_sender, index_receiver) = mpsc::channel();
-    let (status_sender, status_receiver) = mpsc::channel();
+    let (status_sender, status_receiver) = mpsc::sync_channel(TEST_CHANNEL_CAPACITY);
     let builder = ComparisonIndexStoreBuilder::new()
       .last_index(0)
       .start_index(0)
@@ -1,4 +1,7 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{
+  Arc,
+  mpsc::{Receiver, SyncSender},
+};
 
 use crate::permutation_graph::method_iterator::{
   comparison_index_store::ComparisonIndexStore, comparison_status::ComparisonStatus,
@@ -7,7 +10,8 @@ use crate::permutation_graph::method_iterator::{
 pub struct ComparisonIndexStoreBuilder {
   last_index: Option<usize>,
   start_index: Option<usize>,
-  status_sender: Option<Sender<ComparisonStatus>>,
+  group_last_indexes: Option<Arc<Vec<usize>>>,
+  status_sender: Option<SyncSender<ComparisonStatus>>,
   index_receiver: Option<Receiver<usize>>,
 }
 
@@ -16,6 +20,7 @@ impl ComparisonIndexStoreBuilder {
     ComparisonIndexStoreBuilder {
       last_index: None,
       start_index: None,
+      group_last_indexes: None,
       status_sender: None,
       index_receiver: None,
     }
@@ -31,7 +36,12 @@ impl ComparisonIndexStoreBuilder {
     self
   }
 
-  pub fn status_sender(mut self, sender: Sender<ComparisonStatus>) -> Self {
+  pub fn group_last_indexes(mut self, group_last_indexes: Arc<Vec<usize>>) -> Self {
+    self.group_last_indexes = Some(group_last_indexes);
+    self
+  }
+
+  pub fn status_sender(mut self, sender: SyncSender<ComparisonStatus>) -> Self {
     self.status_sender = Some(sender);
     self
   }
@@ -41,10 +51,16 @@ impl ComparisonIndexStoreBuilder {
     self
   }
 
+  // Without groups every method is compared against all the methods after it
   pub fn build(self) -> ComparisonIndexStore {
+    let last_index = self.last_index.expect("Must provide field 'last_index'");
+
     ComparisonIndexStore::new(
-      self.last_index.expect("Must provide field 'last_index'"),
+      last_index,
       self.start_index.expect("Must provide field 'start_index'"),
+      self
+        .group_last_indexes
+        .unwrap_or_else(|| Arc::new(vec![last_index; last_index + 1])),
       self
         .status_sender
         .expect("Must provide field 'status_sender'"),
@@ -69,7 +85,7 @@ mod test {
   #[test]
   fn can_build_a_comparison_index_store() {
     let (index_sender, index_receiver) = mpsc::channel();
-    let (status_sender, status_receiver) = mpsc::channel();
+    let (status_sender, status_receiver) = mpsc::sync_channel(1);
     let builder = ComparisonIndexStoreBuilder::new();
     let test_last_index = 5;
     let test_start_index = 1;
@@ -84,6 +100,10 @@ mod test {
     assert_eq!(index_store.last_index, test_last_index);
     assert_eq!(index_store.current_index, test_start_index);
     assert_eq!(index_store.comparison_index, test_start_index + 1);
+    assert_eq!(
+      index_store.group_last_indexes.as_slice(),
+      &[test_last_index; 6]
+    );
 
     let sent_index = 8;
     index_sender.send(sent_index).unwrap();
@@ -100,11 +120,28 @@ mod test {
     );
   }
 
+  #[test]
+  fn can_build_with_group_last_indexes() {
+    let (_, index_receiver) = mpsc::channel();
+    let (status_sender, _) = mpsc::sync_channel(1);
+    let group_last_indexes = Arc::new(vec![1, 1, 2]);
+
+    let index_store = ComparisonIndexStoreBuilder::new()
+      .last_index(2)
+      .start_index(0)
+      .group_last_indexes(Arc::clone(&group_last_indexes))
+      .status_sender(status_sender)
+      .index_receiver(index_receiver)
+      .build();
+
+    assert_eq!(index_store.group_last_indexes, group_last_indexes);
+  }
+
   #[test]
   #[should_panic(expected = "Must provide field 'last_index'")]
   fn panics_if_last_index_not_filled() {
     let (_, index_receiver) = mpsc::channel();
-    let (status_sender, _) = mpsc::channel();
+    let (status_sender, _) = mpsc::sync_channel(1);
     let builder = ComparisonIndexStoreBuilder::new();
 
     builder
@@ -118,7 +155,7 @@ mod test {
   #[should_panic(expected = "Must provide field 'start_index'")]
   fn panics_if_start_index_not_filled() {
     let (_, index_receiver) = mpsc::channel();
-    let (status_sender, _) = mpsc::channel();
+    let (status_sender, _) = mpsc::sync_channel(1);
     let builder = ComparisonIndexStoreBuilder::new();
 
     builder
@@ -144,7 +181,7 @@ mod test {
   #[test]
   #[should_panic(expected = "Must provide field 'index_receiver'")]
   fn panics_if_index_receiver_not_filled() {
-    let (status_sender, _) = mpsc::channel();
+    let (status_sender, _) = mpsc::sync_channel(1);
     let builder = ComparisonIndexStoreBuilder::new();
 
     builder
@@ -0,0 +1,172 @@
+use std::{collections::HashMap, iter::repeat_n, sync::Arc};
+
+use crate::permutation_graph::{permutation_node::StrongNodeVector, row_set::RowSet};
+
+pub struct HalfMethodGroups {
+  half_methods: Vec<StrongNodeVector>,
+  inner_row_sets: Vec<RowSet>,
+  group_last_indexes: Arc<Vec<usize>>,
+}
+
+impl HalfMethodGroups {
+  pub fn new(half_methods: Vec<StrongNodeVector>) -> HalfMethodGroups {
+    Self::from_groups(Self::group_by_end_node(half_methods))
+  }
+
+  /// Every half method in one group, so each is compared with every later
+  /// one whatever row it ends on.
+  pub fn ungrouped(half_methods: Vec<StrongNodeVector>) -> HalfMethodGroups {
+    match half_methods.is_empty() {
+      true => Self::from_groups(Vec::new()),
+      false => Self::from_groups(vec![half_methods]),
+    }
+  }
+
+  fn from_groups(groups: Vec<Vec<StrongNodeVector>>) -> HalfMethodGroups {
+    let mut grouped_half_methods = Vec::with_capacity(groups.iter().map(Vec::len).sum());
+    let mut group_last_indexes = Vec::with_capacity(grouped_half_methods.capacity());
+
+    for mut group in groups {
+      let group_last_index = grouped_half_methods.len() + group.len() - 1;
+      group_last_indexes.extend(repeat_n(group_last_index, group.len()));
+      grouped_half_methods.append(&mut group);
+    }
+
+    HalfMethodGroups {
+      inner_row_sets: grouped_half_methods
+        .iter()
+        .map(Self::get_inner_row_set)
+        .collect(),
+      half_methods: grouped_half_methods,
+      group_last_indexes: Arc::new(group_last_indexes),
+    }
+  }
+
+  // Every half method starts at rounds and ends on its group's joining
+  // node, so only the rows in between can clash
+  fn get_inner_row_set(half_method: &StrongNodeVector) -> RowSet {
+    if half_method.len() > 2 {
+      RowSet::from_nodes(&half_method[1..half_method.len() - 1])
+    } else {
+      RowSet::new()
+    }
+  }
+
+  // Groups keep the order in which their end nodes first appear, and
+  // each group keeps the relative order of its half methods
+  fn group_by_end_node(half_methods: Vec<StrongNodeVector>) -> Vec<Vec<StrongNodeVector>> {
+    let mut group_indexes: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut groups: Vec<Vec<StrongNodeVector>> = Vec::new();
+
+    for half_method in half_methods {
+      let end_node = half_method.last().expect("All methods should be populated");
+      let end_row = end_node.get_permutation().clone();
+      let group_index = *group_indexes.entry(end_row).or_insert_with(|| {
+        groups.push(Vec::new());
+        groups.len() - 1
+      });
+
+      groups[group_index].push(half_method);
+    }
+
+    groups
+  }
+
+  pub fn get_half_methods(&self) -> &[StrongNodeVector] {
+    &self.half_methods
+  }
+
+  pub fn get_group_last_indexes(&self) -> &Arc<Vec<usize>> {
+    &self.group_last_indexes
+  }
+
+  pub fn are_disjoint(&self, index1: usize, index2: usize) -> bool {
+    self.inner_row_sets[index1].is_disjoint(&self.inner_row_sets[index2])
+  }
+
+  pub fn len(&self) -> usize {
+    self.half_methods.len()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::permutation_graph::utility::test::set_up_node_vector;
+
+  use super::*;
+
+  #[test]
+  fn groups_half_methods_by_end_node() {
+    let nodes = set_up_node_vector(3);
+    let half_method1 = vec![Arc::clone(&nodes[0]), Arc::clone(&nodes[5])];
+    let half_method2 = vec![Arc::clone(&nodes[0]), Arc::clone(&nodes[3])];
+    let half_method3 = vec![Arc::clone(&nodes[1]), Arc::clone(&nodes[5])];
+
+    let groups = HalfMethodGroups::new(vec![
+      half_method1.clone(),
+      half_method2.clone(),
+      half_method3.clone(),
+    ]);
+
+    assert_eq!(
+      groups.get_half_methods(),
+      &[half_method1, half_method3, half_method2]
+    );
+    assert_eq!(groups.get_group_last_indexes().as_slice(), &[1, 1, 2]);
+    assert_eq!(groups.len(), 3);
+  }
+
+  #[test]
+  fn can_leave_half_methods_ungrouped() {
+    let nodes = set_up_node_vector(3);
+    let half_method1 = vec![Arc::clone(&nodes[0]), Arc::clone(&nodes[5])];
+    let half_method2 = vec![Arc::clone(&nodes[0]), Arc::clone(&nodes[3])];
+    let half_method3 = vec![Arc::clone(&nodes[1]), Arc::clone(&nodes[5])];
+    let half_methods = vec![half_method1, half_method2, half_method3];
+
+    let groups = HalfMethodGroups::ungrouped(half_methods.clone());
+
+    assert_eq!(groups.get_half_methods(), half_methods.as_slice());
+    assert_eq!(groups.get_group_last_indexes().as_slice(), &[2, 2, 2]);
+    assert_eq!(HalfMethodGroups::ungrouped(vec![]).len(), 0);
+  }
+
+  #[test]
+  fn can_check_half_methods_share_no_inner_rows() {
+    let nodes = set_up_node_vector(3);
+    let rounds = &nodes[0];
+    let end_node = &nodes[5];
+    let half_method1 = vec![
+      Arc::clone(rounds),
+      Arc::clone(&nodes[1]),
+      Arc::clone(&nodes[4]),
+      Arc::clone(end_node),
+    ];
+    let half_method2 = vec![
+      Arc::clone(rounds),
+      Arc::clone(&nodes[2]),
+      Arc::clone(&nodes[3]),
+      Arc::clone(end_node),
+    ];
+    let half_method3 = vec![
+      Arc::clone(rounds),
+      Arc::clone(&nodes[1]),
+      Arc::clone(&nodes[3]),
+      Arc::clone(end_node),
+    ];
+
+    let groups = HalfMethodGroups::new(vec![half_method1, half_method2, half_method3]);
+
+    assert!(groups.are_disjoint(0, 1));
+    assert!(!groups.are_disjoint(0, 2));
+    assert!(!groups.are_disjoint(1, 2));
+  }
+
+  #[test]
+  fn handles_no_half_methods() {
+    let groups = HalfMethodGroups::new(vec![]);
+
+    assert!(groups.get_half_methods().is_empty());
+    assert!(groups.get_group_last_indexes().is_empty());
+  }
+}
@@ -15,26 +15,22 @@ impl ThreadAndCup {
     ThreadAndCup { cup, thread }
   }
 
+  /// Waits for the thread to finish, first dropping the cup so a thread
+  /// waiting for its next index stops instead of waiting forever.
   pub fn join_thread(self) -> Result<()> {
-    self.thread.join()
+    let ThreadAndCup { cup, thread } = self;
+    drop(cup);
+    thread.join()
   }
 
   pub fn send_to_thread(&self, value: usize) {
     send_or_error(&self.cup, value);
   }
-
-  pub fn is_thread_running(&self) -> bool {
-    !self.thread.is_finished()
-  }
 }
 
 #[cfg(test)]
 mod test {
-  use std::{
-    sync::mpsc::channel,
-    thread::{self, sleep},
-    time::Duration,
-  };
+  use std::{sync::mpsc::channel, thread};
 
   use super::*;
 
@@ -73,17 +69,4 @@ mod test {
     assert_eq!(test_receiver.recv().unwrap(), value);
     thread_and_cup.join_thread().unwrap();
   }
-
-  #[test]
-  fn can_check_if_thread_is_still_running() {
-    let (sender, _) = channel();
-    let thread = thread::spawn(|| {
-      sleep(Duration::from_millis(300));
-    });
-    let thread_and_cup = ThreadAndCup::new(thread, sender);
-
-    assert!(thread_and_cup.is_thread_running());
-
-    thread_and_cup.join_thread().unwrap();
-  }
 }
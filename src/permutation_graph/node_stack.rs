@@ -1,11 +1,14 @@
 mod node_and_index;
+pub mod path_directions;
 
 use std::sync::Arc;
 
 use crate::permutation_graph::{
-  node_stack::node_and_index::NodeAndIndex,
+  method_filter::MethodFilter,
+  node_stack::{node_and_index::NodeAndIndex, path_directions::PathDirections},
   permutation_node::{PermutationNode, StrongNodeVector},
-  utility::{are_unique, factorial},
+  treble_path::TreblePath,
+  utility::are_unique,
 };
 
 pub enum PushStatus {
@@ -22,18 +25,46 @@ pub enum NodeChain {
 pub struct NodeStack {
   stack_max: usize,
   node_stack: Vec<NodeAndIndex>,
+  preceding_node: Option<Arc<PermutationNode>>,
+  filter: MethodFilter,
+  treble_path: Option<(TreblePath, usize)>,
+  path_directions: Vec<PathDirections>,
 }
 
 impl NodeStack {
-  pub fn new(first_node: &Arc<PermutationNode>) -> NodeStack {
-    let stack_max = factorial(first_node.get_permutation().len()) / 2;
-
+  /// A stack finding chains of `stack_max` rows starting at `first_node`,
+  /// only pushing nodes whose change is allowed by `filter`, where
+  /// `preceding_node` is the row rung before `first_node`.
+  pub fn new(
+    first_node: &Arc<PermutationNode>,
+    stack_max: usize,
+    preceding_node: Option<&Arc<PermutationNode>>,
+    filter: MethodFilter,
+  ) -> NodeStack {
     NodeStack {
       stack_max,
       node_stack: Self::initialise_node_stack(first_node, stack_max),
+      preceding_node: preceding_node.map(Arc::clone),
+      filter,
+      treble_path: None,
+      path_directions: vec![PathDirections::any()],
     }
   }
 
+  /// Only pushes nodes keeping the treble on `path`, rung either forwards
+  /// from rounds or backwards into rounds at the end of `method_length`
+  /// changes. The first node is rung after rounds.
+  pub fn with_treble_path(mut self, path: TreblePath, method_length: usize) -> NodeStack {
+    let first_node = self.node_stack[0].get_node();
+    self.path_directions = vec![
+      PathDirections::any()
+        .follow(&path, method_length, 1, first_node.get_permutation())
+        .expect("The first node should follow the treble path"),
+    ];
+    self.treble_path = Some((path, method_length));
+    self
+  }
+
   fn initialise_node_stack(
     first_node: &Arc<PermutationNode>,
     stack_max: usize,
@@ -46,6 +77,7 @@ impl NodeStack {
 
   pub fn pop(&mut self) {
     self.node_stack.pop();
+    self.path_directions.pop();
   }
 
   pub fn push_next(&mut self) -> PushStatus {
@@ -53,13 +85,45 @@ impl NodeStack {
       return PushStatus::HalfMethod(self.get_unique_node_chain());
     }
 
+    let stack_len = self.node_stack.len();
+    let before_previous = if stack_len > 1 {
+      Some(Arc::clone(self.node_stack[stack_len - 2].get_node()))
+    } else {
+      self.preceding_node.as_ref().map(Arc::clone)
+    };
     let node_and_index = self
       .node_stack
       .last_mut()
       .expect("Node stack should always contain at least one node");
+    let previous = Arc::clone(node_and_index.get_node());
+    let previous_directions = *self
+      .path_directions
+      .last()
+      .expect("Every node should have its path directions");
+    let follow_path = |next: &NodeAndIndex| match &self.treble_path {
+      Some((path, method_length)) => previous_directions.follow(
+        path,
+        *method_length,
+        stack_len + 1,
+        next.get_node().get_permutation(),
+      ),
+      None => Some(previous_directions),
+    };
 
-    match node_and_index.next() {
+    let allowed_node = node_and_index.find(|next| {
+      self.filter.allows_change(
+        before_previous
+          .as_ref()
+          .map(|node| node.get_permutation().as_slice()),
+        previous.get_permutation(),
+        next.get_node().get_permutation(),
+      ) && follow_path(next).is_some()
+    });
+
+    match allowed_node {
       Some(node) => {
+        let directions = follow_path(&node).expect("Pushed nodes follow the treble path");
+        self.path_directions.push(directions);
         self.node_stack.push(node);
         PushStatus::Next
       }
@@ -90,24 +154,46 @@ impl NodeStack {
 mod test {
   use crate::permutation_graph::{
     node_stack::node_and_index::NodeAndIndex,
-    utility::test::{build_node_graph, create_mock_permutation_node, set_up_node_vector},
+    utility::{
+      factorial,
+      test::{build_node_graph, create_mock_permutation_node, set_up_node_vector},
+    },
   };
 
   use super::*;
 
+  fn new_unfiltered_stack(first_node: &Arc<PermutationNode>) -> NodeStack {
+    let stack_max = factorial(first_node.get_permutation().len()) / 2;
+    NodeStack::new(first_node, stack_max, None, MethodFilter::default())
+  }
+
   #[test]
   fn can_make_a_node_stack() {
     let node = create_mock_permutation_node();
-    let node_stack = NodeStack::new(&node);
+    let node_stack = new_unfiltered_stack(&node);
     let node_and_index = NodeAndIndex::new(&node);
 
     assert_eq!(node_stack.node_stack, vec![node_and_index]);
   }
 
+  #[test]
+  fn only_pushes_nodes_the_filter_allows() {
+    let nodes = set_up_node_vector(4);
+    build_node_graph(&nodes);
+    let mut node_stack = NodeStack::new(&nodes[1], 12, Some(&nodes[0]), MethodFilter::NoLongPlaces);
+
+    // 1243 to 1423 would keep the treble leading for a third row
+    node_stack.push_next();
+    assert_eq!(
+      *node_stack.node_stack[1].get_node().get_permutation(),
+      vec![2, 1, 3, 4]
+    );
+  }
+
   #[test]
   fn can_pop_the_stack() {
     let node = create_mock_permutation_node();
-    let mut node_stack = NodeStack::new(&node);
+    let mut node_stack = new_unfiltered_stack(&node);
     node_stack.pop();
 
     assert_eq!(node_stack.node_stack, vec![]);
@@ -117,7 +203,7 @@ mod test {
   fn can_push_next_node() {
     let nodes = set_up_node_vector(3);
     let node1 = &nodes[0];
-    let mut node_stack = NodeStack::new(node1);
+    let mut node_stack = new_unfiltered_stack(node1);
 
     node1.extract_valid_permutations(&nodes);
 
@@ -130,7 +216,7 @@ mod test {
   fn push_next_indicates_if_no_more_next_nodes() {
     let nodes = set_up_node_vector(3);
     let node1 = &nodes[0];
-    let mut node_stack = NodeStack::new(node1);
+    let mut node_stack = new_unfiltered_stack(node1);
 
     node1.extract_valid_permutations(&nodes);
 
@@ -148,7 +234,7 @@ mod test {
   fn push_next_passes_back_a_valid_half_method() {
     let nodes = set_up_node_vector(2);
     let node1 = &nodes[0];
-    let mut node_stack = NodeStack::new(node1);
+    let mut node_stack = new_unfiltered_stack(node1);
 
     node1.extract_valid_permutations(&nodes);
 
@@ -165,7 +251,7 @@ mod test {
     build_node_graph(&nodes);
     let node1 = &nodes[0];
 
-    let mut node_stack = NodeStack::new(node1);
+    let mut node_stack = new_unfiltered_stack(node1);
 
     if let PushStatus::HalfMethod(chain) = node_stack.push_next() {
       match chain {
@@ -180,7 +266,7 @@ mod test {
     let nodes: StrongNodeVector = set_up_node_vector(3);
     build_node_graph(&nodes);
 
-    let mut node_stack = NodeStack::new(&nodes[1]);
+    let mut node_stack = new_unfiltered_stack(&nodes[1]);
     node_stack.push_next(); // [3, 1, 2]
     node_stack.push_next(); // [1, 3, 2]
 
@@ -196,7 +282,7 @@ mod test {
   #[test]
   fn can_check_is_empty() {
     let node = create_mock_permutation_node();
-    let mut node_stack = NodeStack::new(&node);
+    let mut node_stack = new_unfiltered_stack(&node);
 
     assert!(!node_stack.is_empty());
 
@@ -63,7 +63,7 @@ mod test {
 
     let mut node_and_index = NodeAndIndex::new(&nodes[0]);
 
-    if let None = node_and_index.next() {
+    if node_and_index.next().is_none() {
       panic!("Should have a next node");
     }
   }
@@ -75,10 +75,10 @@ mod test {
 
     let mut node_and_index = NodeAndIndex::new(&nodes[0]);
 
-    if let None = node_and_index.next() {
+    if node_and_index.next().is_none() {
       panic!("Should have a next node");
     }
-    if let Some(_) = node_and_index.next() {
+    if node_and_index.next().is_some() {
       panic!("Should not have a next node");
     }
   }
@@ -105,6 +105,6 @@ mod test {
     let node_and_index2 = NodeAndIndex::new(node2);
 
     assert_eq!(&node_and_index1, &node_and_index1);
-    assert!(!(&node_and_index1 == &node_and_index2));
+    assert!(!(node_and_index1 == node_and_index2));
   }
 }
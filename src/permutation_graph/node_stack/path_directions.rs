@@ -0,0 +1,71 @@
+use crate::permutation_graph::treble_path::TreblePath;
+
+/// Which ways round a chain of rows from rounds can still follow a treble
+/// path: as the first half of a method, or reversed as the second half.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathDirections {
+  forwards: bool,
+  backwards: bool,
+}
+
+impl PathDirections {
+  pub fn any() -> PathDirections {
+    PathDirections {
+      forwards: true,
+      backwards: true,
+    }
+  }
+
+  /// The directions still open once `row` is rung at `row_index`, or `None`
+  /// if neither is. Reversed, the row is rung `row_index` rows before the
+  /// end of a method of `method_length` changes.
+  pub fn follow(
+    &self,
+    path: &TreblePath,
+    method_length: usize,
+    row_index: usize,
+    row: &[u8],
+  ) -> Option<PathDirections> {
+    let directions = PathDirections {
+      forwards: self.forwards && path.allows_row(row_index, row),
+      backwards: self.backwards
+        && row_index <= method_length
+        && path.allows_row(method_length - row_index, row),
+    };
+
+    (directions.forwards || directions.backwards).then_some(directions)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn follows_the_path_either_way_round() {
+    let path = TreblePath::plain_hunt(3);
+    let directions = PathDirections::any();
+
+    // Row 1 has the treble in 2nds rung forwards, and leading as row 6 - 1 backwards
+    let forwards = directions.follow(&path, 6, 1, &[2, 1, 3]).unwrap();
+    let backwards = directions.follow(&path, 6, 1, &[1, 3, 2]).unwrap();
+
+    assert_eq!(
+      forwards,
+      PathDirections {
+        forwards: true,
+        backwards: false
+      }
+    );
+    assert_eq!(
+      backwards,
+      PathDirections {
+        forwards: false,
+        backwards: true
+      }
+    );
+    assert_eq!(directions.follow(&path, 6, 1, &[2, 3, 1]), None);
+    assert_eq!(forwards.follow(&path, 6, 2, &[2, 3, 1]), Some(forwards));
+    assert_eq!(forwards.follow(&path, 6, 2, &[3, 1, 2]), None);
+  }
+}
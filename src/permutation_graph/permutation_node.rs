@@ -4,35 +4,66 @@ use std::sync::{Arc, OnceLock, Weak};
 
 use permutations::Permutation;
 
-use change_validation::is_valid_change;
+pub use change_validation::is_valid_change;
+
+use crate::{permutation_graph::utility::lexicographic_rank, row::Row};
 
 pub type WeakNodeVector = Vec<Weak<PermutationNode>>;
 pub type StrongNodeVector = Vec<Arc<PermutationNode>>;
 
+/// A row of the change graph along with the rows reachable from it in one change.
 #[derive(Debug)]
 pub struct PermutationNode {
   is_rounds: bool,
-  permutation: Vec<u8>,
+  row: Row,
+  rank: usize,
   valid_permutations: OnceLock<WeakNodeVector>,
 }
 
 impl PermutationNode {
   pub fn new(permutation: Permutation) -> PermutationNode {
-    let identity = Permutation::identity(permutation.len());
-    let range: Vec<u8> = (1u8..(permutation.len() as u8) + 1u8).collect();
+    let row = Row::from(&permutation);
+    let rank = lexicographic_rank(row.get_bells());
+    Self::from_row(row, rank)
+  }
+
+  /// A node for a row at position `rank` among the rows of its graph.
+  pub fn from_row(row: Row, rank: usize) -> PermutationNode {
     PermutationNode {
-      is_rounds: permutation.eq(&identity),
-      permutation: permutation.permute(&range),
+      is_rounds: row.is_rounds(),
+      rank,
+      row,
       valid_permutations: OnceLock::new(),
     }
   }
 
-  fn is_rounds(&self) -> bool {
+  /// Whether the row is rounds, e.g. `[1, 2, 3, 4]`.
+  pub fn is_rounds(&self) -> bool {
     self.is_rounds
   }
 
+  /// The row, with bells numbered from 1.
   pub fn get_permutation(&self) -> &Vec<u8> {
-    &self.permutation
+    self.row.get_bells()
+  }
+
+  pub fn get_row(&self) -> &Row {
+    &self.row
+  }
+
+  /// Position of the row when all rows of its graph are sorted, rounds being 0.
+  pub fn get_rank(&self) -> usize {
+    self.rank
+  }
+
+  /// This row multiplied by `other`, i.e. `other` rung starting from this row.
+  pub fn multiply(&self, other: &PermutationNode) -> Row {
+    &self.row * &other.row
+  }
+
+  /// The row which multiplied by this one gives rounds.
+  pub fn get_inverse(&self) -> Row {
+    self.row.inverse()
   }
 
   pub fn extract_valid_permutations(&self, permutations: &[Arc<PermutationNode>]) {
@@ -52,27 +83,43 @@ impl PermutationNode {
 
   fn is_valid_permutation(&self, permutation: &Arc<PermutationNode>) -> bool {
     !permutation.is_rounds()
-      && permutation.permutation != self.permutation
+      && permutation.row != self.row
       && is_valid_change(self.get_permutation(), permutation.get_permutation())
   }
 
+  /// Rows reachable in one change, rounds excepted.
   pub fn get_valid_permutations(&self) -> &WeakNodeVector {
     self
       .valid_permutations
       .get()
       .expect("Valid permutations should be initialised before use")
   }
+
+  /// Rows reachable in one change, rounds excepted, while their graph is alive.
+  pub fn get_neighbours(&self) -> StrongNodeVector {
+    self
+      .get_valid_permutations()
+      .iter()
+      .filter_map(Weak::upgrade)
+      .collect()
+  }
+
+  pub fn get_degree(&self) -> usize {
+    self.get_valid_permutations().len()
+  }
 }
 
 impl PartialEq for PermutationNode {
   fn eq(&self, other: &Self) -> bool {
-    self.permutation == other.permutation
+    self.row == other.row
   }
 }
 
 #[cfg(test)]
 mod test {
-  use crate::permutation_graph::utility::test::{build_node_graph, get_valid_permutation};
+  use crate::permutation_graph::utility::test::{
+    build_node_graph, get_valid_permutation, set_up_node_vector,
+  };
 
   use super::*;
 
@@ -84,6 +131,32 @@ mod test {
     assert_eq!(vec![1, 2, 3], *node.get_permutation());
   }
 
+  #[test]
+  fn can_get_rank() {
+    let rounds_node = PermutationNode::new(Permutation::identity(3));
+    let last_node = PermutationNode::new(Permutations::new(3).get(5).unwrap());
+
+    assert_eq!(rounds_node.get_rank(), 0);
+    assert_eq!(last_node.get_rank(), 5);
+  }
+
+  #[test]
+  fn can_multiply_and_invert() {
+    let nodes: Vec<PermutationNode> = Permutations::new(3)
+      .iter()
+      .map(PermutationNode::new)
+      .collect();
+    for node in nodes.iter() {
+      assert_eq!(node.multiply(&nodes[0]), *node.get_row());
+      assert_eq!(nodes[0].multiply(node), *node.get_row());
+      let inverse = nodes
+        .iter()
+        .find(|other| *other.get_row() == node.get_inverse())
+        .unwrap();
+      assert!(node.multiply(inverse).is_rounds());
+    }
+  }
+
   #[test]
   fn initialises_rounds_node_with_flag() {
     let permutation = Permutation::identity(3);
@@ -113,13 +186,28 @@ mod test {
     build_node_graph(&nodes);
 
     let valid_perms = nodes[0].get_valid_permutations();
-    let first_first_valid = get_valid_permutation(&valid_perms, 0);
+    let first_first_valid = get_valid_permutation(valid_perms, 0);
 
     assert_eq!(nodes[0].get_valid_permutations().len(), 1);
     assert_eq!(first_first_valid.get_permutation(), &vec![2, 1]);
     assert!(nodes[1].get_valid_permutations().is_empty());
   }
 
+  #[test]
+  fn can_get_neighbours_and_degree() {
+    let nodes = set_up_node_vector(3);
+    build_node_graph(&nodes);
+
+    let neighbours = nodes[0].get_neighbours();
+
+    assert_eq!(
+      neighbours,
+      vec![Arc::clone(&nodes[1]), Arc::clone(&nodes[2])]
+    );
+    assert_eq!(nodes[0].get_degree(), 2);
+    assert_eq!(nodes[1].get_degree(), 1);
+  }
+
   #[test]
   #[should_panic(expected = "Valid permutations must only be set once")]
   fn valid_permutations_panic_if_setting_a_second_time() {
@@ -162,53 +250,53 @@ mod test {
 
     assert_eq!(valid_permutations1.len(), 2);
     assert_eq!(
-      get_valid_permutation(&valid_permutations1, 0).get_permutation(),
+      get_valid_permutation(valid_permutations1, 0).get_permutation(),
       &vec![1, 3, 2]
     );
     assert_eq!(
-      get_valid_permutation(&valid_permutations1, 1).get_permutation(),
+      get_valid_permutation(valid_permutations1, 1).get_permutation(),
       &vec![2, 1, 3]
     );
 
     assert_eq!(valid_permutations2.len(), 1);
     assert_eq!(
-      get_valid_permutation(&valid_permutations2, 0).get_permutation(),
+      get_valid_permutation(valid_permutations2, 0).get_permutation(),
       &vec![3, 1, 2]
     );
 
     assert_eq!(valid_permutations3.len(), 1);
     assert_eq!(
-      get_valid_permutation(&valid_permutations3, 0).get_permutation(),
+      get_valid_permutation(valid_permutations3, 0).get_permutation(),
       &vec![2, 3, 1]
     );
 
     assert_eq!(valid_permutations4.len(), 2);
     assert_eq!(
-      get_valid_permutation(&valid_permutations4, 0).get_permutation(),
+      get_valid_permutation(valid_permutations4, 0).get_permutation(),
       &vec![2, 1, 3]
     );
     assert_eq!(
-      get_valid_permutation(&valid_permutations4, 1).get_permutation(),
+      get_valid_permutation(valid_permutations4, 1).get_permutation(),
       &vec![3, 2, 1]
     );
 
     assert_eq!(valid_permutations5.len(), 2);
     assert_eq!(
-      get_valid_permutation(&valid_permutations5, 0).get_permutation(),
+      get_valid_permutation(valid_permutations5, 0).get_permutation(),
       &vec![1, 3, 2]
     );
     assert_eq!(
-      get_valid_permutation(&valid_permutations5, 1).get_permutation(),
+      get_valid_permutation(valid_permutations5, 1).get_permutation(),
       &vec![3, 2, 1]
     );
 
     assert_eq!(valid_permutations6.len(), 2);
     assert_eq!(
-      get_valid_permutation(&valid_permutations6, 0).get_permutation(),
+      get_valid_permutation(valid_permutations6, 0).get_permutation(),
       &vec![2, 3, 1]
     );
     assert_eq!(
-      get_valid_permutation(&valid_permutations6, 1).get_permutation(),
+      get_valid_permutation(valid_permutations6, 1).get_permutation(),
       &vec![3, 1, 2]
     );
   }
@@ -1,13 +1,13 @@
+/// Whether `after` can follow `before`, with each bell moving at most one place.
 pub fn is_valid_change(before: &[u8], after: &[u8]) -> bool {
   if before.len() != after.len() {
     panic!("changes must be the same length");
   }
 
-  before.iter().enumerate().all(|(index, val)| {
-    get_neighbouring_values_slice(index, after)
-      .iter()
-      .any(|comp_val| *comp_val == *val)
-  })
+  before
+    .iter()
+    .enumerate()
+    .all(|(index, val)| get_neighbouring_values_slice(index, after).contains(val))
 }
 
 fn get_neighbouring_values_slice(index: usize, change: &[u8]) -> &[u8] {
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use crate::permutation_graph::permutation_node::PermutationNode;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RowSet {
+  words: Vec<u64>,
+}
+
+impl RowSet {
+  pub fn new() -> RowSet {
+    RowSet { words: Vec::new() }
+  }
+
+  pub fn from_nodes(nodes: &[Arc<PermutationNode>]) -> RowSet {
+    let mut row_set = RowSet::new();
+    for node in nodes.iter() {
+      row_set.insert(node.get_rank());
+    }
+
+    row_set
+  }
+
+  /// Adds a row's rank, returning whether it was not already in the set.
+  pub fn insert(&mut self, rank: usize) -> bool {
+    let word_index = rank / WORD_BITS;
+    if word_index >= self.words.len() {
+      self.words.resize(word_index + 1, 0);
+    }
+
+    let bit = 1 << (rank % WORD_BITS);
+    let is_new = self.words[word_index] & bit == 0;
+    self.words[word_index] |= bit;
+
+    is_new
+  }
+
+  pub fn is_disjoint(&self, other: &RowSet) -> bool {
+    self
+      .words
+      .iter()
+      .zip(other.words.iter())
+      .all(|(word, other_word)| word & other_word == 0)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::permutation_graph::utility::test::set_up_node_vector;
+
+  use super::*;
+
+  #[test]
+  fn can_insert_rows() {
+    let mut row_set = RowSet::new();
+    row_set.insert(3);
+    row_set.insert(130);
+
+    assert_eq!(row_set.words, vec![1 << 3, 0, 1 << 2]);
+  }
+
+  #[test]
+  fn insert_reports_if_row_is_new() {
+    let mut row_set = RowSet::new();
+
+    assert!(row_set.insert(70));
+    assert!(!row_set.insert(70));
+    assert!(row_set.insert(71));
+  }
+
+  #[test]
+  fn can_build_from_nodes() {
+    let nodes = set_up_node_vector(3);
+    let row_set = RowSet::from_nodes(&nodes[2..4]);
+
+    assert_eq!(row_set.words, vec![0b1100]);
+  }
+
+  #[test]
+  fn can_check_disjoint_sets() {
+    let mut row_set1 = RowSet::new();
+    let mut row_set2 = RowSet::new();
+    row_set1.insert(1);
+    row_set1.insert(100);
+    row_set2.insert(2);
+    row_set2.insert(101);
+
+    assert!(row_set1.is_disjoint(&row_set2));
+
+    row_set2.insert(100);
+
+    assert!(!row_set1.is_disjoint(&row_set2));
+    assert!(!row_set2.is_disjoint(&row_set1));
+  }
+
+  #[test]
+  fn sets_of_different_lengths_can_be_disjoint() {
+    let mut row_set1 = RowSet::new();
+    let mut row_set2 = RowSet::new();
+    row_set1.insert(1);
+    row_set2.insert(200);
+
+    assert!(row_set1.is_disjoint(&row_set2));
+    assert!(RowSet::new().is_disjoint(&row_set2));
+  }
+}
@@ -0,0 +1,608 @@
+use crate::permutation_graph::{
+  FixedBellError, PermutationGraph,
+  method_filter::MethodFilter,
+  permutation_node::is_valid_change,
+  search_options::SearchOptions,
+  utility::{factorial, lexicographic_rank},
+};
+
+const DEFAULT_SEED: u64 = 0x5EED;
+const DEFAULT_MAX_STEPS: usize = 200_000;
+const DEFAULT_MAX_RESTARTS: usize = 100;
+
+/// Settings for sampling methods at random with a [`Sampler`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SampleOptions {
+  seed: u64,
+  max_steps: usize,
+  max_restarts: usize,
+}
+
+impl SampleOptions {
+  pub fn new() -> SampleOptions {
+    SampleOptions {
+      seed: DEFAULT_SEED,
+      max_steps: DEFAULT_MAX_STEPS,
+      max_restarts: DEFAULT_MAX_RESTARTS,
+    }
+  }
+
+  /// Starts the random choices from `seed`, so the same seed samples the
+  /// same methods.
+  pub fn seed(mut self, seed: u64) -> Self {
+    self.seed = seed;
+    self
+  }
+
+  /// Rows pushed or popped in one attempt before giving up on it and
+  /// starting again from rounds.
+  pub fn max_steps(mut self, steps: usize) -> Self {
+    if steps == 0 {
+      panic!("Sampling needs at least one step per attempt");
+    }
+
+    self.max_steps = steps;
+    self
+  }
+
+  /// Attempts at one method before sampling stops.
+  pub fn max_restarts(mut self, restarts: usize) -> Self {
+    if restarts == 0 {
+      panic!("Sampling needs at least one attempt");
+    }
+
+    self.max_restarts = restarts;
+    self
+  }
+
+  pub fn get_seed(&self) -> u64 {
+    self.seed
+  }
+
+  pub fn get_max_steps(&self) -> usize {
+    self.max_steps
+  }
+
+  pub fn get_max_restarts(&self) -> usize {
+    self.max_restarts
+  }
+}
+
+impl Default for SampleOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// SplitMix64, good enough for picking between rows and the same on
+/// every platform.
+struct SplitMix64 {
+  state: u64,
+}
+
+impl SplitMix64 {
+  fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+  }
+
+  /// A number from 0 up to but not including `bound`.
+  fn below(&mut self, bound: usize) -> usize {
+    ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+  }
+
+  fn shuffle<T>(&mut self, items: &mut [T]) {
+    for index in (1..items.len()).rev() {
+      items.swap(index, self.below(index + 1));
+    }
+  }
+}
+
+/// Yields full methods found by random depth first searches, each visiting
+/// every row once before returning to rounds, until one can not be found
+/// within the restarts allowed.
+///
+/// Each search prefers the row with the fewest rows left to move on to
+/// (Warnsdorff's rule), picking at random between equally good rows. A path
+/// through every row whose end can not come back to rounds is rotated to new
+/// ends, and a search out of steps starts again from rounds with new choices.
+/// This finds methods on stages far too big to search exhaustively, though
+/// not every method is equally likely.
+///
+/// The rows that can follow a row are made from the changes allowed on the
+/// bells as the search reaches it, rather than held in a
+/// [`PermutationGraph`], whose building compares every pair of rows.
+pub struct Sampler {
+  bells_amount: usize,
+  // Places of the bells that are not fixed, counting from 0
+  moving_places: Vec<usize>,
+  // Each change as the places whose bell swaps with the next bell's
+  changes: Vec<Vec<usize>>,
+  rows_amount: usize,
+  search_options: SearchOptions,
+  sample_options: SampleOptions,
+  random: SplitMix64,
+  done: bool,
+}
+
+struct Attempt {
+  path: Vec<Vec<u8>>,
+  // Rows still to try after each row of the path, the best last
+  candidates: Vec<Vec<Vec<u8>>>,
+  visited: Vec<bool>,
+  // Where each visited row is in the path, by rank
+  positions: Vec<usize>,
+}
+
+impl Sampler {
+  /// Samples methods on `bells_amount` bells with `fixed_bells` kept in
+  /// their home places.
+  pub fn new(
+    bells_amount: usize,
+    fixed_bells: &[u8],
+    search_options: &SearchOptions,
+    sample_options: &SampleOptions,
+  ) -> Sampler {
+    if let Err(FixedBellError(bell, bells_amount)) =
+      PermutationGraph::check_fixed_bells(bells_amount, fixed_bells)
+    {
+      panic!("Fixed bell {bell} is not one of the {bells_amount} bells");
+    }
+
+    let moving_places: Vec<usize> = (0..bells_amount)
+      .filter(|&place| !fixed_bells.contains(&(place as u8 + 1)))
+      .collect();
+    let rows_amount = match bells_amount {
+      0 => 0,
+      _ => factorial(moving_places.len()).max(1),
+    };
+
+    Sampler {
+      bells_amount,
+      changes: Self::get_changes(&moving_places),
+      moving_places,
+      rows_amount,
+      search_options: search_options.clone(),
+      sample_options: sample_options.clone(),
+      random: SplitMix64 {
+        state: sample_options.get_seed(),
+      },
+      done: false,
+    }
+  }
+
+  /// Every set of swaps between neighbouring moving bells with no bell in
+  /// two swaps, bar the empty set.
+  fn get_changes(moving_places: &[usize]) -> Vec<Vec<usize>> {
+    let swaps = moving_places
+      .windows(2)
+      .filter(|places| places[1] == places[0] + 1)
+      .map(|places| places[0]);
+
+    let mut changes = vec![Vec::new()];
+    for swap in swaps {
+      let with_swap: Vec<Vec<usize>> = changes
+        .iter()
+        .filter(|change| change.last().is_none_or(|&last| last + 1 < swap))
+        .map(|change| {
+          let mut change = change.clone();
+          change.push(swap);
+          change
+        })
+        .collect();
+      changes.extend(with_swap);
+    }
+
+    // Swapping nothing leaves the row as it is
+    changes.remove(0);
+    changes
+  }
+
+  fn apply_change(row: &mut [u8], change: &[usize]) {
+    for &place in change {
+      row.swap(place, place + 1);
+    }
+  }
+
+  fn get_neighbours(&self, row: &[u8]) -> Vec<Vec<u8>> {
+    self
+      .changes
+      .iter()
+      .map(|change| {
+        let mut next = row.to_vec();
+        Self::apply_change(&mut next, change);
+        next
+      })
+      .collect()
+  }
+
+  /// Ranks a row among the rows with the fixed bells at home.
+  fn get_rank(&self, row: &[u8]) -> usize {
+    if self.moving_places.len() == row.len() {
+      lexicographic_rank(row)
+    } else {
+      let moving_bells: Vec<u8> = self.moving_places.iter().map(|&place| row[place]).collect();
+      lexicographic_rank(&moving_bells)
+    }
+  }
+
+  fn count_unvisited_neighbours(&self, row: &[u8], visited: &[bool]) -> usize {
+    let mut next = row.to_vec();
+
+    self
+      .changes
+      .iter()
+      .filter(|change| {
+        // Changes are their own inverse, so applying one twice undoes it
+        Self::apply_change(&mut next, change);
+        let is_unvisited = !visited[self.get_rank(&next)];
+        Self::apply_change(&mut next, change);
+        is_unvisited
+      })
+      .count()
+  }
+
+  fn sample(&mut self) -> Option<Vec<Vec<u8>>> {
+    if self.rows_amount == 0 {
+      return None;
+    }
+
+    let rounds: Vec<u8> = (1..=self.bells_amount as u8).collect();
+    if self.rows_amount == 1 {
+      return Some(vec![rounds]);
+    }
+
+    (0..self.sample_options.get_max_restarts()).find_map(|_| self.attempt(&rounds))
+  }
+
+  fn attempt(&mut self, rounds: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut attempt = Attempt {
+      path: Vec::with_capacity(self.rows_amount),
+      candidates: Vec::with_capacity(self.rows_amount),
+      visited: vec![false; self.rows_amount],
+      positions: vec![0; self.rows_amount],
+    };
+    self.push(&mut attempt, rounds.to_vec());
+
+    let can_rotate = self.search_options.get_filter() == &MethodFilter::default()
+      && self.search_options.get_treble_path().is_none();
+
+    for _ in 0..self.sample_options.get_max_steps() {
+      if attempt.path.len() == self.rows_amount && self.can_return_to_rounds(&attempt.path, rounds)
+      {
+        let mut method = attempt.path;
+        method.push(rounds.to_vec());
+        return Some(method);
+      }
+
+      match attempt.candidates.last_mut()?.pop() {
+        Some(row) => self.push(&mut attempt, row),
+        // A new end for the path may have rows left to move on to, or
+        // come back to rounds
+        None if can_rotate && self.rotate(&mut attempt) => {}
+        None => {
+          // Back to rounds with nothing left to try, so there is no method
+          if attempt.path.len() == 1 {
+            return None;
+          }
+          let row = attempt.path.pop().expect("Path holds more than rounds");
+          attempt.visited[self.get_rank(&row)] = false;
+          attempt.candidates.pop();
+        }
+      }
+    }
+
+    None
+  }
+
+  fn push(&mut self, attempt: &mut Attempt, row: Vec<u8>) {
+    let rank = self.get_rank(&row);
+    attempt.visited[rank] = true;
+    attempt.positions[rank] = attempt.path.len();
+    attempt.path.push(row);
+
+    let candidates = self.get_candidates(attempt);
+    attempt.candidates.push(candidates);
+  }
+
+  /// Picks another end for the path without changing its rows (a Pósa
+  /// rotation): the path is joined to one of the end's other neighbours and
+  /// the rows after that neighbour are reversed. Only used without a filter
+  /// or treble path, as reversing can break either. Returns whether there
+  /// was a neighbour to join to.
+  fn rotate(&mut self, attempt: &mut Attempt) -> bool {
+    let path_len = attempt.path.len();
+    let last = attempt.path.last().expect("Paths start with rounds");
+    let joins: Vec<usize> = self
+      .get_neighbours(last)
+      .iter()
+      .map(|row| self.get_rank(row))
+      .filter(|&rank| attempt.visited[rank])
+      .map(|rank| attempt.positions[rank])
+      .filter(|&index| index + 2 < path_len)
+      .collect();
+
+    if joins.is_empty() {
+      return false;
+    }
+
+    let join = joins[self.random.below(joins.len())];
+    attempt.path[join + 1..].reverse();
+    for index in join + 1..path_len {
+      attempt.positions[self.get_rank(&attempt.path[index])] = index;
+    }
+
+    // Rows left to try after the reversed rows no longer follow them
+    attempt.candidates.truncate(join + 1);
+    attempt.candidates.resize(path_len - 1, Vec::new());
+    let candidates = self.get_candidates(attempt);
+    attempt.candidates.push(candidates);
+
+    true
+  }
+
+  /// The unvisited rows allowed after the end of the path, ordered so the
+  /// row with the fewest unvisited rows after it comes last.
+  fn get_candidates(&mut self, attempt: &Attempt) -> Vec<Vec<u8>> {
+    let path = &attempt.path;
+    let previous = path.last().expect("Paths start with rounds");
+    let before_previous = path
+      .len()
+      .checked_sub(2)
+      .map(|index| path[index].as_slice());
+
+    let mut candidates: Vec<Vec<u8>> = self
+      .get_neighbours(previous)
+      .into_iter()
+      .filter(|row| !attempt.visited[self.get_rank(row)])
+      .filter(|row| self.allows(before_previous, previous, row, path.len()))
+      .collect();
+
+    let rounds = &path[0];
+    self.random.shuffle(&mut candidates);
+    // Stable, so equally good rows stay in their random order. The way back
+    // to rounds counts as a way on, keeping rows next to rounds for the end.
+    candidates.sort_by_cached_key(|row| {
+      let unvisited = self.count_unvisited_neighbours(row, &attempt.visited);
+      std::cmp::Reverse(unvisited + is_valid_change(row, rounds) as usize)
+    });
+    candidates
+  }
+
+  fn allows(
+    &self,
+    before_previous: Option<&[u8]>,
+    previous: &[u8],
+    next: &[u8],
+    row_index: usize,
+  ) -> bool {
+    self
+      .search_options
+      .get_filter()
+      .allows_change(before_previous, previous, next)
+      && self
+        .search_options
+        .get_treble_path()
+        .is_none_or(|path| path.allows_row(row_index, next))
+  }
+
+  fn can_return_to_rounds(&self, path: &[Vec<u8>], rounds: &[u8]) -> bool {
+    let last = path.last().expect("Paths start with rounds");
+    let before_last = path
+      .len()
+      .checked_sub(2)
+      .map(|index| path[index].as_slice());
+
+    last != rounds
+      && is_valid_change(last, rounds)
+      && self.allows(before_last, last, rounds, path.len())
+  }
+}
+
+impl Iterator for Sampler {
+  type Item = Vec<Vec<u8>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+
+    let method = self.sample();
+    self.done = method.is_none();
+    method
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::{TreblePath, permutation_graph::utility::are_unique_rows};
+
+  fn is_full_method(method: &[Vec<u8>], rows_amount: usize) -> bool {
+    let is_rounds = |row: &Vec<u8>| {
+      row
+        .iter()
+        .enumerate()
+        .all(|(index, &bell)| bell as usize == index + 1)
+    };
+
+    method.len() == rows_amount + 1
+      && is_rounds(&method[0])
+      && is_rounds(&method[rows_amount])
+      && are_unique_rows(&method[..rows_amount])
+      && method
+        .windows(2)
+        .all(|pair| pair[0] != pair[1] && is_valid_change(&pair[0], &pair[1]))
+  }
+
+  fn sample(
+    bells_amount: usize,
+    search_options: &SearchOptions,
+    sample_options: &SampleOptions,
+  ) -> Sampler {
+    Sampler::new(bells_amount, &[], search_options, sample_options)
+  }
+
+  #[test]
+  fn samples_full_methods() {
+    let methods: Vec<Vec<Vec<u8>>> = sample(4, &SearchOptions::new(), &SampleOptions::new())
+      .take(10)
+      .collect();
+
+    assert_eq!(methods.len(), 10);
+    assert!(methods.iter().all(|method| is_full_method(method, 24)));
+  }
+
+  #[test]
+  fn samples_stages_too_big_to_search() {
+    let method = sample(6, &SearchOptions::new(), &SampleOptions::new().seed(7))
+      .next()
+      .expect("Should find an extent of minor");
+
+    assert!(is_full_method(&method, 720));
+  }
+
+  #[test]
+  fn samples_triples_with_the_default_limits() {
+    for seed in [1, 2, 3] {
+      let method = sample(7, &SearchOptions::new(), &SampleOptions::new().seed(seed))
+        .next()
+        .expect("Should find an extent of triples");
+
+      assert!(is_full_method(&method, 5040));
+    }
+  }
+
+  #[test]
+  fn keeps_fixed_bells_at_home() {
+    let method = Sampler::new(5, &[1, 5], &SearchOptions::new(), &SampleOptions::new())
+      .next()
+      .expect("Should find an extent with the treble and tenor fixed");
+
+    assert_eq!(method.len(), 7);
+    assert!(method.iter().all(|row| row[0] == 1 && row[4] == 5));
+    assert!(are_unique_rows(&method[..6]));
+    assert!(
+      method
+        .windows(2)
+        .all(|pair| pair[0] != pair[1] && is_valid_change(&pair[0], &pair[1]))
+    );
+  }
+
+  #[test]
+  fn makes_every_change_between_moving_bells() {
+    // Adjacent swaps on 4 bells: 1-2, 2-3, 3-4 and 1-2 with 3-4
+    assert_eq!(
+      Sampler::get_changes(&[0, 1, 2, 3]),
+      vec![vec![0], vec![1], vec![2], vec![0, 2]]
+    );
+    // A fixed bell in 2nds stops anything swapping across it
+    assert_eq!(Sampler::get_changes(&[0, 2, 3]), vec![vec![2]]);
+    assert_eq!(Sampler::get_changes(&[0]), Vec::<Vec<usize>>::new());
+  }
+
+  #[test]
+  fn repeats_samples_from_the_same_seed() {
+    let methods_from = |seed| -> Vec<Vec<Vec<u8>>> {
+      sample(4, &SearchOptions::new(), &SampleOptions::new().seed(seed))
+        .take(3)
+        .collect()
+    };
+
+    assert_eq!(methods_from(1), methods_from(1));
+    assert_ne!(methods_from(1), methods_from(2));
+  }
+
+  #[test]
+  fn keeps_to_the_search_options() {
+    let filter = MethodFilter::NoLongPlaces.and(MethodFilter::RightPlace);
+    let path = TreblePath::plain_hunt(4);
+    let options = SearchOptions::new()
+      .filter(filter.clone())
+      .treble_path(path.clone());
+
+    let methods: Vec<Vec<Vec<u8>>> = sample(4, &options, &SampleOptions::new()).take(5).collect();
+
+    assert_eq!(methods.len(), 5);
+    for method in methods {
+      assert!(is_full_method(&method, 24));
+      assert!(filter.matches_rows(&method));
+      assert!(
+        method
+          .iter()
+          .enumerate()
+          .all(|(index, row)| path.allows_row(index, row))
+      );
+    }
+  }
+
+  #[test]
+  fn stops_when_no_method_is_found() {
+    // The treble can never be in 4ths straight after rounds
+    let options = SearchOptions::new().treble_path(TreblePath::custom(vec![1, 4], 4).unwrap());
+
+    assert_eq!(
+      sample(4, &options, &SampleOptions::new().max_restarts(2)).count(),
+      0
+    );
+  }
+
+  #[test]
+  fn samples_the_smallest_stages() {
+    let methods: Vec<Vec<Vec<u8>>> = sample(1, &SearchOptions::new(), &SampleOptions::new())
+      .take(2)
+      .collect();
+    assert_eq!(methods, vec![vec![vec![1]], vec![vec![1]]]);
+
+    let methods: Vec<Vec<Vec<u8>>> = sample(2, &SearchOptions::new(), &SampleOptions::new())
+      .take(1)
+      .collect();
+    assert_eq!(methods, vec![vec![vec![1, 2], vec![2, 1], vec![1, 2]]]);
+
+    assert_eq!(
+      sample(0, &SearchOptions::new(), &SampleOptions::new()).count(),
+      0
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "Fixed bell 5 is not one of the 4 bells")]
+  fn panics_on_fixed_bells_that_are_not_rung() {
+    Sampler::new(4, &[5], &SearchOptions::new(), &SampleOptions::new());
+  }
+
+  #[test]
+  fn picks_numbers_below_a_bound() {
+    let mut random = SplitMix64 { state: 3 };
+    let picks: Vec<usize> = (0..100).map(|_| random.below(6)).collect();
+
+    assert!(picks.iter().all(|&pick| pick < 6));
+    assert!((0..6).all(|number| picks.contains(&number)));
+  }
+
+  #[test]
+  fn has_defaults() {
+    let options = SampleOptions::new();
+
+    assert_eq!(options.get_seed(), DEFAULT_SEED);
+    assert_eq!(options.get_max_steps(), DEFAULT_MAX_STEPS);
+    assert_eq!(options.get_max_restarts(), DEFAULT_MAX_RESTARTS);
+    assert_eq!(options, SampleOptions::default());
+  }
+
+  #[test]
+  #[should_panic(expected = "Sampling needs at least one attempt")]
+  fn panics_without_attempts() {
+    SampleOptions::new().max_restarts(0);
+  }
+
+  #[test]
+  #[should_panic(expected = "Sampling needs at least one step per attempt")]
+  fn panics_without_steps() {
+    SampleOptions::new().max_steps(0);
+  }
+}
@@ -0,0 +1,151 @@
+use crate::permutation_graph::{method_filter::MethodFilter, treble_path::TreblePath};
+
+const DEFAULT_COMPARISON_THREADS: usize = 4;
+const DEFAULT_CHANNEL_CAPACITY: usize = 16;
+
+/// Settings for a search over a [`PermutationGraph`](crate::PermutationGraph).
+///
+/// Built up from the defaults, e.g.
+/// `SearchOptions::new().comparison_threads(2).include_reversed(false)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchOptions {
+  comparison_threads: usize,
+  channel_capacity: usize,
+  include_reversed: bool,
+  group_by_end_row: bool,
+  filter: MethodFilter,
+  treble_path: Option<TreblePath>,
+}
+
+impl SearchOptions {
+  /// Four comparison threads queuing up to 16 messages, with every method
+  /// followed by its reverse.
+  pub fn new() -> SearchOptions {
+    SearchOptions {
+      comparison_threads: DEFAULT_COMPARISON_THREADS,
+      channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+      include_reversed: true,
+      group_by_end_row: true,
+      filter: MethodFilter::default(),
+      treble_path: None,
+    }
+  }
+
+  /// Maximum number of threads pairing half methods into full methods.
+  pub fn comparison_threads(mut self, threads: usize) -> Self {
+    if threads == 0 {
+      panic!("Search needs at least one comparison thread");
+    }
+
+    self.comparison_threads = threads;
+    self
+  }
+
+  /// Most messages, such as methods found, the comparison threads can queue
+  /// before waiting for the iterator to take them. With 0 each thread waits
+  /// until its message is taken.
+  pub fn channel_capacity(mut self, capacity: usize) -> Self {
+    self.channel_capacity = capacity;
+    self
+  }
+
+  /// Whether each method is followed by the same method rung backwards.
+  pub fn include_reversed(mut self, include_reversed: bool) -> Self {
+    self.include_reversed = include_reversed;
+    self
+  }
+
+  /// Whether half methods are only compared with those ending on the same
+  /// row. Turning it off compares every pair, which finds the same methods
+  /// more slowly, so is only useful to measure what grouping saves.
+  pub fn group_by_end_row(mut self, group_by_end_row: bool) -> Self {
+    self.group_by_end_row = group_by_end_row;
+    self
+  }
+
+  /// Only find methods the filter allows, dropping half methods breaking it
+  /// as they are searched.
+  pub fn filter(mut self, filter: MethodFilter) -> Self {
+    self.filter = filter;
+    self
+  }
+
+  /// Only find methods keeping the treble on the path, dropping half
+  /// methods leaving it as they are searched.
+  pub fn treble_path(mut self, treble_path: TreblePath) -> Self {
+    self.treble_path = Some(treble_path);
+    self
+  }
+
+  pub fn get_comparison_threads(&self) -> usize {
+    self.comparison_threads
+  }
+
+  pub fn get_channel_capacity(&self) -> usize {
+    self.channel_capacity
+  }
+
+  pub fn get_include_reversed(&self) -> bool {
+    self.include_reversed
+  }
+
+  pub fn get_group_by_end_row(&self) -> bool {
+    self.group_by_end_row
+  }
+
+  pub fn get_filter(&self) -> &MethodFilter {
+    &self.filter
+  }
+
+  pub fn get_treble_path(&self) -> Option<&TreblePath> {
+    self.treble_path.as_ref()
+  }
+}
+
+impl Default for SearchOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn has_defaults() {
+    let options = SearchOptions::new();
+
+    assert_eq!(options.get_comparison_threads(), 4);
+    assert_eq!(options.get_channel_capacity(), 16);
+    assert!(options.get_include_reversed());
+    assert!(options.get_group_by_end_row());
+    assert_eq!(*options.get_filter(), MethodFilter::default());
+    assert!(options.get_treble_path().is_none());
+    assert_eq!(options, SearchOptions::default());
+  }
+
+  #[test]
+  fn can_set_options() {
+    let options = SearchOptions::new()
+      .comparison_threads(2)
+      .channel_capacity(0)
+      .include_reversed(false)
+      .group_by_end_row(false)
+      .filter(MethodFilter::NoLongPlaces)
+      .treble_path(TreblePath::plain_hunt(4));
+
+    assert_eq!(options.get_comparison_threads(), 2);
+    assert_eq!(options.get_channel_capacity(), 0);
+    assert!(!options.get_include_reversed());
+    assert!(!options.get_group_by_end_row());
+    assert_eq!(*options.get_filter(), MethodFilter::NoLongPlaces);
+    assert_eq!(options.get_treble_path(), Some(&TreblePath::plain_hunt(4)));
+  }
+
+  #[test]
+  #[should_panic(expected = "Search needs at least one comparison thread")]
+  fn panics_if_no_comparison_threads() {
+    SearchOptions::new().comparison_threads(0);
+  }
+}
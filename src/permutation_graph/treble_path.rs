@@ -0,0 +1,215 @@
+use std::{error::Error, fmt, sync::Arc};
+
+use crate::permutation_graph::permutation_node::PermutationNode;
+
+const TREBLE: u8 = 1;
+
+/// The places the treble must ring in, repeating from rounds, e.g.
+/// `1, 2, 3, 4, 4, 3, 2, 1` for plain hunting on four bells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreblePath {
+  places: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TreblePathError {
+  InvalidPlace(String),
+  PlaceOutOfRange(usize),
+  OddTrebleBob(usize),
+  Empty,
+}
+
+impl fmt::Display for TreblePathError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TreblePathError::InvalidPlace(place) => write!(f, "'{place}' is not a place"),
+      TreblePathError::PlaceOutOfRange(place) => {
+        write!(f, "place {place} is beyond the number of bells")
+      }
+      TreblePathError::OddTrebleBob(bells_amount) => {
+        write!(
+          f,
+          "treble bob needs an even number of bells, not {bells_amount}"
+        )
+      }
+      TreblePathError::Empty => write!(f, "treble path has no places"),
+    }
+  }
+}
+
+impl Error for TreblePathError {}
+
+impl TreblePath {
+  /// The treble hunting up to the back and down to the front, leading and
+  /// lying for two rows.
+  pub fn plain_hunt(bells_amount: usize) -> TreblePath {
+    let mut places: Vec<usize> = (1..=bells_amount).collect();
+    places.extend((1..=bells_amount).rev());
+
+    TreblePath { places }
+  }
+
+  /// The treble dodging in each pair of places on the way up and down.
+  pub fn treble_bob(bells_amount: usize) -> TreblePath {
+    if !bells_amount.is_multiple_of(2) {
+      panic!("Treble bob needs an even number of bells");
+    }
+
+    let up: Vec<usize> = (1..=bells_amount)
+      .step_by(2)
+      .flat_map(|place| [place, place + 1, place, place + 1])
+      .collect();
+    let mut places = up.clone();
+    places.extend(up.iter().rev());
+
+    TreblePath { places }
+  }
+
+  /// Any sequence of places on `bells_amount` bells, numbered from 1.
+  pub fn custom(places: Vec<usize>, bells_amount: usize) -> Result<TreblePath, TreblePathError> {
+    if places.is_empty() {
+      return Err(TreblePathError::Empty);
+    }
+    if let Some(place) = places.iter().find(|&&place| place == 0) {
+      return Err(TreblePathError::InvalidPlace(place.to_string()));
+    }
+    if let Some(&place) = places.iter().find(|&&place| place > bells_amount) {
+      return Err(TreblePathError::PlaceOutOfRange(place));
+    }
+
+    Ok(TreblePath { places })
+  }
+
+  /// Reads `plain-hunt`, `treble-bob` or a comma separated list of places.
+  pub fn parse(path: &str, bells_amount: usize) -> Result<TreblePath, TreblePathError> {
+    match path {
+      "plain-hunt" => Ok(Self::plain_hunt(bells_amount)),
+      "treble-bob" if !bells_amount.is_multiple_of(2) => {
+        Err(TreblePathError::OddTrebleBob(bells_amount))
+      }
+      "treble-bob" => Ok(Self::treble_bob(bells_amount)),
+      _ => Self::custom(
+        path
+          .split(',')
+          .map(|place| {
+            place
+              .trim()
+              .parse()
+              .map_err(|_| TreblePathError::InvalidPlace(place.to_string()))
+          })
+          .collect::<Result<Vec<usize>, TreblePathError>>()?,
+        bells_amount,
+      ),
+    }
+  }
+
+  pub fn get_places(&self) -> &[usize] {
+    &self.places
+  }
+
+  /// The place the treble rings in at a row, rounds being row 0.
+  pub fn place_at(&self, row_index: usize) -> usize {
+    self.places[row_index % self.places.len()]
+  }
+
+  /// Whether the treble is in its place at a row.
+  pub fn allows_row(&self, row_index: usize, row: &[u8]) -> bool {
+    row.get(self.place_at(row_index) - 1) == Some(&TREBLE)
+  }
+
+  /// Whether the treble follows the path through a whole method.
+  pub fn matches(&self, method: &[Arc<PermutationNode>]) -> bool {
+    method
+      .iter()
+      .enumerate()
+      .all(|(index, node)| self.allows_row(index, node.get_permutation()))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::place_notation::expand_lead;
+
+  use super::*;
+
+  #[test]
+  fn can_plain_hunt() {
+    assert_eq!(
+      TreblePath::plain_hunt(4).get_places(),
+      &[1, 2, 3, 4, 4, 3, 2, 1]
+    );
+  }
+
+  #[test]
+  fn can_treble_bob() {
+    assert_eq!(
+      TreblePath::treble_bob(4).get_places(),
+      &[1, 2, 1, 2, 3, 4, 3, 4, 4, 3, 4, 3, 2, 1, 2, 1]
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "Treble bob needs an even number of bells")]
+  fn treble_bob_panics_on_odd_bells() {
+    TreblePath::treble_bob(5);
+  }
+
+  #[test]
+  fn can_parse_paths() {
+    assert_eq!(
+      TreblePath::parse("plain-hunt", 6),
+      Ok(TreblePath::plain_hunt(6))
+    );
+    assert_eq!(
+      TreblePath::parse("treble-bob", 6),
+      Ok(TreblePath::treble_bob(6))
+    );
+    assert_eq!(
+      TreblePath::parse("1, 1, 2, 2", 4).unwrap().get_places(),
+      &[1, 1, 2, 2]
+    );
+    assert_eq!(
+      TreblePath::parse("1,x", 4),
+      Err(TreblePathError::InvalidPlace("x".to_string()))
+    );
+    assert_eq!(
+      TreblePath::parse("1,0", 4),
+      Err(TreblePathError::InvalidPlace("0".to_string()))
+    );
+    assert_eq!(
+      TreblePath::parse("1,2,5", 4),
+      Err(TreblePathError::PlaceOutOfRange(5))
+    );
+    assert_eq!(
+      TreblePath::parse("treble-bob", 5),
+      Err(TreblePathError::OddTrebleBob(5))
+    );
+    assert_eq!(
+      TreblePath::custom(Vec::new(), 4),
+      Err(TreblePathError::Empty)
+    );
+  }
+
+  #[test]
+  fn follows_the_treble_through_rows() {
+    let plain_bob = expand_lead("x16x16x16,12", 6).unwrap();
+    let kent = expand_lead("34x34.16x12x16x12x16,12", 6).unwrap();
+    let plain_hunt = TreblePath::plain_hunt(6);
+    let treble_bob = TreblePath::treble_bob(6);
+
+    assert_eq!(plain_hunt.place_at(13), 2);
+    assert!(
+      plain_bob
+        .iter()
+        .enumerate()
+        .all(|(index, row)| plain_hunt.allows_row(index, row))
+    );
+    assert!(
+      kent
+        .iter()
+        .enumerate()
+        .all(|(index, row)| treble_bob.allows_row(index, row))
+    );
+    assert!(!plain_hunt.allows_row(2, &kent[2]));
+  }
+}
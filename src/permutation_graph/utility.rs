@@ -1,4 +1,9 @@
-use std::{iter::Product, ops::Add};
+use std::{collections::HashSet, iter::Product, ops::Add};
+
+use crate::permutation_graph::row_set::RowSet;
+
+// Beyond this a bit per possible row takes more memory than hashing rows
+const MAX_ROW_SET_BELLS: usize = 10;
 
 pub fn are_unique<'a, T: ?Sized, I>(nodes: &'a T) -> bool
 where
@@ -24,6 +29,22 @@ where
   is_valid
 }
 
+/// Checks rows are all different in linear time, rather than comparing
+/// every pair like `are_unique`.
+pub fn are_unique_rows<R: AsRef<[u8]>>(rows: &[R]) -> bool {
+  let bells_amount = rows.first().map_or(0, |row| row.as_ref().len());
+
+  if bells_amount <= MAX_ROW_SET_BELLS {
+    let mut row_set = RowSet::new();
+    rows
+      .iter()
+      .all(|row| row_set.insert(lexicographic_rank(row.as_ref())))
+  } else {
+    let mut seen_rows = HashSet::with_capacity(rows.len());
+    rows.iter().all(|row| seen_rows.insert(row.as_ref()))
+  }
+}
+
 pub fn factorial<T>(n: T) -> T
 where
   T: Product<usize> + PartialEq<usize> + Add<usize, Output = usize>,
@@ -31,6 +52,31 @@ where
   if n == 0 { n } else { (1..n + 1).product() }
 }
 
+pub fn lexicographic_rank(row: &[u8]) -> usize {
+  row.iter().enumerate().fold(0, |rank, (index, bell)| {
+    let smaller_after = row[index + 1..]
+      .iter()
+      .filter(|other| *other < bell)
+      .count();
+    rank * (row.len() - index) + smaller_after
+  })
+}
+
+/// The row on `bells_amount` bells with the given lexicographic rank, the
+/// inverse of `lexicographic_rank`.
+pub fn row_from_lexicographic_rank(mut rank: usize, bells_amount: usize) -> Vec<u8> {
+  let mut remaining: Vec<u8> = (1..=bells_amount as u8).collect();
+  let mut row = Vec::with_capacity(bells_amount);
+
+  for place in (0..bells_amount).rev() {
+    let block = factorial(place).max(1);
+    row.push(remaining.remove(rank / block));
+    rank %= block;
+  }
+
+  row
+}
+
 #[cfg(test)]
 pub mod test {
   use std::sync::Arc;
@@ -67,7 +113,7 @@ pub mod test {
 
   pub fn build_node_graph(nodes: &StrongNodeVector) {
     for node in nodes.iter() {
-      node.extract_valid_permutations(&nodes);
+      node.extract_valid_permutations(nodes);
     }
   }
 
@@ -96,6 +142,60 @@ pub mod test {
     assert!(are_unique(&empty));
   }
 
+  #[test]
+  fn can_check_rows_are_unique() {
+    let rows = vec![vec![1, 2, 3], vec![2, 1, 3], vec![2, 3, 1]];
+    let repeated_rows = vec![vec![1, 2, 3], vec![2, 1, 3], vec![1, 2, 3]];
+    let empty: Vec<Vec<u8>> = vec![];
+
+    assert!(are_unique_rows(&rows));
+    assert!(!are_unique_rows(&repeated_rows));
+    assert!(are_unique_rows(&empty));
+  }
+
+  #[test]
+  fn can_check_rows_are_unique_on_higher_numbers() {
+    let rounds: Vec<u8> = (1..=12).collect();
+    let mut changed = rounds.clone();
+    changed.swap(0, 1);
+
+    assert!(are_unique_rows(&[rounds.clone(), changed.clone()]));
+    assert!(!are_unique_rows(&[rounds.clone(), changed, rounds]));
+  }
+
+  #[test]
+  fn ranks_rows_in_lexicographic_order() {
+    assert_eq!(lexicographic_rank(&[]), 0);
+    assert_eq!(lexicographic_rank(&[1]), 0);
+    assert_eq!(lexicographic_rank(&[1, 2, 3]), 0);
+    assert_eq!(lexicographic_rank(&[2, 3, 1]), 3);
+    assert_eq!(lexicographic_rank(&[3, 2, 1]), 5);
+    assert_eq!(lexicographic_rank(&[4, 3, 2, 1]), 23);
+  }
+
+  #[test]
+  fn ranks_match_the_order_permutations_are_generated() {
+    let nodes = set_up_node_vector(5);
+
+    for (index, node) in nodes.iter().enumerate() {
+      assert_eq!(lexicographic_rank(node.get_permutation()), index);
+    }
+  }
+
+  #[test]
+  fn can_get_rows_back_from_ranks() {
+    assert_eq!(row_from_lexicographic_rank(0, 0), Vec::<u8>::new());
+    assert_eq!(row_from_lexicographic_rank(3, 3), vec![2, 3, 1]);
+    assert_eq!(row_from_lexicographic_rank(23, 4), vec![4, 3, 2, 1]);
+
+    for rank in 0..120 {
+      assert_eq!(
+        lexicographic_rank(&row_from_lexicographic_rank(rank, 5)),
+        rank
+      );
+    }
+  }
+
   #[test]
   fn can_get_the_factorial_of_a_number() {
     assert_eq!(factorial(0), 0);
@@ -0,0 +1,351 @@
+use std::{error::Error, fmt};
+
+const BELL_SYMBOLS: &[u8] = b"1234567890ETABCDFGHJKLMNPQRSUVWYZ";
+
+/// The places made in one change, numbered from 1.
+pub type Places = Vec<u8>;
+
+#[derive(Debug, PartialEq)]
+pub enum PlaceNotationError {
+  UnknownSymbol(char),
+  PlaceOutOfRange(char),
+  InvalidChange(String),
+  Empty,
+}
+
+impl fmt::Display for PlaceNotationError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      PlaceNotationError::UnknownSymbol(symbol) => write!(f, "unknown place symbol '{symbol}'"),
+      PlaceNotationError::PlaceOutOfRange(symbol) => {
+        write!(f, "place '{symbol}' is beyond the number of bells")
+      }
+      PlaceNotationError::InvalidChange(places) => {
+        write!(f, "places '{places}' leave a bell with nowhere to move")
+      }
+      PlaceNotationError::Empty => write!(f, "place notation has no changes"),
+    }
+  }
+}
+
+impl Error for PlaceNotationError {}
+
+/// The character ringers use for a bell, e.g. `0` for the tenth and `E` for the eleventh.
+pub fn bell_to_char(bell: u8) -> char {
+  match BELL_SYMBOLS.get((bell as usize).wrapping_sub(1)) {
+    Some(&symbol) => symbol as char,
+    None => panic!("No symbol for bell {bell}"),
+  }
+}
+
+/// The bell a symbol stands for, e.g. `11` for `E`.
+pub fn char_to_bell(symbol: char) -> Option<u8> {
+  let upper_symbol = symbol.to_ascii_uppercase();
+  BELL_SYMBOLS
+    .iter()
+    .position(|&bell_symbol| bell_symbol as char == upper_symbol)
+    .map(|index| index as u8 + 1)
+}
+
+/// A row written the way ringers write it, e.g. `[2, 1, 4, 3]` as `2143`.
+pub fn row_to_string(row: &[u8]) -> String {
+  row.iter().map(|&bell| bell_to_char(bell)).collect()
+}
+
+/// The places made between two rows, empty when every bell moves.
+pub fn get_places(before: &[u8], after: &[u8]) -> Places {
+  if before.len() != after.len() {
+    panic!("changes must be the same length");
+  }
+
+  before
+    .iter()
+    .zip(after.iter())
+    .enumerate()
+    .filter(|(_, (before_bell, after_bell))| before_bell == after_bell)
+    .map(|(index, _)| index as u8 + 1)
+    .collect()
+}
+
+/// The place notation for a change, listing the places where a bell stays
+/// put, or `x` when every bell moves.
+pub fn get_place_notation(before: &[u8], after: &[u8]) -> String {
+  let places: String = get_places(before, after)
+    .into_iter()
+    .map(bell_to_char)
+    .collect();
+
+  if places.is_empty() {
+    String::from("x")
+  } else {
+    places
+  }
+}
+
+/// Parses place notation such as `x16x16x16,12` into one set of places per change.
+///
+/// Changes are separated by `x` or `-` for a cross change, or by `.` between
+/// places. A comma splits the notation into sections which are each rung
+/// forwards then backwards, as do sections starting with `&`, while sections
+/// starting with `+` are rung as written. Places implied at the front or back
+/// of a change are filled in, so `3` on five bells is read as `3` but `4` on
+/// six bells is read as `14`.
+pub fn parse_place_notation(
+  notation: &str,
+  bells_amount: usize,
+) -> Result<Vec<Places>, PlaceNotationError> {
+  let is_comma_separated = notation.contains(',');
+  let mut changes = Vec::new();
+
+  for section in notation.split(',') {
+    let section = section.trim();
+    let (is_palindrome, section) = match section.chars().next() {
+      Some('&') => (true, &section[1..]),
+      Some('+') => (false, &section[1..]),
+      _ => (is_comma_separated, section),
+    };
+
+    let mut section_changes = parse_section(section, bells_amount)?;
+    if is_palindrome {
+      let reflection: Vec<Places> = section_changes.iter().rev().skip(1).cloned().collect();
+      section_changes.extend(reflection);
+    }
+
+    changes.append(&mut section_changes);
+  }
+
+  if changes.is_empty() {
+    Err(PlaceNotationError::Empty)
+  } else {
+    Ok(changes)
+  }
+}
+
+fn parse_section(section: &str, bells_amount: usize) -> Result<Vec<Places>, PlaceNotationError> {
+  let mut changes = Vec::new();
+  let mut places = Vec::new();
+
+  for symbol in section.chars() {
+    match symbol {
+      'x' | 'X' | '-' => {
+        if !places.is_empty() {
+          changes.push(complete_places(&places, bells_amount)?);
+          places.clear();
+        }
+        changes.push(complete_places(&[], bells_amount)?);
+      }
+      '.' => {
+        if !places.is_empty() {
+          changes.push(complete_places(&places, bells_amount)?);
+          places.clear();
+        }
+      }
+      symbol if symbol.is_whitespace() => (),
+      symbol => {
+        let place = char_to_bell(symbol).ok_or(PlaceNotationError::UnknownSymbol(symbol))?;
+        if place as usize > bells_amount {
+          return Err(PlaceNotationError::PlaceOutOfRange(symbol));
+        }
+        places.push(place);
+      }
+    }
+  }
+
+  if !places.is_empty() {
+    changes.push(complete_places(&places, bells_amount)?);
+  }
+
+  Ok(changes)
+}
+
+// Adds the lead or lie if the bells in front of the first place or behind
+// the last place could not otherwise all swap in pairs
+fn complete_places(places: &[u8], bells_amount: usize) -> Result<Places, PlaceNotationError> {
+  let mut complete: Places = places.to_vec();
+  complete.sort_unstable();
+  complete.dedup();
+
+  match complete.first() {
+    Some(&first) if first % 2 == 0 => complete.insert(0, 1),
+    None if bells_amount % 2 == 1 => complete.push(bells_amount as u8),
+    _ => (),
+  }
+
+  if let Some(&last) = complete.last()
+    && (bells_amount - last as usize) % 2 == 1
+  {
+    complete.push(bells_amount as u8);
+  }
+
+  let is_valid = complete
+    .windows(2)
+    .all(|pair| (pair[1] - pair[0] - 1) % 2 == 0);
+
+  if is_valid {
+    Ok(complete)
+  } else {
+    Err(PlaceNotationError::InvalidChange(
+      places.iter().map(|&place| bell_to_char(place)).collect(),
+    ))
+  }
+}
+
+/// The row reached by making the given places from `row` and swapping every other pair of bells.
+pub fn apply_change(row: &[u8], places: &[u8]) -> Vec<u8> {
+  let mut next_row = row.to_vec();
+  let mut index = 0;
+
+  while index < row.len() {
+    if places.contains(&(index as u8 + 1)) || index + 1 == row.len() {
+      index += 1;
+    } else {
+      next_row.swap(index, index + 1);
+      index += 2;
+    }
+  }
+
+  next_row
+}
+
+/// Every row of one lead of the place notation, starting and ending with
+/// the lead head, starting from rounds.
+pub fn expand_lead(
+  notation: &str,
+  bells_amount: usize,
+) -> Result<Vec<Vec<u8>>, PlaceNotationError> {
+  let changes = parse_place_notation(notation, bells_amount)?;
+  let mut rows = vec![(1..=bells_amount as u8).collect::<Vec<u8>>()];
+
+  for places in changes.iter() {
+    let next_row = apply_change(rows.last().expect("Rows start with rounds"), places);
+    rows.push(next_row);
+  }
+
+  Ok(rows)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn can_get_bell_symbols() {
+    assert_eq!(bell_to_char(1), '1');
+    assert_eq!(bell_to_char(9), '9');
+    assert_eq!(bell_to_char(10), '0');
+    assert_eq!(bell_to_char(11), 'E');
+    assert_eq!(bell_to_char(12), 'T');
+    assert_eq!(bell_to_char(16), 'D');
+  }
+
+  #[test]
+  #[should_panic(expected = "No symbol for bell 0")]
+  fn panics_if_bell_has_no_symbol() {
+    bell_to_char(0);
+  }
+
+  #[test]
+  fn can_write_rows() {
+    assert_eq!(row_to_string(&[2, 1, 4, 3]), "2143");
+    assert_eq!(row_to_string(&[1, 2, 10, 11, 12]), "120ET");
+    assert_eq!(row_to_string(&[]), "");
+  }
+
+  #[test]
+  fn can_get_place_notation() {
+    assert_eq!(get_place_notation(&[1, 2, 3, 4], &[2, 1, 4, 3]), "x");
+    assert_eq!(get_place_notation(&[1, 2, 3, 4], &[1, 3, 2, 4]), "14");
+    assert_eq!(get_place_notation(&[1, 2, 3], &[2, 1, 3]), "3");
+    assert_eq!(get_places(&[1, 2, 3, 4], &[1, 3, 2, 4]), vec![1, 4]);
+    assert!(get_places(&[1, 2], &[2, 1]).is_empty());
+  }
+
+  #[test]
+  fn can_get_bells_from_symbols() {
+    assert_eq!(char_to_bell('1'), Some(1));
+    assert_eq!(char_to_bell('0'), Some(10));
+    assert_eq!(char_to_bell('e'), Some(11));
+    assert_eq!(char_to_bell('T'), Some(12));
+    assert_eq!(char_to_bell('?'), None);
+  }
+
+  #[test]
+  fn can_parse_place_notation() {
+    assert_eq!(
+      parse_place_notation("x14x14", 4).unwrap(),
+      vec![vec![], vec![1, 4], vec![], vec![1, 4]]
+    );
+    assert_eq!(
+      parse_place_notation("3.1.5", 5).unwrap(),
+      vec![vec![3], vec![1], vec![5]]
+    );
+    assert_eq!(
+      parse_place_notation("-12-", 4).unwrap(),
+      vec![vec![], vec![1, 2], vec![]]
+    );
+  }
+
+  #[test]
+  fn can_parse_symmetric_place_notation() {
+    let plain_bob_minor = parse_place_notation("x16x16x16,12", 6).unwrap();
+    let ampersand_form = parse_place_notation("&x16x16x16,+12", 6).unwrap();
+
+    assert_eq!(plain_bob_minor.len(), 12);
+    assert_eq!(plain_bob_minor[5], vec![1, 6]);
+    assert_eq!(plain_bob_minor[6], vec![]);
+    assert_eq!(plain_bob_minor[10], vec![]);
+    assert_eq!(plain_bob_minor[11], vec![1, 2]);
+    assert_eq!(plain_bob_minor, ampersand_form);
+  }
+
+  #[test]
+  fn fills_in_implied_places() {
+    assert_eq!(parse_place_notation("4", 6).unwrap(), vec![vec![1, 4]]);
+    assert_eq!(parse_place_notation("3", 6).unwrap(), vec![vec![3, 6]]);
+    assert_eq!(parse_place_notation("x", 5).unwrap(), vec![vec![5]]);
+    assert_eq!(parse_place_notation("1", 5).unwrap(), vec![vec![1]]);
+  }
+
+  #[test]
+  fn reports_invalid_place_notation() {
+    assert_eq!(
+      parse_place_notation("x1?", 4),
+      Err(PlaceNotationError::UnknownSymbol('?'))
+    );
+    assert_eq!(
+      parse_place_notation("17", 6),
+      Err(PlaceNotationError::PlaceOutOfRange('7'))
+    );
+    assert_eq!(
+      parse_place_notation("13", 6),
+      Err(PlaceNotationError::InvalidChange(String::from("13")))
+    );
+    assert_eq!(parse_place_notation("", 6), Err(PlaceNotationError::Empty));
+  }
+
+  #[test]
+  fn can_apply_a_change() {
+    assert_eq!(apply_change(&[1, 2, 3, 4], &[]), vec![2, 1, 4, 3]);
+    assert_eq!(apply_change(&[1, 2, 3, 4], &[1, 4]), vec![1, 3, 2, 4]);
+    assert_eq!(apply_change(&[1, 2, 3, 4, 5], &[3]), vec![2, 1, 3, 5, 4]);
+  }
+
+  #[test]
+  fn can_expand_a_lead() {
+    let rows = expand_lead("x14x14,12", 4).unwrap();
+    let strings: Vec<String> = rows.iter().map(|row| row_to_string(row)).collect();
+
+    assert_eq!(
+      strings,
+      vec![
+        "1234", "2143", "2413", "4231", "4321", "3412", "3142", "1324", "1342"
+      ]
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "changes must be the same length")]
+  fn place_notation_panics_if_mismatched_lengths() {
+    get_place_notation(&[1, 2, 3], &[1, 2]);
+  }
+}
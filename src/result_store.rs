@@ -0,0 +1,617 @@
+//! A binary file of methods found by a search, so they can be queried again
+//! without searching.
+//!
+//! Every number is little endian. The file is:
+//!
+//! - a header of `RBRS`, the format version and the number of bells,
+//! - each method as its number of rows (`u32`) followed by the
+//!   lexicographic rank of each row (`u16`),
+//! - an index entry for each method: its offset (`u64`), number of rows
+//!   (`u32`), class (`u8`) and music score (`i32`),
+//! - a trailer of the index's offset (`u64`), the number of methods
+//!   (`u32`) and `RBRS` again.
+
+use std::{
+  collections::HashMap,
+  error::Error,
+  fmt,
+  fs::File,
+  io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+  path::Path,
+};
+
+use crate::{
+  classification::{Classification, classify},
+  encoding::{MAX_RANK_BELLS, decode_ranks, encode_ranks},
+  permutation_graph::utility::factorial,
+};
+
+/// The most bells a store holds, as rows are stored as `u16` ranks.
+pub const MAX_STORE_BELLS: usize = MAX_RANK_BELLS;
+
+const MAGIC: &[u8; 4] = b"RBRS";
+const VERSION: u8 = 1;
+const HEADER_LENGTH: u64 = 6;
+const ENTRY_LENGTH: usize = 17;
+const TRAILER_LENGTH: u64 = 16;
+
+#[derive(Debug)]
+pub enum StoreError {
+  Io(io::Error),
+  NotAStore,
+  UnsupportedVersion(u8),
+  Corrupt(String),
+  UnknownMethod(usize),
+}
+
+impl fmt::Display for StoreError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      StoreError::Io(error) => write!(f, "could not read the result store: {error}"),
+      StoreError::NotAStore => write!(f, "file is not a result store"),
+      StoreError::UnsupportedVersion(version) => {
+        write!(f, "result store version {version} is not supported")
+      }
+      StoreError::Corrupt(reason) => write!(f, "result store is corrupt: {reason}"),
+      StoreError::UnknownMethod(id) => write!(f, "no method {id} in the result store"),
+    }
+  }
+}
+
+impl Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+  fn from(error: io::Error) -> Self {
+    StoreError::Io(error)
+  }
+}
+
+/// The index entry of a stored method.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StoredMethod {
+  offset: u64,
+  rows_amount: u32,
+  classification: Classification,
+  music: i32,
+}
+
+impl StoredMethod {
+  pub fn get_rows_amount(&self) -> usize {
+    self.rows_amount as usize
+  }
+
+  pub fn get_classification(&self) -> Classification {
+    self.classification
+  }
+
+  pub fn get_music(&self) -> i32 {
+    self.music
+  }
+
+  fn to_bytes(self) -> [u8; ENTRY_LENGTH] {
+    let mut bytes = [0; ENTRY_LENGTH];
+    bytes[..8].copy_from_slice(&self.offset.to_le_bytes());
+    bytes[8..12].copy_from_slice(&self.rows_amount.to_le_bytes());
+    bytes[12] = class_to_byte(self.classification);
+    bytes[13..].copy_from_slice(&self.music.to_le_bytes());
+    bytes
+  }
+
+  fn from_bytes(bytes: &[u8]) -> Result<StoredMethod, StoreError> {
+    Ok(StoredMethod {
+      offset: u64::from_le_bytes(bytes[..8].try_into().expect("Entries have 8 offset bytes")),
+      rows_amount: u32::from_le_bytes(bytes[8..12].try_into().expect("Entries have 4 row bytes")),
+      classification: byte_to_class(bytes[12])?,
+      music: i32::from_le_bytes(bytes[13..].try_into().expect("Entries have 4 music bytes")),
+    })
+  }
+}
+
+fn class_to_byte(classification: Classification) -> u8 {
+  match classification {
+    Classification::Plain => 0,
+    Classification::TrebleDodging => 1,
+    Classification::Unclassified => 2,
+  }
+}
+
+fn byte_to_class(byte: u8) -> Result<Classification, StoreError> {
+  match byte {
+    0 => Ok(Classification::Plain),
+    1 => Ok(Classification::TrebleDodging),
+    2 => Ok(Classification::Unclassified),
+    _ => Err(StoreError::Corrupt(format!("unknown class {byte}"))),
+  }
+}
+
+/// Checks a store can hold rows on `bells_amount` bells, before searching
+/// for methods to put in it.
+pub fn check_store_bells(bells_amount: usize) -> io::Result<()> {
+  if bells_amount > MAX_STORE_BELLS {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      format!("a result store holds at most {MAX_STORE_BELLS} bells, not {bells_amount}"),
+    ));
+  }
+
+  Ok(())
+}
+
+/// Writes methods to a result store as they are found.
+pub struct ResultStoreWriter<W: Write> {
+  out: W,
+  bells_amount: usize,
+  position: u64,
+  methods: Vec<StoredMethod>,
+}
+
+impl ResultStoreWriter<BufWriter<File>> {
+  pub fn create<P: AsRef<Path>>(
+    path: P,
+    bells_amount: usize,
+  ) -> io::Result<ResultStoreWriter<BufWriter<File>>> {
+    ResultStoreWriter::new(BufWriter::new(File::create(path)?), bells_amount)
+  }
+}
+
+impl<W: Write> ResultStoreWriter<W> {
+  pub fn new(mut out: W, bells_amount: usize) -> io::Result<ResultStoreWriter<W>> {
+    check_store_bells(bells_amount)?;
+
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION, bells_amount as u8])?;
+
+    Ok(ResultStoreWriter {
+      out,
+      bells_amount,
+      position: HEADER_LENGTH,
+      methods: Vec::new(),
+    })
+  }
+
+  /// Adds a method's rows with its music score, returning its id.
+  pub fn add<R: AsRef<[u8]>>(&mut self, rows: &[R], music: i32) -> io::Result<usize> {
+    if rows
+      .iter()
+      .any(|row| row.as_ref().len() != self.bells_amount)
+    {
+      panic!("Rows must be on the store's {} bells", self.bells_amount);
+    }
+
+    let mut bytes = Vec::with_capacity(4 + 2 * rows.len());
+    bytes.extend((rows.len() as u32).to_le_bytes());
+    for rank in encode_ranks(rows) {
+      bytes.extend(rank.to_le_bytes());
+    }
+    self.out.write_all(&bytes)?;
+
+    self.methods.push(StoredMethod {
+      offset: self.position,
+      rows_amount: rows.len() as u32,
+      classification: classify(rows),
+      music,
+    });
+    self.position += bytes.len() as u64;
+
+    Ok(self.methods.len() - 1)
+  }
+
+  /// Writes the index and hands back the output.
+  pub fn finish(mut self) -> io::Result<W> {
+    for method in &self.methods {
+      self.out.write_all(&method.to_bytes())?;
+    }
+    self.out.write_all(&self.position.to_le_bytes())?;
+    self
+      .out
+      .write_all(&(self.methods.len() as u32).to_le_bytes())?;
+    self.out.write_all(MAGIC)?;
+    self.out.flush()?;
+
+    Ok(self.out)
+  }
+}
+
+/// Which stored methods to find.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StoreQuery {
+  classification: Option<Classification>,
+  min_music: Option<i32>,
+  max_music: Option<i32>,
+  limit: Option<usize>,
+}
+
+impl StoreQuery {
+  pub fn new() -> StoreQuery {
+    StoreQuery::default()
+  }
+
+  pub fn classification(mut self, classification: Classification) -> Self {
+    self.classification = Some(classification);
+    self
+  }
+
+  pub fn min_music(mut self, score: i32) -> Self {
+    self.min_music = Some(score);
+    self
+  }
+
+  pub fn max_music(mut self, score: i32) -> Self {
+    self.max_music = Some(score);
+    self
+  }
+
+  pub fn limit(mut self, limit: usize) -> Self {
+    self.limit = Some(limit);
+    self
+  }
+
+  pub fn get_classification(&self) -> Option<Classification> {
+    self.classification
+  }
+
+  pub fn get_min_music(&self) -> Option<i32> {
+    self.min_music
+  }
+
+  pub fn get_max_music(&self) -> Option<i32> {
+    self.max_music
+  }
+
+  pub fn get_limit(&self) -> Option<usize> {
+    self.limit
+  }
+}
+
+/// A result store opened for queries. Only the index is read up front,
+/// each method's rows are read when asked for.
+pub struct ResultStore<R: Read + Seek> {
+  reader: R,
+  bells_amount: usize,
+  methods: Vec<StoredMethod>,
+  // Ids from the most to the least musical, overall and within each class
+  by_music: Vec<usize>,
+  by_class: HashMap<Classification, Vec<usize>>,
+}
+
+impl ResultStore<BufReader<File>> {
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<ResultStore<BufReader<File>>, StoreError> {
+    ResultStore::from_reader(BufReader::new(File::open(path)?))
+  }
+}
+
+impl<R: Read + Seek> ResultStore<R> {
+  pub fn from_reader(mut reader: R) -> Result<ResultStore<R>, StoreError> {
+    let mut header = [0; HEADER_LENGTH as usize];
+    reader.seek(SeekFrom::Start(0))?;
+    read_or_not_a_store(&mut reader, &mut header)?;
+    if &header[..4] != MAGIC {
+      return Err(StoreError::NotAStore);
+    }
+    if header[4] != VERSION {
+      return Err(StoreError::UnsupportedVersion(header[4]));
+    }
+    let bells_amount = header[5] as usize;
+    if bells_amount > MAX_STORE_BELLS {
+      return Err(StoreError::Corrupt(format!("{bells_amount} bells")));
+    }
+
+    let length = reader.seek(SeekFrom::End(0))?;
+    if length < HEADER_LENGTH + TRAILER_LENGTH {
+      return Err(StoreError::NotAStore);
+    }
+    let mut trailer = [0; TRAILER_LENGTH as usize];
+    reader.seek(SeekFrom::Start(length - TRAILER_LENGTH))?;
+    reader.read_exact(&mut trailer)?;
+    if &trailer[12..] != MAGIC {
+      return Err(StoreError::NotAStore);
+    }
+    let index_offset = u64::from_le_bytes(trailer[..8].try_into().expect("Trailer has 8 bytes"));
+    let methods_amount =
+      u32::from_le_bytes(trailer[8..12].try_into().expect("Trailer has 4 bytes")) as usize;
+    let index_end = methods_amount
+      .checked_mul(ENTRY_LENGTH)
+      .and_then(|index_length| index_offset.checked_add(index_length as u64))
+      .and_then(|index_end| index_end.checked_add(TRAILER_LENGTH));
+    if index_offset < HEADER_LENGTH || index_end != Some(length) {
+      return Err(StoreError::Corrupt(
+        "index does not fit the file".to_string(),
+      ));
+    }
+
+    let mut index = vec![0; methods_amount * ENTRY_LENGTH];
+    reader.seek(SeekFrom::Start(index_offset))?;
+    reader.read_exact(&mut index)?;
+    let methods = index
+      .chunks(ENTRY_LENGTH)
+      .map(StoredMethod::from_bytes)
+      .collect::<Result<Vec<StoredMethod>, StoreError>>()?;
+    if let Some(id) = methods.iter().position(|method| {
+      method
+        .offset
+        .checked_add(4 + 2 * method.rows_amount as u64)
+        .is_none_or(|end| method.offset < HEADER_LENGTH || end > index_offset)
+    }) {
+      return Err(StoreError::Corrupt(format!(
+        "method {id} does not fit the file"
+      )));
+    }
+
+    let mut by_music: Vec<usize> = (0..methods.len()).collect();
+    by_music.sort_by_key(|&id| std::cmp::Reverse(methods[id].music));
+    let mut by_class: HashMap<Classification, Vec<usize>> = HashMap::new();
+    for &id in &by_music {
+      by_class
+        .entry(methods[id].classification)
+        .or_default()
+        .push(id);
+    }
+
+    Ok(ResultStore {
+      reader,
+      bells_amount,
+      methods,
+      by_music,
+      by_class,
+    })
+  }
+
+  pub fn get_bells_amount(&self) -> usize {
+    self.bells_amount
+  }
+
+  pub fn len(&self) -> usize {
+    self.methods.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.methods.is_empty()
+  }
+
+  pub fn get_method(&self, id: usize) -> Option<&StoredMethod> {
+    self.methods.get(id)
+  }
+
+  /// Ids of the methods matching `query`, from the most to the least
+  /// musical, keeping the order they were stored in between equal scores.
+  pub fn query(&self, query: &StoreQuery) -> Vec<usize> {
+    let ids: &[usize] = match query.classification {
+      Some(classification) => self
+        .by_class
+        .get(&classification)
+        .map_or(&[], Vec::as_slice),
+      None => &self.by_music,
+    };
+
+    let music = |id: &usize| self.methods[*id].music;
+    let start = query
+      .max_music
+      .map_or(0, |max| ids.partition_point(|id| music(id) > max));
+
+    ids[start..]
+      .iter()
+      .take_while(|id| query.min_music.is_none_or(|min| music(id) >= min))
+      .take(query.limit.unwrap_or(usize::MAX))
+      .copied()
+      .collect()
+  }
+
+  /// Reads the rows of a stored method.
+  pub fn get_rows(&mut self, id: usize) -> Result<Vec<Vec<u8>>, StoreError> {
+    let method = *self.methods.get(id).ok_or(StoreError::UnknownMethod(id))?;
+    let rows_amount = method.rows_amount as usize;
+
+    let mut bytes = vec![0; 4 + 2 * rows_amount];
+    self.reader.seek(SeekFrom::Start(method.offset))?;
+    self.reader.read_exact(&mut bytes)?;
+    if bytes[..4] != method.rows_amount.to_le_bytes() {
+      return Err(StoreError::Corrupt(format!(
+        "method {id} does not match the index"
+      )));
+    }
+
+    let ranks: Vec<u16> = bytes[4..]
+      .chunks(2)
+      .map(|rank| u16::from_le_bytes([rank[0], rank[1]]))
+      .collect();
+    let rows_possible = factorial(self.bells_amount).max(1);
+    if let Some(rank) = ranks.iter().find(|&&rank| rank as usize >= rows_possible) {
+      return Err(StoreError::Corrupt(format!("row rank {rank}")));
+    }
+
+    Ok(decode_ranks(&ranks, self.bells_amount))
+  }
+}
+
+fn read_or_not_a_store(reader: &mut impl Read, bytes: &mut [u8]) -> Result<(), StoreError> {
+  reader
+    .read_exact(bytes)
+    .map_err(|error| match error.kind() {
+      io::ErrorKind::UnexpectedEof => StoreError::NotAStore,
+      _ => StoreError::Io(error),
+    })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use std::io::Cursor;
+
+  use crate::{MethodFilter, PermutationGraph, SearchOptions, test_helpers::plain_course};
+
+  /// The plain course coming back to rounds.
+  fn closed_course(notation: &str, bells_amount: usize) -> Vec<Vec<u8>> {
+    let mut rows = plain_course(notation, bells_amount);
+    rows.push(rows[0].clone());
+    rows
+  }
+
+  fn store_of(
+    methods: &[(Vec<Vec<u8>>, i32)],
+    bells_amount: usize,
+  ) -> ResultStore<Cursor<Vec<u8>>> {
+    let mut writer = ResultStoreWriter::new(Vec::new(), bells_amount).unwrap();
+    for (rows, music) in methods {
+      writer.add(rows, *music).unwrap();
+    }
+    ResultStore::from_reader(Cursor::new(writer.finish().unwrap())).unwrap()
+  }
+
+  #[test]
+  fn stores_search_results() {
+    let graph = PermutationGraph::new(4);
+    let methods: Vec<Vec<Vec<u8>>> = graph
+      .search(&SearchOptions::new().filter(MethodFilter::RightPlace))
+      .take(5)
+      .map(|method| crate::get_rows(&method))
+      .collect();
+    let mut store = store_of(
+      &methods
+        .iter()
+        .map(|rows| (rows.clone(), 0))
+        .collect::<Vec<_>>(),
+      4,
+    );
+
+    assert_eq!(store.get_bells_amount(), 4);
+    assert_eq!(store.len(), 5);
+    for (id, rows) in methods.iter().enumerate() {
+      assert_eq!(&store.get_rows(id).unwrap(), rows);
+      assert_eq!(store.get_method(id).unwrap().get_rows_amount(), 25);
+    }
+  }
+
+  #[test]
+  fn reopens_a_store_file() {
+    let path = std::env::temp_dir().join(format!("rusty_bells_store_{}.rbrs", std::process::id()));
+    let rows = closed_course("x16x16x16,12", 6);
+
+    let mut writer = ResultStoreWriter::create(&path, 6).unwrap();
+    writer.add(&rows, 3).unwrap();
+    writer.finish().unwrap();
+
+    let mut store = ResultStore::open(&path).unwrap();
+    assert_eq!(store.get_rows(0).unwrap(), rows);
+    assert_eq!(
+      store.get_method(0).unwrap().get_classification(),
+      Classification::Plain
+    );
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn queries_by_class_and_music() {
+    let plain_bob = closed_course("x16x16x16,12", 6);
+    let kent = closed_course("34x34.16x12x16x12x16,12", 6);
+    let store = store_of(
+      &[
+        (plain_bob.clone(), 2),
+        (kent.clone(), 5),
+        (plain_bob.clone(), 5),
+        (kent, 1),
+        (plain_bob, 8),
+      ],
+      6,
+    );
+
+    assert_eq!(store.query(&StoreQuery::new()), vec![4, 1, 2, 0, 3]);
+    assert_eq!(
+      store.query(&StoreQuery::new().classification(Classification::Plain)),
+      vec![4, 2, 0]
+    );
+    assert_eq!(
+      store.query(
+        &StoreQuery::new()
+          .classification(Classification::TrebleDodging)
+          .min_music(2)
+      ),
+      vec![1]
+    );
+    assert_eq!(
+      store.query(&StoreQuery::new().min_music(2).max_music(5)),
+      vec![1, 2, 0]
+    );
+    assert_eq!(store.query(&StoreQuery::new().limit(2)), vec![4, 1]);
+    assert_eq!(
+      store.query(&StoreQuery::new().classification(Classification::Unclassified)),
+      Vec::<usize>::new()
+    );
+  }
+
+  #[test]
+  fn rejects_other_files() {
+    let open = |bytes: Vec<u8>| ResultStore::from_reader(Cursor::new(bytes)).err().unwrap();
+
+    assert!(matches!(open(Vec::new()), StoreError::NotAStore));
+    assert!(matches!(
+      open(b"rusty bells, not a store".to_vec()),
+      StoreError::NotAStore
+    ));
+
+    let mut bytes = ResultStoreWriter::new(Vec::new(), 4)
+      .unwrap()
+      .finish()
+      .unwrap();
+    bytes[4] = 9;
+    assert!(matches!(open(bytes), StoreError::UnsupportedVersion(9)));
+  }
+
+  #[test]
+  fn rejects_unknown_methods() {
+    let mut store = store_of(&[], 4);
+
+    assert!(store.is_empty());
+    assert!(matches!(
+      store.get_rows(0),
+      Err(StoreError::UnknownMethod(0))
+    ));
+  }
+
+  #[test]
+  fn rejects_more_than_eight_bells() {
+    let error = ResultStoreWriter::new(Vec::new(), 9).err().unwrap();
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(
+      error.to_string(),
+      "a result store holds at most 8 bells, not 9"
+    );
+    assert!(check_store_bells(8).is_ok());
+  }
+
+  #[test]
+  fn rejects_corrupt_offsets() {
+    let open = |bytes: &[u8]| ResultStore::from_reader(Cursor::new(bytes.to_vec())).err();
+    let mut writer = ResultStoreWriter::new(Vec::new(), 4).unwrap();
+    writer.add(&closed_course("x14x14,12", 4), 0).unwrap();
+    let bytes = writer.finish().unwrap();
+    let trailer = bytes.len() - TRAILER_LENGTH as usize;
+    let index = trailer - ENTRY_LENGTH;
+
+    let mut huge_index = bytes.clone();
+    huge_index[trailer..trailer + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(open(&huge_index), Some(StoreError::Corrupt(_))));
+
+    let mut many_methods = bytes.clone();
+    many_methods[trailer + 8..trailer + 12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(open(&many_methods), Some(StoreError::Corrupt(_))));
+
+    let mut huge_offset = bytes.clone();
+    huge_offset[index..index + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(open(&huge_offset), Some(StoreError::Corrupt(_))));
+
+    let mut many_rows = bytes.clone();
+    many_rows[index + 8..index + 12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(open(&many_rows), Some(StoreError::Corrupt(_))));
+
+    assert!(open(&bytes).is_none());
+  }
+
+  #[test]
+  #[should_panic(expected = "Rows must be on the store's 4 bells")]
+  fn panics_on_rows_of_other_stages() {
+    let mut writer = ResultStoreWriter::new(Vec::new(), 4).unwrap();
+    let _ = writer.add(&[vec![1, 2, 3]], 0);
+  }
+}
@@ -0,0 +1,351 @@
+use std::{error::Error, fmt, ops::Mul, str::FromStr};
+
+use permutations::Permutation;
+
+use crate::place_notation::{char_to_bell, row_to_string};
+
+/// Whether a row is an even or odd number of swaps away from rounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parity {
+  /// In-course, e.g. `2314`.
+  Even,
+  /// Out-of-course, e.g. `2134`.
+  Odd,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RowError {
+  UnknownSymbol(char),
+  BellOutOfRange(u8),
+  RepeatedBell(u8),
+}
+
+impl fmt::Display for RowError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      RowError::UnknownSymbol(symbol) => write!(f, "unknown bell symbol '{symbol}'"),
+      RowError::BellOutOfRange(bell) => write!(f, "bell {bell} is beyond the number of bells"),
+      RowError::RepeatedBell(bell) => write!(f, "bell {bell} appears more than once"),
+    }
+  }
+}
+
+impl Error for RowError {}
+
+/// An order of the bells, numbered from 1. Multiplying `a * b` gives the row
+/// reached by ringing the permutation `b` starting from `a`, so a lead head
+/// times a row of the plain lead is the matching row of that lead.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Row {
+  bells: Vec<u8>,
+}
+
+impl Row {
+  /// Checks every bell from 1 to the number of bells appears exactly once.
+  pub fn new(bells: Vec<u8>) -> Result<Row, RowError> {
+    let mut seen = vec![false; bells.len()];
+
+    for &bell in bells.iter() {
+      let index = (bell as usize).wrapping_sub(1);
+      match seen.get(index) {
+        None => return Err(RowError::BellOutOfRange(bell)),
+        Some(true) => return Err(RowError::RepeatedBell(bell)),
+        Some(false) => seen[index] = true,
+      }
+    }
+
+    Ok(Row { bells })
+  }
+
+  pub fn rounds(bells_amount: usize) -> Row {
+    Row {
+      bells: (1..=bells_amount as u8).collect(),
+    }
+  }
+
+  /// The bells in order, e.g. `[1, 3, 2, 4]` for `1324`.
+  pub fn get_bells(&self) -> &Vec<u8> {
+    &self.bells
+  }
+
+  pub fn get_bells_amount(&self) -> usize {
+    self.bells.len()
+  }
+
+  pub fn is_rounds(&self) -> bool {
+    self
+      .bells
+      .iter()
+      .enumerate()
+      .all(|(index, &bell)| bell as usize == index + 1)
+  }
+
+  /// The row which multiplied by this one gives rounds.
+  pub fn inverse(&self) -> Row {
+    let mut bells = vec![0; self.bells.len()];
+    for (index, &bell) in self.bells.iter().enumerate() {
+      bells[bell as usize - 1] = index as u8 + 1;
+    }
+    Row { bells }
+  }
+
+  pub fn parity(&self) -> Parity {
+    let swaps = self.bells.len() - self.cycles_with_fixed_bells().count();
+    if swaps.is_multiple_of(2) {
+      Parity::Even
+    } else {
+      Parity::Odd
+    }
+  }
+
+  /// Whether an even number of swaps away from rounds.
+  pub fn is_in_course(&self) -> bool {
+    self.parity() == Parity::Even
+  }
+
+  /// 1 for in-course rows and -1 for out-of-course rows.
+  pub fn sign(&self) -> i8 {
+    match self.parity() {
+      Parity::Even => 1,
+      Parity::Odd => -1,
+    }
+  }
+
+  /// The cycles of bells moved by the row, each starting from its lowest
+  /// bell: the bell in the place of that number follows it. Bells at home
+  /// are left out, e.g. `2314` is `[[1, 2, 3]]`.
+  pub fn cycles(&self) -> Vec<Vec<u8>> {
+    self
+      .cycles_with_fixed_bells()
+      .filter(|cycle| cycle.len() > 1)
+      .collect()
+  }
+
+  fn cycles_with_fixed_bells(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let mut seen = vec![false; self.bells.len()];
+
+    (1..=self.bells.len() as u8).filter_map(move |start| {
+      if seen[start as usize - 1] {
+        return None;
+      }
+
+      let mut cycle = vec![start];
+      seen[start as usize - 1] = true;
+      let mut bell = self.bells[start as usize - 1];
+      while bell != start {
+        cycle.push(bell);
+        seen[bell as usize - 1] = true;
+        bell = self.bells[bell as usize - 1];
+      }
+
+      Some(cycle)
+    })
+  }
+
+  /// How many times the row has to be rung from itself to get back to rounds.
+  pub fn order(&self) -> usize {
+    self
+      .cycles()
+      .iter()
+      .map(Vec::len)
+      .fold(1, |order, length| order / gcd(order, length) * length)
+  }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+  if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl From<&Permutation> for Row {
+  fn from(permutation: &Permutation) -> Row {
+    let range: Vec<u8> = (1..=permutation.len() as u8).collect();
+    Row {
+      bells: permutation.permute(&range),
+    }
+  }
+}
+
+impl Mul for &Row {
+  type Output = Row;
+
+  fn mul(self, other: &Row) -> Row {
+    assert_eq!(
+      self.bells.len(),
+      other.bells.len(),
+      "Rows must be on the same number of bells"
+    );
+    Row {
+      bells: other
+        .bells
+        .iter()
+        .map(|&bell| self.bells[bell as usize - 1])
+        .collect(),
+    }
+  }
+}
+
+impl Mul for Row {
+  type Output = Row;
+
+  fn mul(self, other: Row) -> Row {
+    &self * &other
+  }
+}
+
+impl FromStr for Row {
+  type Err = RowError;
+
+  fn from_str(symbols: &str) -> Result<Row, RowError> {
+    let bells = symbols
+      .chars()
+      .map(|symbol| char_to_bell(symbol).ok_or(RowError::UnknownSymbol(symbol)))
+      .collect::<Result<Vec<u8>, RowError>>()?;
+
+    Row::new(bells)
+  }
+}
+
+impl fmt::Display for Row {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", row_to_string(&self.bells))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use permutations::Permutations;
+
+  use super::*;
+
+  fn row(symbols: &str) -> Row {
+    symbols.parse().unwrap()
+  }
+
+  fn all_rows(bells_amount: usize) -> Vec<(Permutation, Row)> {
+    Permutations::new(bells_amount)
+      .iter()
+      .map(|permutation| {
+        let row = Row::from(&permutation);
+        (permutation, row)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn can_create_a_row() {
+    assert_eq!(Row::new(vec![2, 1, 3]).unwrap().get_bells(), &vec![2, 1, 3]);
+    assert_eq!(Row::new(vec![1, 4, 3]), Err(RowError::BellOutOfRange(4)));
+    assert_eq!(Row::new(vec![1, 0, 3]), Err(RowError::BellOutOfRange(0)));
+    assert_eq!(Row::new(vec![1, 3, 3]), Err(RowError::RepeatedBell(3)));
+    assert_eq!(Row::rounds(4), row("1234"));
+    assert!(Row::rounds(0).is_rounds());
+  }
+
+  #[test]
+  fn can_read_and_write_rows() {
+    assert_eq!(row("1324").get_bells(), &vec![1, 3, 2, 4]);
+    assert_eq!(row("1234567890ET").to_string(), "1234567890ET");
+    assert_eq!("12?".parse::<Row>(), Err(RowError::UnknownSymbol('?')));
+  }
+
+  #[test]
+  fn can_multiply_rows() {
+    assert_eq!(row("135264") * row("213456"), row("315264"));
+    assert_eq!(&row("1342") * &row("1342"), row("1423"));
+  }
+
+  #[test]
+  #[should_panic(expected = "Rows must be on the same number of bells")]
+  fn multiplying_panics_if_mismatched_lengths() {
+    let _ = row("123") * row("1234");
+  }
+
+  #[test]
+  fn can_invert_rows() {
+    assert_eq!(row("1342").inverse(), row("1423"));
+    assert_eq!(row("213").inverse(), row("213"));
+
+    for (_, row) in all_rows(4) {
+      assert!((&row * &row.inverse()).is_rounds());
+      assert!((&row.inverse() * &row).is_rounds());
+    }
+  }
+
+  #[test]
+  fn can_get_parity_and_sign() {
+    assert_eq!(row("2314").parity(), Parity::Even);
+    assert_eq!(row("2134").parity(), Parity::Odd);
+    assert!(row("1234").is_in_course());
+    assert_eq!(row("2134").sign(), -1);
+  }
+
+  #[test]
+  fn can_decompose_into_cycles() {
+    assert_eq!(row("2314").cycles(), vec![vec![1, 2, 3]]);
+    assert_eq!(
+      row("214365").cycles(),
+      vec![vec![1, 2], vec![3, 4], vec![5, 6]]
+    );
+    assert!(row("1234").cycles().is_empty());
+    assert_eq!(row("231546").order(), 6);
+    assert_eq!(row("1234").order(), 1);
+  }
+
+  #[test]
+  fn matches_rows_made_from_permutations() {
+    for (permutation, row) in all_rows(4) {
+      assert_eq!(row.is_rounds(), permutation == Permutation::identity(4));
+      assert_eq!(row.inverse(), Row::from(&permutation.inv()));
+      assert_eq!(row.is_in_course(), permutation.is_even());
+      assert_eq!(row.sign() as isize, permutation.sign());
+    }
+  }
+
+  #[test]
+  fn multiplies_like_permutations() {
+    for bells_amount in 1..=5 {
+      let rows = all_rows(bells_amount);
+
+      for (left_permutation, left) in rows.iter() {
+        for (right_permutation, right) in rows.iter() {
+          assert_eq!(
+            left * right,
+            Row::from(&(right_permutation * left_permutation))
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn order_brings_a_row_back_to_rounds() {
+    for (permutation, row) in all_rows(6) {
+      let order = row.order() as u32;
+
+      assert!(permutation.pow(order) == Permutation::identity(6));
+      assert!((1..order).all(|power| permutation.pow(power) != Permutation::identity(6)));
+    }
+  }
+
+  #[test]
+  fn cycles_cover_every_moved_bell() {
+    for (permutation, row) in all_rows(5) {
+      let cycles = row.cycles();
+      let moved_bells = cycles.iter().map(Vec::len).sum::<usize>();
+
+      assert_eq!(
+        moved_bells,
+        (0..5)
+          .filter(|&index| permutation.apply(index) != index)
+          .count()
+      );
+
+      for cycle in cycles.iter() {
+        for (index, &bell) in cycle.iter().enumerate() {
+          let next_bell = cycle[(index + 1) % cycle.len()];
+          assert_eq!(row.get_bells()[bell as usize - 1], next_bell);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,493 @@
+//! A small HTTP service answering questions about methods with JSON.
+//!
+//! Every endpoint takes `GET` requests with query parameters:
+//!
+//! - `/methods?bells=N&filter=..&treble_path=..&fixed=..&limit=..` streams
+//!   each method found as one line of JSON (NDJSON).
+//! - `/place-notation?notation=..&bells=N` expands a lead of place notation.
+//! - `/truth?notation=..&bells=N&tenors=T` checks the plain course and its
+//!   false course heads.
+//! - `/classify?notation=..&bells=N` classifies the method by its treble.
+//!
+//! Bad parameters are answered with status 400 and `{"error": ".."}`.
+
+use std::{
+  error::Error,
+  fmt,
+  io::{self, BufReader, Write},
+  net::{TcpListener, TcpStream},
+  str::FromStr,
+  thread,
+};
+
+use crate::{
+  MethodFilter, PermutationGraph, SearchOptions, TreblePath,
+  classification::classify,
+  falseness,
+  method::Method,
+  music::MusicScorer,
+  place_notation::{bell_to_char, get_place_notation, row_to_string},
+};
+
+mod http;
+mod json;
+
+pub use http::{Request, read_request};
+
+/// The most bells `/methods` will search. The search holds every half method
+/// before yielding any method, so neither `limit` nor the client hanging up
+/// can cut a bigger search short.
+pub const MAX_SEARCH_BELLS: usize = 4;
+
+/// The most bells place notation can name.
+const MAX_BELLS: usize = 33;
+
+const JSON: &str = "application/json";
+const NDJSON: &str = "application/x-ndjson";
+
+#[derive(Debug, PartialEq)]
+pub enum ServiceError {
+  BadRequest(String),
+  NotFound(String),
+  MethodNotAllowed(String),
+}
+
+impl ServiceError {
+  pub fn get_status(&self) -> u16 {
+    match self {
+      ServiceError::BadRequest(_) => 400,
+      ServiceError::NotFound(_) => 404,
+      ServiceError::MethodNotAllowed(_) => 405,
+    }
+  }
+}
+
+impl fmt::Display for ServiceError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ServiceError::BadRequest(message) => write!(f, "{message}"),
+      ServiceError::NotFound(path) => write!(f, "nothing at '{path}'"),
+      ServiceError::MethodNotAllowed(method) => write!(f, "'{method}' is not supported, use GET"),
+    }
+  }
+}
+
+impl Error for ServiceError {}
+
+/// A search asked for by `/methods`.
+#[derive(Clone, Debug)]
+pub struct MethodsQuery {
+  bells_amount: usize,
+  fixed_bells: Vec<u8>,
+  search_options: SearchOptions,
+  limit: Option<usize>,
+}
+
+impl MethodsQuery {
+  pub fn get_bells_amount(&self) -> usize {
+    self.bells_amount
+  }
+
+  pub fn get_fixed_bells(&self) -> &[u8] {
+    &self.fixed_bells
+  }
+
+  pub fn get_search_options(&self) -> &SearchOptions {
+    &self.search_options
+  }
+
+  pub fn get_limit(&self) -> Option<usize> {
+    self.limit
+  }
+
+  /// Writes each method found as a line of JSON, stopping at the next method
+  /// if `out` fails, e.g. when the client hangs up. The half methods are all
+  /// found first whatever happens to `out`.
+  pub fn write_methods(&self, out: &mut impl Write) -> io::Result<()> {
+    let graph = PermutationGraph::with_fixed_bells(self.bells_amount, &self.fixed_bells);
+    let music_scorer = MusicScorer::for_stage(self.bells_amount);
+
+    let methods = graph
+      .search(&self.search_options)
+      .take(self.limit.unwrap_or(usize::MAX));
+    for (index, method) in methods.enumerate() {
+      let rows = crate::get_rows(&method);
+      let line = json::object(&[
+        ("index", index.to_string()),
+        ("rows", rows_to_json(&rows)),
+        ("place_notation", json::string(&course_notation(&rows))),
+        ("music", music_scorer.score(&rows).to_string()),
+        ("classification", json::string(&classify(&rows).to_string())),
+      ]);
+      writeln!(out, "{line}")?;
+      out.flush()?;
+    }
+
+    Ok(())
+  }
+}
+
+/// What a request should be answered with.
+#[derive(Debug)]
+pub enum Response {
+  Json(String),
+  Methods(MethodsQuery),
+}
+
+/// Answers requests on `listener` forever, one thread per connection.
+pub fn serve(listener: TcpListener) -> io::Result<()> {
+  for stream in listener.incoming() {
+    let stream = stream?;
+    thread::spawn(move || {
+      // A client hanging up part way only ends its own connection
+      let _ = handle_connection(stream);
+    });
+  }
+
+  Ok(())
+}
+
+/// Reads one request from `stream` and writes its response.
+pub fn handle_connection(stream: TcpStream) -> io::Result<()> {
+  let mut reader = BufReader::new(stream.try_clone()?);
+  let mut out = io::BufWriter::new(stream);
+
+  match read_request(&mut reader)? {
+    Some(request) => respond(&request, &mut out),
+    None => http::write_response(
+      &mut out,
+      400,
+      JSON,
+      &error_json(&ServiceError::BadRequest("not an HTTP request".to_string())),
+    ),
+  }
+}
+
+/// Writes the whole HTTP response to `request`.
+pub fn respond(request: &Request, out: &mut impl Write) -> io::Result<()> {
+  match route(request) {
+    Ok(Response::Json(body)) => http::write_response(out, 200, JSON, &body),
+    Ok(Response::Methods(query)) => {
+      http::write_stream_head(out, NDJSON)?;
+      query.write_methods(out)
+    }
+    Err(error) => http::write_response(out, error.get_status(), JSON, &error_json(&error)),
+  }
+}
+
+/// Works out the response to `request` without writing anything.
+pub fn route(request: &Request) -> Result<Response, ServiceError> {
+  if request.get_method() != "GET" {
+    return Err(ServiceError::MethodNotAllowed(
+      request.get_method().to_string(),
+    ));
+  }
+
+  match request.get_path() {
+    "/methods" => Ok(Response::Methods(methods_query(request)?)),
+    "/place-notation" => Ok(Response::Json(place_notation_json(request)?)),
+    "/truth" => Ok(Response::Json(truth_json(request)?)),
+    "/classify" => Ok(Response::Json(classify_json(request)?)),
+    path => Err(ServiceError::NotFound(path.to_string())),
+  }
+}
+
+fn methods_query(request: &Request) -> Result<MethodsQuery, ServiceError> {
+  let bells_amount = get_bells(request, MAX_SEARCH_BELLS)?;
+
+  let fixed_bells = match request.get_parameter("fixed") {
+    Some(fixed) if !fixed.is_empty() => fixed
+      .split(',')
+      .map(|bell| parse_parameter::<u8>("fixed", bell.trim()))
+      .collect::<Result<Vec<u8>, ServiceError>>()?,
+    _ => Vec::new(),
+  };
+  PermutationGraph::check_fixed_bells(bells_amount, &fixed_bells)
+    .map_err(|error| ServiceError::BadRequest(error.to_string()))?;
+
+  let mut search_options = SearchOptions::new();
+  if let Some(filter) = request.get_parameter("filter") {
+    search_options = search_options.filter(parse_parameter::<MethodFilter>("filter", filter)?);
+  }
+  if let Some(path) = request.get_parameter("treble_path") {
+    let path = TreblePath::parse(path, bells_amount)
+      .map_err(|error| ServiceError::BadRequest(format!("treble_path: {error}")))?;
+    search_options = search_options.treble_path(path);
+  }
+
+  let limit = request
+    .get_parameter("limit")
+    .map(|limit| parse_parameter::<usize>("limit", limit))
+    .transpose()?;
+
+  Ok(MethodsQuery {
+    bells_amount,
+    fixed_bells,
+    search_options,
+    limit,
+  })
+}
+
+fn place_notation_json(request: &Request) -> Result<String, ServiceError> {
+  let method = get_method(request)?;
+  let changes = method.get_changes().iter().map(|places| {
+    if places.is_empty() {
+      json::string("x")
+    } else {
+      json::string(
+        &places
+          .iter()
+          .map(|&place| bell_to_char(place))
+          .collect::<String>(),
+      )
+    }
+  });
+
+  Ok(json::object(&[
+    ("bells", method.get_bells_amount().to_string()),
+    ("changes", json::array(changes)),
+    ("lead_length", method.get_lead_length().to_string()),
+    (
+      "lead_head",
+      json::string(&row_to_string(&method.get_lead_head())),
+    ),
+    ("lead", rows_to_json(&method.get_lead_rows())),
+  ]))
+}
+
+fn truth_json(request: &Request) -> Result<String, ServiceError> {
+  let method = get_method(request)?;
+  let tenors = request
+    .get_parameter("tenors")
+    .map(|tenors| parse_parameter::<usize>("tenors", tenors))
+    .transpose()?
+    .unwrap_or(falseness::MAJOR_TENORS);
+
+  let mut plain_course = method.get_plain_course();
+  let course_length = plain_course.len();
+  plain_course.sort_unstable();
+  plain_course.dedup();
+
+  let false_course_heads = falseness::get_false_course_heads(&method, tenors);
+  Ok(json::object(&[
+    ("true", (plain_course.len() == course_length).to_string()),
+    ("course_length", course_length.to_string()),
+    ("clean_proof", false_course_heads.is_empty().to_string()),
+    (
+      "false_course_heads",
+      json::array(
+        false_course_heads
+          .iter()
+          .map(|head| json::string(&head.to_string())),
+      ),
+    ),
+  ]))
+}
+
+fn classify_json(request: &Request) -> Result<String, ServiceError> {
+  let method = get_method(request)?;
+
+  Ok(json::object(&[(
+    "classification",
+    json::string(&classify(&method.get_plain_course()).to_string()),
+  )]))
+}
+
+fn get_method(request: &Request) -> Result<Method, ServiceError> {
+  let bells_amount = get_bells(request, MAX_BELLS)?;
+  let notation = get_parameter(request, "notation")?;
+
+  Method::from_place_notation(notation, bells_amount)
+    .map_err(|error| ServiceError::BadRequest(format!("notation: {error}")))
+}
+
+fn get_bells(request: &Request, max_bells: usize) -> Result<usize, ServiceError> {
+  let bells_amount = parse_parameter::<usize>("bells", get_parameter(request, "bells")?)?;
+  if bells_amount < 2 || bells_amount > max_bells {
+    return Err(ServiceError::BadRequest(format!(
+      "bells must be from 2 to {max_bells}"
+    )));
+  }
+
+  Ok(bells_amount)
+}
+
+fn get_parameter<'a>(request: &'a Request, name: &str) -> Result<&'a str, ServiceError> {
+  request
+    .get_parameter(name)
+    .ok_or_else(|| ServiceError::BadRequest(format!("missing parameter '{name}'")))
+}
+
+fn parse_parameter<T>(name: &str, value: &str) -> Result<T, ServiceError>
+where
+  T: FromStr,
+  T::Err: fmt::Display,
+{
+  value
+    .parse()
+    .map_err(|error| ServiceError::BadRequest(format!("{name}: {error}")))
+}
+
+fn rows_to_json(rows: &[Vec<u8>]) -> String {
+  json::array(rows.iter().map(|row| json::string(&row_to_string(row))))
+}
+
+/// The place notation of every change in `rows`, dotted.
+fn course_notation(rows: &[Vec<u8>]) -> String {
+  rows
+    .windows(2)
+    .map(|pair| get_place_notation(&pair[0], &pair[1]))
+    .collect::<Vec<String>>()
+    .join(".")
+}
+
+fn error_json(error: &ServiceError) -> String {
+  json::object(&[("error", json::string(&error.to_string()))])
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use std::{io::Read, net::Shutdown};
+
+  fn request(target: &str) -> Request {
+    read_request(&mut format!("GET {target} HTTP/1.1\r\n\r\n").as_bytes())
+      .unwrap()
+      .unwrap()
+  }
+
+  fn json_body(target: &str) -> String {
+    match route(&request(target)).unwrap() {
+      Response::Json(body) => body,
+      Response::Methods(_) => panic!("Expected a JSON body"),
+    }
+  }
+
+  fn response(target: &str) -> String {
+    let mut out = Vec::new();
+    respond(&request(target), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn expands_place_notation() {
+    assert_eq!(
+      json_body("/place-notation?notation=x14x14,12&bells=4"),
+      r#"{"bells":4,"changes":["x","14","x","14","x","14","x","12"],"lead_length":8,"lead_head":"1342","lead":["1234","2143","2413","4231","4321","3412","3142","1324"]}"#
+    );
+  }
+
+  #[test]
+  fn checks_truth() {
+    assert_eq!(
+      json_body("/truth?notation=x14x14,12&bells=4&tenors=0"),
+      r#"{"true":true,"course_length":24,"clean_proof":false,"false_course_heads":["1243","1324","1342","1423","1432"]}"#
+    );
+    assert_eq!(
+      json_body("/truth?notation=x18x18x18x18,12&bells=8"),
+      r#"{"true":true,"course_length":112,"clean_proof":true,"false_course_heads":[]}"#
+    );
+  }
+
+  #[test]
+  fn classifies_methods() {
+    assert_eq!(
+      json_body("/classify?notation=x16x16x16,12&bells=6"),
+      r#"{"classification":"Plain"}"#
+    );
+    assert_eq!(
+      json_body("/classify?notation=34x34.16x12x16x12x16%2C12&bells=6"),
+      r#"{"classification":"Treble Dodging"}"#
+    );
+  }
+
+  #[test]
+  fn reads_method_queries() {
+    let Response::Methods(query) = route(&request(
+      "/methods?bells=4&fixed=4&treble_path=plain-hunt&limit=3",
+    ))
+    .unwrap() else {
+      panic!("Expected a methods query");
+    };
+
+    assert_eq!(query.get_bells_amount(), 4);
+    assert_eq!(query.get_fixed_bells(), &[4]);
+    assert!(query.get_search_options().get_treble_path().is_some());
+    assert_eq!(query.get_limit(), Some(3));
+  }
+
+  #[test]
+  fn streams_methods() {
+    let text = response("/methods?bells=4&limit=2");
+    let (head, body) = text.split_once("\r\n\r\n").unwrap();
+
+    assert!(head.starts_with("HTTP/1.1 200 OK"));
+    assert!(head.contains(NDJSON));
+    assert!(!head.contains("Content-Length"));
+
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(r#"{"index":0,"rows":["1234","#));
+    assert!(lines[1].starts_with(r#"{"index":1,"#));
+    assert!(
+      lines
+        .iter()
+        .all(|line| line.contains(r#""classification":"#))
+    );
+  }
+
+  #[test]
+  fn rejects_bad_requests() {
+    let bad_request = |target: &str| match route(&request(target)) {
+      Err(error) => error,
+      Ok(_) => panic!("Expected {target} to fail"),
+    };
+
+    assert_eq!(
+      bad_request("/classify?bells=4"),
+      ServiceError::BadRequest("missing parameter 'notation'".to_string())
+    );
+    assert_eq!(bad_request("/methods?bells=9").get_status(), 400);
+    assert_eq!(bad_request("/methods?bells=5&limit=1").get_status(), 400);
+    assert_eq!(bad_request("/methods?bells=4&fixed=5").get_status(), 400);
+    assert_eq!(
+      bad_request("/methods?bells=3&treble_path=treble-bob").get_status(),
+      400
+    );
+    assert_eq!(
+      bad_request("/methods?bells=4&filter=loud").get_status(),
+      400
+    );
+    assert_eq!(bad_request("/truth?notation=x19&bells=4").get_status(), 400);
+    assert_eq!(
+      bad_request("/nowhere"),
+      ServiceError::NotFound("/nowhere".to_string())
+    );
+
+    let text = response("/classify?bells=four&notation=x");
+    assert!(text.starts_with("HTTP/1.1 400 Bad Request"));
+    assert!(text.ends_with(r#"{"error":"bells: invalid digit found in string"}"#));
+  }
+
+  #[test]
+  fn serves_localhost() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener));
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+      stream,
+      "GET /classify?notation=x14x14,12&bells=4 HTTP/1.1\r\nHost: localhost\r\n\r\n"
+    )
+    .unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+
+    let mut text = String::new();
+    stream.read_to_string(&mut text).unwrap();
+
+    assert!(text.starts_with("HTTP/1.1 200 OK"));
+    assert!(text.ends_with(r#"{"classification":"Plain"}"#));
+  }
+}
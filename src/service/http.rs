@@ -0,0 +1,177 @@
+//! Reading HTTP/1.1 requests and writing responses, closing the connection
+//! after each one.
+
+use std::{
+  collections::HashMap,
+  io::{self, BufRead, Write},
+};
+
+const MAX_HEADER_LINES: usize = 100;
+
+/// A request's method, path and decoded query parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+  method: String,
+  path: String,
+  query: HashMap<String, String>,
+}
+
+impl Request {
+  pub fn get_method(&self) -> &str {
+    &self.method
+  }
+
+  pub fn get_path(&self) -> &str {
+    &self.path
+  }
+
+  pub fn get_parameter(&self, name: &str) -> Option<&str> {
+    self.query.get(name).map(String::as_str)
+  }
+}
+
+/// Reads the request line and skips the headers, or `None` if the line is not HTTP.
+pub fn read_request(input: &mut impl BufRead) -> io::Result<Option<Request>> {
+  let mut line = String::new();
+  input.read_line(&mut line)?;
+
+  let mut parts = line.split_whitespace();
+  let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next())
+  else {
+    return Ok(None);
+  };
+  if !version.starts_with("HTTP/") {
+    return Ok(None);
+  }
+
+  for _ in 0..MAX_HEADER_LINES {
+    let mut header = String::new();
+    if input.read_line(&mut header)? == 0 || header.trim().is_empty() {
+      break;
+    }
+  }
+
+  let (path, query) = target.split_once('?').unwrap_or((target, ""));
+  let query = query
+    .split('&')
+    .filter(|pair| !pair.is_empty())
+    .map(|pair| {
+      let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+      (decode(name), decode(value))
+    })
+    .collect();
+
+  Ok(Some(Request {
+    method: method.to_string(),
+    path: decode(path),
+    query,
+  }))
+}
+
+/// Decodes `%XX` escapes and `+` for spaces.
+fn decode(text: &str) -> String {
+  let bytes = text.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+
+  while index < bytes.len() {
+    match bytes[index] {
+      b'+' => decoded.push(b' '),
+      b'%' => {
+        let escaped = text
+          .get(index + 1..index + 3)
+          .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+          Some(byte) => {
+            decoded.push(byte);
+            index += 2;
+          }
+          None => decoded.push(b'%'),
+        }
+      }
+      byte => decoded.push(byte),
+    }
+    index += 1;
+  }
+
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn reason(status: u16) -> &'static str {
+  match status {
+    200 => "OK",
+    400 => "Bad Request",
+    404 => "Not Found",
+    405 => "Method Not Allowed",
+    _ => "Internal Server Error",
+  }
+}
+
+/// Writes a whole response with its length.
+pub fn write_response(
+  out: &mut impl Write,
+  status: u16,
+  content_type: &str,
+  body: &str,
+) -> io::Result<()> {
+  write!(
+    out,
+    "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+    reason(status),
+    body.len()
+  )?;
+  out.flush()
+}
+
+/// Starts a response whose body runs until the connection closes.
+pub fn write_stream_head(out: &mut impl Write, content_type: &str) -> io::Result<()> {
+  write!(
+    out,
+    "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nConnection: close\r\n\r\n"
+  )?;
+  out.flush()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn reads_requests() {
+    let mut input =
+      "GET /classify?notation=x14x14%2C12&bells=4&empty HTTP/1.1\r\nHost: localhost\r\n\r\n"
+        .as_bytes();
+    let request = read_request(&mut input).unwrap().unwrap();
+
+    assert_eq!(request.get_method(), "GET");
+    assert_eq!(request.get_path(), "/classify");
+    assert_eq!(request.get_parameter("notation"), Some("x14x14,12"));
+    assert_eq!(request.get_parameter("bells"), Some("4"));
+    assert_eq!(request.get_parameter("empty"), Some(""));
+    assert_eq!(request.get_parameter("missing"), None);
+  }
+
+  #[test]
+  fn ignores_other_protocols() {
+    assert_eq!(read_request(&mut "hello\r\n".as_bytes()).unwrap(), None);
+    assert_eq!(read_request(&mut "".as_bytes()).unwrap(), None);
+  }
+
+  #[test]
+  fn decodes_escapes() {
+    assert_eq!(decode("max-places%3D2+no"), "max-places=2 no");
+    assert_eq!(decode("100%"), "100%");
+    assert_eq!(decode("%zz"), "%zz");
+  }
+
+  #[test]
+  fn writes_responses() {
+    let mut out = Vec::new();
+    write_response(&mut out, 404, "application/json", "{}").unwrap();
+
+    assert_eq!(
+      String::from_utf8(out).unwrap(),
+      "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+    );
+  }
+}
@@ -0,0 +1,57 @@
+//! Just enough JSON writing for the service's responses.
+
+use std::fmt::Write;
+
+/// A JSON string, quoted and escaped.
+pub fn string(text: &str) -> String {
+  let mut json = String::from("\"");
+  for symbol in text.chars() {
+    match symbol {
+      '"' => json.push_str("\\\""),
+      '\\' => json.push_str("\\\\"),
+      '\n' => json.push_str("\\n"),
+      '\r' => json.push_str("\\r"),
+      '\t' => json.push_str("\\t"),
+      symbol if symbol.is_control() => {
+        write!(json, "\\u{:04x}", symbol as u32).expect("Writing to a string can not fail")
+      }
+      symbol => json.push(symbol),
+    }
+  }
+  json.push('"');
+  json
+}
+
+/// A JSON array of values which are already JSON.
+pub fn array(values: impl IntoIterator<Item = String>) -> String {
+  format!(
+    "[{}]",
+    values.into_iter().collect::<Vec<String>>().join(",")
+  )
+}
+
+/// A JSON object of fields whose values are already JSON.
+pub fn object(fields: &[(&str, String)]) -> String {
+  let fields: Vec<String> = fields
+    .iter()
+    .map(|(name, value)| format!("{}:{value}", string(name)))
+    .collect();
+  format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn writes_json() {
+    assert_eq!(string("Plain \"Bob\"\n\\"), r#""Plain \"Bob\"\n\\""#);
+    assert_eq!(string("\u{1}"), r#""\u0001""#);
+    assert_eq!(array([string("a"), 1.to_string()]), r#"["a",1]"#);
+    assert_eq!(array(Vec::new()), "[]");
+    assert_eq!(
+      object(&[("name", string("x")), ("true", true.to_string())]),
+      r#"{"name":"x","true":true}"#
+    );
+  }
+}
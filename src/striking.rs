@@ -0,0 +1,514 @@
+//! Measuring how well a touch was struck, from the times each bell struck.
+//!
+//! Strike times are read as CSV lines of `bell,milliseconds`, such as a
+//! ringing simulator records, and taken in order a row at a time to compare
+//! with the rows that should have been rung. A row may take one strike more
+//! or fewer than the number of bells, whichever best matches it and the row
+//! after, so a dropped or extra strike spoils only its own row rather than
+//! every row after it. Each whole row is fitted to even gaps between bells,
+//! the gap being the average across the touch, so a bell's error is how far
+//! it struck from its place in that row.
+
+use std::{
+  error::Error,
+  fmt,
+  io::{self, BufRead},
+};
+
+#[derive(Debug)]
+pub enum StrikingError {
+  Io(io::Error),
+  InvalidLine(String),
+  NoRows,
+}
+
+impl fmt::Display for StrikingError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      StrikingError::Io(error) => write!(f, "could not read strike times: {error}"),
+      StrikingError::InvalidLine(line) => write!(f, "invalid strike time line '{line}'"),
+      StrikingError::NoRows => write!(f, "not enough strikes for a whole row"),
+    }
+  }
+}
+
+impl Error for StrikingError {}
+
+impl From<io::Error> for StrikingError {
+  fn from(error: io::Error) -> Self {
+    StrikingError::Io(error)
+  }
+}
+
+/// A bell striking, `time` milliseconds after the recording started.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Strike {
+  bell: u8,
+  time: f64,
+}
+
+impl Strike {
+  pub fn new(bell: u8, time: f64) -> Strike {
+    Strike { bell, time }
+  }
+
+  pub fn get_bell(&self) -> u8 {
+    self.bell
+  }
+
+  pub fn get_time(&self) -> f64 {
+    self.time
+  }
+}
+
+/// A row rung in a different order to the one expected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mistake {
+  row_index: usize,
+  expected: Vec<u8>,
+  observed: Vec<u8>,
+}
+
+impl Mistake {
+  pub fn get_row_index(&self) -> usize {
+    self.row_index
+  }
+
+  pub fn get_expected(&self) -> &[u8] {
+    &self.expected
+  }
+
+  pub fn get_observed(&self) -> &[u8] {
+    &self.observed
+  }
+}
+
+/// The average and spread of a set of gaps, in milliseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GapStatistics {
+  count: usize,
+  mean: f64,
+  standard_deviation: f64,
+}
+
+impl GapStatistics {
+  fn new(gaps: &[f64]) -> GapStatistics {
+    if gaps.is_empty() {
+      return GapStatistics::default();
+    }
+
+    let count = gaps.len();
+    let mean = gaps.iter().sum::<f64>() / count as f64;
+    let variance = gaps.iter().map(|gap| (gap - mean).powi(2)).sum::<f64>() / count as f64;
+
+    GapStatistics {
+      count,
+      mean,
+      standard_deviation: variance.sqrt(),
+    }
+  }
+
+  pub fn get_count(&self) -> usize {
+    self.count
+  }
+
+  pub fn get_mean(&self) -> f64 {
+    self.mean
+  }
+
+  pub fn get_standard_deviation(&self) -> f64 {
+    self.standard_deviation
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrikingReport {
+  rows_amount: usize,
+  bell_gap: f64,
+  mistakes: Vec<Mistake>,
+  bell_errors: Vec<f64>,
+  handstroke_gaps: GapStatistics,
+  backstroke_gaps: GapStatistics,
+}
+
+impl StrikingReport {
+  /// How many whole rows were compared.
+  pub fn get_rows_amount(&self) -> usize {
+    self.rows_amount
+  }
+
+  /// The average milliseconds between bells within a row.
+  pub fn get_bell_gap(&self) -> f64 {
+    self.bell_gap
+  }
+
+  pub fn get_mistakes(&self) -> &[Mistake] {
+    &self.mistakes
+  }
+
+  /// The root mean square error of each bell in milliseconds, treble first.
+  pub fn get_bell_errors(&self) -> &[f64] {
+    &self.bell_errors
+  }
+
+  /// The gaps from the last bell of a backstroke row to the first bell of
+  /// the following handstroke.
+  pub fn get_handstroke_gaps(&self) -> GapStatistics {
+    self.handstroke_gaps
+  }
+
+  /// The gaps from the last bell of a handstroke row to the first bell of
+  /// the following backstroke.
+  pub fn get_backstroke_gaps(&self) -> GapStatistics {
+    self.backstroke_gaps
+  }
+}
+
+/// Reads `bell,milliseconds` lines for bells 1 to `bells_amount`, skipping
+/// blank lines and a header.
+pub fn read_strikes<R: BufRead>(
+  input: R,
+  bells_amount: usize,
+) -> Result<Vec<Strike>, StrikingError> {
+  let mut strikes = Vec::new();
+
+  for (index, line) in input.lines().enumerate() {
+    let line = line?;
+    if line.trim().is_empty() {
+      continue;
+    }
+
+    let strike = line
+      .split_once(',')
+      .and_then(|(bell, time)| {
+        Some(Strike {
+          bell: bell.trim().parse().ok()?,
+          time: time.trim().parse().ok()?,
+        })
+      })
+      .filter(|strike| (1..=bells_amount).contains(&(strike.bell as usize)));
+
+    match strike {
+      Some(strike) => strikes.push(strike),
+      None if index == 0 => {}
+      None => return Err(StrikingError::InvalidLine(line)),
+    }
+  }
+
+  Ok(strikes)
+}
+
+/// Compares strikes, taken in time order, with the rows expected, as far as
+/// both go. The first row is taken to be a handstroke.
+pub fn analyse(
+  strikes: &[Strike],
+  expected_rows: &[Vec<u8>],
+) -> Result<StrikingReport, StrikingError> {
+  let bells_amount = expected_rows.first().map_or(0, Vec::len);
+  let mut strikes = strikes.to_vec();
+  strikes.sort_by(|left, right| left.time.total_cmp(&right.time));
+
+  if bells_amount < 2 {
+    return Err(StrikingError::NoRows);
+  }
+  let rows = align_rows(&strikes, expected_rows);
+  // Rows with a bell missing or struck twice can't be fitted to places
+  let whole_rows: Vec<(usize, &[Strike])> = rows
+    .iter()
+    .enumerate()
+    .filter(|(_, row)| is_whole_row(row, bells_amount))
+    .map(|(row_index, row)| (row_index, *row))
+    .collect();
+  if whole_rows.is_empty() {
+    return Err(StrikingError::NoRows);
+  }
+
+  let bell_gap = whole_rows
+    .iter()
+    .map(|(_, row)| (row[bells_amount - 1].time - row[0].time) / (bells_amount - 1) as f64)
+    .sum::<f64>()
+    / whole_rows.len() as f64;
+
+  let mistakes = rows
+    .iter()
+    .zip(expected_rows)
+    .enumerate()
+    .filter_map(|(row_index, (row, expected))| {
+      let observed: Vec<u8> = row.iter().map(|strike| strike.bell).collect();
+      (observed != *expected).then(|| Mistake {
+        row_index,
+        expected: expected.clone(),
+        observed,
+      })
+    })
+    .collect();
+
+  let mut squared_errors = vec![(0.0, 0); bells_amount];
+  for (_, row) in whole_rows.iter() {
+    let start = row
+      .iter()
+      .enumerate()
+      .map(|(place, strike)| strike.time - place as f64 * bell_gap)
+      .sum::<f64>()
+      / bells_amount as f64;
+
+    for (place, strike) in row.iter().enumerate() {
+      let (total, count) = &mut squared_errors[strike.bell as usize - 1];
+      *total += (strike.time - start - place as f64 * bell_gap).powi(2);
+      *count += 1;
+    }
+  }
+  let bell_errors = squared_errors
+    .iter()
+    .map(|&(total, count)| {
+      if count == 0 {
+        0.0
+      } else {
+        (total / count as f64).sqrt()
+      }
+    })
+    .collect();
+
+  let (mut handstroke_gaps, mut backstroke_gaps) = (Vec::new(), Vec::new());
+  for pair in whole_rows.windows(2) {
+    let ((row_index, row), (next_index, next_row)) = (pair[0], pair[1]);
+    if next_index != row_index + 1 {
+      continue;
+    }
+
+    let gap = next_row[0].time - row[bells_amount - 1].time;
+    if row_index % 2 == 0 {
+      backstroke_gaps.push(gap);
+    } else {
+      handstroke_gaps.push(gap);
+    }
+  }
+
+  Ok(StrikingReport {
+    rows_amount: rows.len(),
+    bell_gap,
+    mistakes,
+    bell_errors,
+    handstroke_gaps: GapStatistics::new(&handstroke_gaps),
+    backstroke_gaps: GapStatistics::new(&backstroke_gaps),
+  })
+}
+
+/// Splits the strikes into one row for each expected row while a row's
+/// worth of strikes is left. Each row takes one strike more or fewer than
+/// the number of bells if that better matches the row expected and the row
+/// after it, so the rows after a dropped or extra strike are not shifted.
+fn align_rows<'a>(strikes: &'a [Strike], expected_rows: &[Vec<u8>]) -> Vec<&'a [Strike]> {
+  let bells_amount = expected_rows[0].len();
+  let mut rows = Vec::new();
+  let mut start = 0;
+
+  for (row_index, expected) in expected_rows.iter().enumerate() {
+    if strikes.len() - start < bells_amount {
+      break;
+    }
+
+    let next_expected = expected_rows.get(row_index + 1);
+    // Ties go to the number of bells, then to fewer strikes
+    let length = [bells_amount, bells_amount - 1, bells_amount + 1]
+      .into_iter()
+      .filter(|&length| start + length <= strikes.len())
+      .min_by_key(|&length| {
+        let next_start = start + length;
+        let next_cost = next_expected.map_or(0, |next_expected| {
+          let next_end = strikes.len().min(next_start + bells_amount);
+          let next_row = &strikes[next_start..next_end];
+          edit_distance(next_row, &next_expected[..next_row.len()])
+        });
+
+        edit_distance(&strikes[start..next_start], expected) + next_cost
+      })
+      .expect("A row's worth of strikes is left");
+
+    rows.push(&strikes[start..start + length]);
+    start += length;
+  }
+
+  rows
+}
+
+/// The fewest strikes added, removed or changed to ring `expected`.
+fn edit_distance(row: &[Strike], expected: &[u8]) -> usize {
+  let mut previous: Vec<usize> = (0..=expected.len()).collect();
+
+  for (index, strike) in row.iter().enumerate() {
+    let mut current = vec![index + 1; expected.len() + 1];
+    for (place, &bell) in expected.iter().enumerate() {
+      let changed = previous[place] + usize::from(strike.bell != bell);
+      current[place + 1] = changed.min(previous[place + 1] + 1).min(current[place] + 1);
+    }
+    previous = current;
+  }
+
+  previous[expected.len()]
+}
+
+/// Whether each bell strikes once in the row.
+fn is_whole_row(row: &[Strike], bells_amount: usize) -> bool {
+  let mut bells: Vec<u8> = row.iter().map(|strike| strike.bell).collect();
+  bells.sort_unstable();
+
+  bells.len() == bells_amount
+    && bells
+      .iter()
+      .enumerate()
+      .all(|(index, &bell)| bell as usize == index + 1)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::{
+    test_helpers::{assert_close, plain_course},
+    timing::Timing,
+  };
+
+  /// Strikes perfectly in time, 200ms apart with an open handstroke lead.
+  fn perfect_strikes(rows: &[Vec<u8>]) -> Vec<Strike> {
+    let timing = Timing::new().bell_gap(0.2);
+    rows
+      .iter()
+      .enumerate()
+      .flat_map(|(row_index, row)| {
+        row.iter().enumerate().map(move |(place, &bell)| {
+          Strike::new(
+            bell,
+            timing.get_strike_time(row_index, place, row.len()) * 1000.0,
+          )
+        })
+      })
+      .collect()
+  }
+
+  #[test]
+  fn can_read_strikes() {
+    let csv = "bell,time\n1,0\n2, 210.5\n\n3,400\n";
+
+    assert_eq!(
+      read_strikes(csv.as_bytes(), 3).unwrap(),
+      vec![
+        Strike::new(1, 0.0),
+        Strike::new(2, 210.5),
+        Strike::new(3, 400.0)
+      ]
+    );
+    assert!(matches!(
+      read_strikes("1,0\n2,late\n".as_bytes(), 3),
+      Err(StrikingError::InvalidLine(_))
+    ));
+  }
+
+  #[test]
+  fn rejects_bells_that_are_not_rung() {
+    for csv in ["1,0\n4,200\n", "1,0\n0,200\n"] {
+      assert!(matches!(
+        read_strikes(csv.as_bytes(), 3),
+        Err(StrikingError::InvalidLine(line)) if line == csv.lines().nth(1).unwrap()
+      ));
+    }
+  }
+
+  #[test]
+  fn finds_no_errors_in_perfect_striking() {
+    let rows = plain_course("x14x14,12", 4);
+    let report = analyse(&perfect_strikes(&rows), &rows).unwrap();
+
+    assert_eq!(report.get_rows_amount(), 24);
+    assert_close(report.get_bell_gap(), 200.0);
+    assert!(report.get_mistakes().is_empty());
+    assert!(report.get_bell_errors().iter().all(|&error| error < 1e-6));
+    assert_eq!(report.get_handstroke_gaps().get_count(), 11);
+    assert_close(report.get_handstroke_gaps().get_mean(), 400.0);
+    assert_close(report.get_backstroke_gaps().get_mean(), 200.0);
+    assert_close(report.get_backstroke_gaps().get_standard_deviation(), 0.0);
+  }
+
+  #[test]
+  fn measures_each_bells_error() {
+    let rows = plain_course("x14x14,12", 4);
+    let mut strikes = perfect_strikes(&rows);
+    for strike in strikes.iter_mut().filter(|strike| strike.bell == 3) {
+      strike.time += 40.0;
+    }
+
+    let report = analyse(&strikes, &rows).unwrap();
+    let errors = report.get_bell_errors();
+
+    assert!(errors[2] > 25.0);
+    assert!(errors[2] > errors[0] * 2.0);
+    assert!(report.get_mistakes().is_empty());
+  }
+
+  #[test]
+  fn finds_rows_rung_in_the_wrong_order() {
+    let rows = plain_course("x14x14,12", 4);
+    let mut strikes = perfect_strikes(&rows);
+    // The bells in 3rds and 4ths of row 5 swap
+    let (first, second) = (strikes[22].time, strikes[23].time);
+    strikes[22].time = second;
+    strikes[23].time = first;
+
+    let report = analyse(&strikes, &rows).unwrap();
+
+    assert_eq!(report.get_mistakes().len(), 1);
+    assert_eq!(report.get_mistakes()[0].get_row_index(), 5);
+    assert_eq!(report.get_mistakes()[0].get_expected(), rows[5]);
+    assert_eq!(
+      report.get_mistakes()[0].get_observed(),
+      [rows[5][0], rows[5][1], rows[5][3], rows[5][2]]
+    );
+  }
+
+  #[test]
+  fn resyncs_after_a_dropped_strike() {
+    let rows = plain_course("x14x14,12", 4);
+    let mut strikes = perfect_strikes(&rows);
+    // The second bell of row 5 never sounds
+    strikes.remove(21);
+
+    let report = analyse(&strikes, &rows).unwrap();
+
+    assert_eq!(report.get_rows_amount(), 24);
+    assert_eq!(report.get_mistakes().len(), 1);
+    assert_eq!(report.get_mistakes()[0].get_row_index(), 5);
+    assert_eq!(
+      report.get_mistakes()[0].get_observed(),
+      [rows[5][0], rows[5][2], rows[5][3]]
+    );
+    assert_close(report.get_bell_gap(), 200.0);
+    assert!(report.get_bell_errors().iter().all(|&error| error < 1e-6));
+  }
+
+  #[test]
+  fn resyncs_after_an_extra_strike() {
+    let rows = plain_course("x14x14,12", 4);
+    let mut strikes = perfect_strikes(&rows);
+    // The bell in 3rds of row 7 clips and sounds twice
+    let clipped = Strike::new(strikes[30].bell, strikes[30].time + 50.0);
+    strikes.insert(31, clipped);
+
+    let report = analyse(&strikes, &rows).unwrap();
+
+    assert_eq!(report.get_rows_amount(), 24);
+    assert_eq!(report.get_mistakes().len(), 1);
+    assert_eq!(report.get_mistakes()[0].get_row_index(), 7);
+    assert_eq!(report.get_mistakes()[0].get_observed().len(), 5);
+    assert!(report.get_bell_errors().iter().all(|&error| error < 1e-6));
+  }
+
+  #[test]
+  fn needs_a_whole_row() {
+    let rows = plain_course("x14x14,12", 4);
+
+    assert!(matches!(
+      analyse(&perfect_strikes(&rows)[..3], &rows),
+      Err(StrikingError::NoRows)
+    ));
+  }
+}
@@ -0,0 +1,15 @@
+//! Helpers shared by the unit tests of several modules.
+
+use crate::method::Method;
+
+/// The rows of a method's plain course, from rounds up to the row before it
+/// comes round.
+pub fn plain_course(notation: &str, bells_amount: usize) -> Vec<Vec<u8>> {
+  Method::from_place_notation(notation, bells_amount)
+    .unwrap()
+    .get_plain_course()
+}
+
+pub fn assert_close(left: f64, right: f64) {
+  assert!((left - right).abs() < 1e-9, "{left} is not {right}");
+}
@@ -0,0 +1,149 @@
+//! When each bell strikes, shared by the audio, the MIDI and the striking
+//! analysis.
+//!
+//! Bells strike at an even gap, with an extra gap before each handstroke
+//! row, the open handstroke lead. The gap is set directly or from a peal
+//! speed, the minutes taken to ring 5040 changes, which gives a longer gap
+//! the fewer bells there are.
+
+const EXTENT_CHANGES: f64 = 5040.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Speed {
+  BellGap(f64),
+  PealMinutes(f64),
+}
+
+/// The speed of the ringing and the gap left at handstroke.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+  speed: Speed,
+  handstroke_gap: f64,
+}
+
+impl Timing {
+  /// Three hour peal speed with an open handstroke lead.
+  pub fn new() -> Timing {
+    Timing {
+      speed: Speed::PealMinutes(180.0),
+      handstroke_gap: 1.0,
+    }
+  }
+
+  /// Seconds between one bell striking and the next, whatever the number of bells.
+  pub fn bell_gap(mut self, seconds: f64) -> Self {
+    if seconds <= 0.0 {
+      panic!("The gap between bells must be positive");
+    }
+
+    self.speed = Speed::BellGap(seconds);
+    self
+  }
+
+  /// Minutes taken to ring 5040 changes.
+  pub fn peal_speed(mut self, minutes: f64) -> Self {
+    if minutes <= 0.0 {
+      panic!("The peal speed must be positive");
+    }
+
+    self.speed = Speed::PealMinutes(minutes);
+    self
+  }
+
+  /// Extra gaps left before each handstroke row, 1 for an open handstroke lead.
+  pub fn handstroke_gap(mut self, gaps: f64) -> Self {
+    if gaps < 0.0 {
+      panic!("The handstroke gap can not be negative");
+    }
+
+    self.handstroke_gap = gaps;
+    self
+  }
+
+  pub fn get_handstroke_gap(&self) -> f64 {
+    self.handstroke_gap
+  }
+
+  /// Seconds between one bell striking and the next.
+  pub fn get_bell_gap(&self, bells_amount: usize) -> f64 {
+    match self.speed {
+      Speed::BellGap(seconds) => seconds,
+      Speed::PealMinutes(minutes) => {
+        let gaps_per_whole_pull = 2.0 * bells_amount as f64 + self.handstroke_gap;
+        minutes * 60.0 / (EXTENT_CHANGES / 2.0 * gaps_per_whole_pull)
+      }
+    }
+  }
+
+  /// Seconds from the start until the bell in `place`, from 0, strikes in a row.
+  /// Row 0 is the first handstroke.
+  pub fn get_strike_time(&self, row_index: usize, place: usize, bells_amount: usize) -> f64 {
+    let gaps =
+      (row_index * bells_amount + place) as f64 + (row_index / 2) as f64 * self.handstroke_gap;
+    gaps * self.get_bell_gap(bells_amount)
+  }
+}
+
+impl Default for Timing {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  use crate::test_helpers::assert_close;
+
+  #[test]
+  fn leaves_a_gap_before_each_handstroke() {
+    let timing = Timing::new().bell_gap(0.5);
+    let closed = timing.handstroke_gap(0.0);
+
+    assert_close(timing.get_strike_time(0, 0, 4), 0.0);
+    assert_close(timing.get_strike_time(0, 3, 4), 1.5);
+    assert_close(timing.get_strike_time(1, 0, 4), 2.0);
+    assert_close(timing.get_strike_time(2, 0, 4), 4.5);
+    assert_close(closed.get_strike_time(2, 0, 4), 4.0);
+  }
+
+  #[test]
+  fn times_bells_by_the_peal_speed() {
+    // 2520 whole pulls of 13 gaps on six bells
+    let timing = Timing::new().peal_speed(2520.0 * 13.0 / 60.0);
+    let closed = Timing::new()
+      .peal_speed(2520.0 * 12.0 / 60.0)
+      .handstroke_gap(0.0);
+
+    assert_close(timing.get_bell_gap(6), 1.0);
+    assert_close(timing.get_strike_time(1, 2, 6), 8.0);
+    assert_close(timing.get_strike_time(2, 0, 6), 13.0);
+    assert_close(closed.get_strike_time(2, 0, 6), 12.0);
+  }
+
+  #[test]
+  fn rings_at_three_hour_peal_speed_by_default() {
+    // 2520 whole pulls of 17 gaps on eight bells in 180 minutes
+    assert_close(Timing::new().get_bell_gap(8), 10_800.0 / (2520.0 * 17.0));
+    assert_eq!(Timing::default(), Timing::new());
+  }
+
+  #[test]
+  #[should_panic(expected = "The gap between bells must be positive")]
+  fn panics_without_a_gap_between_bells() {
+    Timing::new().bell_gap(0.0);
+  }
+
+  #[test]
+  #[should_panic(expected = "The peal speed must be positive")]
+  fn panics_without_a_peal_speed() {
+    Timing::new().peal_speed(0.0);
+  }
+
+  #[test]
+  #[should_panic(expected = "The handstroke gap can not be negative")]
+  fn panics_with_a_negative_handstroke_gap() {
+    Timing::new().handstroke_gap(-1.0);
+  }
+}